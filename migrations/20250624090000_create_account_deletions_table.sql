@@ -0,0 +1,19 @@
+-- Accounts scheduled for deletion; signing in before purge_after cancels it
+CREATE TABLE account_deletions (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    requested_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    purge_after TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX idx_account_deletions_purge_after ON account_deletions(purge_after);
+
+-- Deleting an account keeps the materials it created, without an author,
+-- so courses do not lose content. Memberships go with the account.
+ALTER TABLE materials ALTER COLUMN creator DROP NOT NULL;
+ALTER TABLE materials
+    DROP CONSTRAINT materials_creator_fkey,
+    ADD CONSTRAINT materials_creator_fkey FOREIGN KEY (creator) REFERENCES users(id) ON DELETE SET NULL;
+
+ALTER TABLE group_members
+    DROP CONSTRAINT group_members_user_id_fkey,
+    ADD CONSTRAINT group_members_user_id_fkey FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
@@ -0,0 +1,11 @@
+-- Deleting a group removes its courses, and deleting a label removes it
+-- from the materials it was attached to
+ALTER TABLE courses
+    DROP CONSTRAINT courses_group_name_fkey,
+    ADD CONSTRAINT courses_group_name_fkey
+        FOREIGN KEY (group_name) REFERENCES groups(name) ON DELETE CASCADE;
+
+ALTER TABLE material_labels
+    DROP CONSTRAINT material_labels_group_name_label_name_fkey,
+    ADD CONSTRAINT material_labels_group_name_label_name_fkey
+        FOREIGN KEY (group_name, label_name) REFERENCES group_labels(group_name, name) ON DELETE CASCADE;
@@ -0,0 +1,9 @@
+-- Ownership handovers waiting for the nominated member to accept; one per group
+CREATE TABLE group_ownership_transfers (
+    group_name VARCHAR(255) PRIMARY KEY REFERENCES groups(name) ON DELETE CASCADE,
+    from_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    to_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_group_ownership_transfers_to_user_id ON group_ownership_transfers(to_user_id);
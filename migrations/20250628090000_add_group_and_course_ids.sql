@@ -0,0 +1,120 @@
+-- Key groups and courses by UUID so they can be renamed. Names stay unique
+-- (courses within their group) and slugs give each one a stable URL; they
+-- start out as the slugified name, the same rules as StringUtils::slugify.
+
+ALTER TABLE groups
+    ADD COLUMN id UUID NOT NULL DEFAULT gen_random_uuid(),
+    ADD COLUMN slug VARCHAR(255);
+
+ALTER TABLE courses
+    ADD COLUMN id UUID NOT NULL DEFAULT gen_random_uuid(),
+    ADD COLUMN group_id UUID,
+    ADD COLUMN slug VARCHAR(255);
+
+UPDATE courses c SET group_id = g.id FROM groups g WHERE g.name = c.group_name;
+
+-- Names that slugify to the same thing keep the oldest one as is and get
+-- part of their id appended
+UPDATE groups g
+SET slug = CASE WHEN s.n = 1 THEN s.base ELSE s.base || '-' || left(g.id::text, 8) END
+FROM (
+    SELECT name, base, ROW_NUMBER() OVER (PARTITION BY base ORDER BY created_at, name) AS n
+    FROM (
+        SELECT name, created_at,
+               COALESCE(NULLIF(trim(BOTH '-' FROM regexp_replace(lower(name), '[^[:alnum:]]+', '-', 'g')), ''), 'group') AS base
+        FROM groups
+    ) names
+) s
+WHERE s.name = g.name;
+
+UPDATE courses c
+SET slug = CASE WHEN s.n = 1 THEN s.base ELSE s.base || '-' || left(c.id::text, 8) END
+FROM (
+    SELECT id, base, ROW_NUMBER() OVER (PARTITION BY group_id, base ORDER BY created_at, name) AS n
+    FROM (
+        SELECT id, group_id, name, created_at,
+               COALESCE(NULLIF(trim(BOTH '-' FROM regexp_replace(lower(name), '[^[:alnum:]]+', '-', 'g')), ''), 'course') AS base
+        FROM courses
+    ) names
+) s
+WHERE s.id = c.id;
+
+-- Point everything that referred to a group or course by name at its id
+ALTER TABLE group_members ADD COLUMN group_id UUID;
+ALTER TABLE join_requests ADD COLUMN group_id UUID;
+ALTER TABLE group_labels ADD COLUMN group_id UUID;
+ALTER TABLE material_labels ADD COLUMN group_id UUID;
+ALTER TABLE group_ownership_transfers ADD COLUMN group_id UUID;
+ALTER TABLE materials ADD COLUMN course_id UUID;
+
+UPDATE group_members t SET group_id = g.id FROM groups g WHERE g.name = t.group_name;
+UPDATE join_requests t SET group_id = g.id FROM groups g WHERE g.name = t.group_name;
+UPDATE group_labels t SET group_id = g.id FROM groups g WHERE g.name = t.group_name;
+UPDATE material_labels t SET group_id = g.id FROM groups g WHERE g.name = t.group_name;
+UPDATE group_ownership_transfers t SET group_id = g.id FROM groups g WHERE g.name = t.group_name;
+UPDATE materials m SET course_id = c.id
+FROM courses c
+WHERE c.group_name = m.group_name AND c.name = m.course_name;
+
+-- Dropping the name columns drops the keys built on them
+ALTER TABLE materials DROP COLUMN group_name, DROP COLUMN course_name;
+ALTER TABLE material_labels DROP COLUMN group_name;
+ALTER TABLE group_labels DROP COLUMN group_name;
+ALTER TABLE group_members DROP COLUMN group_name;
+ALTER TABLE join_requests DROP COLUMN group_name;
+ALTER TABLE group_ownership_transfers DROP COLUMN group_name;
+ALTER TABLE courses DROP COLUMN group_name;
+
+ALTER TABLE groups
+    DROP CONSTRAINT groups_pkey,
+    ADD PRIMARY KEY (id),
+    ADD CONSTRAINT groups_name_key UNIQUE (name),
+    ALTER COLUMN slug SET NOT NULL,
+    ADD CONSTRAINT groups_slug_key UNIQUE (slug);
+
+ALTER TABLE courses
+    ALTER COLUMN group_id SET NOT NULL,
+    ALTER COLUMN slug SET NOT NULL,
+    ADD PRIMARY KEY (id),
+    ADD CONSTRAINT courses_group_id_fkey
+        FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE CASCADE,
+    ADD CONSTRAINT courses_group_id_name_key UNIQUE (group_id, name),
+    ADD CONSTRAINT courses_group_id_slug_key UNIQUE (group_id, slug);
+
+ALTER TABLE group_members
+    ALTER COLUMN group_id SET NOT NULL,
+    ADD PRIMARY KEY (user_id, group_id),
+    ADD CONSTRAINT group_members_group_id_fkey
+        FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE CASCADE;
+
+ALTER TABLE join_requests
+    ALTER COLUMN group_id SET NOT NULL,
+    ADD PRIMARY KEY (group_id, user_id),
+    ADD CONSTRAINT join_requests_group_id_fkey
+        FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE CASCADE;
+
+ALTER TABLE group_labels
+    ALTER COLUMN group_id SET NOT NULL,
+    ADD PRIMARY KEY (group_id, name),
+    ADD CONSTRAINT group_labels_group_id_fkey
+        FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE CASCADE;
+
+ALTER TABLE material_labels
+    ALTER COLUMN group_id SET NOT NULL,
+    ADD PRIMARY KEY (material_id, group_id, label_name),
+    ADD CONSTRAINT material_labels_group_id_label_name_fkey
+        FOREIGN KEY (group_id, label_name) REFERENCES group_labels(group_id, name) ON DELETE CASCADE;
+
+ALTER TABLE group_ownership_transfers
+    ALTER COLUMN group_id SET NOT NULL,
+    ADD PRIMARY KEY (group_id),
+    ADD CONSTRAINT group_ownership_transfers_group_id_fkey
+        FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE CASCADE;
+
+ALTER TABLE materials
+    ALTER COLUMN course_id SET NOT NULL,
+    ADD CONSTRAINT materials_course_id_fkey
+        FOREIGN KEY (course_id) REFERENCES courses(id) ON DELETE CASCADE;
+
+CREATE INDEX idx_group_members_group_id ON group_members(group_id);
+CREATE INDEX idx_materials_course_id ON materials(course_id);
@@ -0,0 +1,4 @@
+-- Cap how many logins one address can have in flight at once
+ALTER TABLE oidc_login_states ADD COLUMN ip_address VARCHAR(45) NULL;
+
+CREATE INDEX idx_oidc_login_states_ip_address ON oidc_login_states(ip_address);
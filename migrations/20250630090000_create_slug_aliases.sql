@@ -0,0 +1,29 @@
+-- Slugs a group or course had before it was given a new one, so old links
+-- keep resolving to it
+CREATE TABLE group_slug_aliases (
+    slug VARCHAR(255) PRIMARY KEY,
+    group_id UUID NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE TABLE course_slug_aliases (
+    group_id UUID NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+    slug VARCHAR(255) NOT NULL,
+    course_id UUID NOT NULL REFERENCES courses(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    PRIMARY KEY (group_id, slug)
+);
+
+CREATE INDEX idx_group_slug_aliases_group_id ON group_slug_aliases(group_id);
+CREATE INDEX idx_course_slug_aliases_course_id ON course_slug_aliases(course_id);
+
+-- Names that slugified to something shaped like a UUID were taken as is
+-- when slugs were introduced, and could shadow another group's id. Give
+-- them the fallback slug_base uses instead.
+UPDATE groups
+SET slug = 'group-' || left(id::text, 8)
+WHERE slug ~ '^([0-9a-f]{32}|[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12})$';
+
+UPDATE courses
+SET slug = 'course-' || left(id::text, 8)
+WHERE slug ~ '^([0-9a-f]{32}|[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12})$';
@@ -0,0 +1,11 @@
+-- Invitations to join a group, waiting for the invited user to accept; the
+-- only way into a CLOSED group
+CREATE TABLE group_invitations (
+    group_id UUID NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    invited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (group_id, user_id)
+);
+
+CREATE INDEX idx_group_invitations_user_id ON group_invitations(user_id);
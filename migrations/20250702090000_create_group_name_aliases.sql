@@ -0,0 +1,20 @@
+-- Names a group has gone by, so links made from a name before slugs existed,
+-- or before a rename, keep resolving to it. The database slugifies ASCII
+-- only, so exact names are the only reliable way back for the rest.
+CREATE TABLE group_name_aliases (
+    name VARCHAR(255) PRIMARY KEY,
+    group_id UUID NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_group_name_aliases_group_id ON group_name_aliases(group_id);
+
+-- A slug equal to another group's name took over that group's old
+-- name-based URL ("Math" got `math` while "math" was the name of another
+-- group). Give the URL back to the name.
+UPDATE groups g
+SET slug = g.slug || '-' || left(g.id::text, 8)
+WHERE EXISTS (SELECT 1 FROM groups other WHERE other.name = g.slug AND other.id <> g.id);
+
+INSERT INTO group_name_aliases (name, group_id)
+SELECT name, id FROM groups;
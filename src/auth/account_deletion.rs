@@ -0,0 +1,133 @@
+// src/auth/account_deletion.rs
+//! Self-service account deletion.
+//!
+//! A deletion request signs the user out everywhere, deletes their personal
+//! access tokens so scripts stop acting for them, and schedules the purge
+//! after a grace period (`ACCOUNT_DELETION_GRACE_DAYS`, default 30). Signing
+//! in again before then cancels it.
+//!
+//! When the account is purged, groups it owns are handed to an admin or the
+//! longest-standing member, and groups nobody else is in are deleted.
+//! Materials and comments it wrote stay in their groups without an author.
+
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    auth::{oidc::purge_expired_login_states, revocation::revoke_all_tokens},
+    errors::AppError,
+    mail::{self, MailMessage},
+    models::{
+        account_deletion::AccountDeletion,
+        group::Group,
+        group_member::GroupMember,
+        personal_access_token::PersonalAccessToken,
+        user::User,
+    },
+};
+
+pub const DEFAULT_ACCOUNT_DELETION_GRACE_DAYS: i64 = 30;
+const PURGE_INTERVAL_SECONDS: u64 = 60 * 60;
+
+pub fn account_deletion_grace_days() -> i64 {
+    std::env::var("ACCOUNT_DELETION_GRACE_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|days| *days >= 0)
+        .unwrap_or(DEFAULT_ACCOUNT_DELETION_GRACE_DAYS)
+}
+
+/// Sign the user out everywhere, delete their access tokens and schedule the
+/// purge of their account
+pub async fn schedule_account_deletion(pool: &Pool<Postgres>, user: &User) -> Result<AccountDeletion, AppError> {
+    let purge_after = Utc::now() + Duration::days(account_deletion_grace_days());
+    let deletion = AccountDeletion::schedule(pool, user.id, purge_after).await?;
+    revoke_all_tokens(pool, user.id).await?;
+    PersonalAccessToken::delete_all_for_user(pool, user.id).await?;
+
+    // The request has been recorded; a lost notice should not undo it
+    if let Err(e) = send_deletion_notice(user, deletion.purge_after).await {
+        tracing::error!(user_id = %user.id, "failed to send account deletion notice: {}", e);
+    }
+
+    tracing::info!(user_id = %user.id, purge_after = %deletion.purge_after, "account deletion scheduled");
+    Ok(deletion)
+}
+
+/// Cancel a pending deletion, returns `false` if none was scheduled
+pub async fn cancel_account_deletion(pool: &Pool<Postgres>, user_id: Uuid) -> Result<bool, AppError> {
+    let cancelled = AccountDeletion::cancel(pool, user_id).await?;
+    if cancelled {
+        tracing::info!(user_id = %user_id, "account deletion cancelled by sign-in");
+    }
+    Ok(cancelled)
+}
+
+async fn send_deletion_notice(user: &User, purge_after: DateTime<Utc>) -> anyhow::Result<()> {
+    let message = MailMessage {
+        to: user.email.clone(),
+        subject: "Your StudySphere account will be deleted".to_string(),
+        body: format!(
+            "Hi {},\n\nWe received a request to delete your StudySphere account. It will be deleted permanently on {}.\n\nIf you change your mind, sign in before then at {} and the deletion will be cancelled.",
+            user.username,
+            purge_after.format("%Y-%m-%d %H:%M UTC"),
+            mail::frontend_url()
+        ),
+    };
+
+    mail::mailer_from_env()?.send(message).await
+}
+
+/// Delete the accounts whose grace period is over. Returns how many were
+/// purged; a failure on one account is logged and does not stop the others.
+pub async fn purge_due_accounts(pool: &Pool<Postgres>) -> Result<usize, AppError> {
+    let mut purged = 0;
+    for deletion in AccountDeletion::list_due(pool).await? {
+        match purge_account(pool, deletion.user_id).await {
+            Ok(()) => purged += 1,
+            Err(e) => tracing::error!(user_id = %deletion.user_id, "failed to purge account: {}", e),
+        }
+    }
+    Ok(purged)
+}
+
+async fn purge_account(pool: &Pool<Postgres>, user_id: Uuid) -> Result<(), AppError> {
+    for group in Group::find_by_owner(pool, user_id).await? {
+        match GroupMember::find_successor(pool, group.id, user_id).await? {
+            Some(successor) => {
+                Group::transfer_ownership(pool, group.id, successor).await?;
+                tracing::info!(group = %group.name, new_owner = %successor, "group ownership transferred");
+            }
+            None => {
+                Group::delete(pool, group.id).await?;
+                tracing::info!(group = %group.name, "group without members deleted");
+            }
+        }
+    }
+
+    User::delete(pool, user_id).await?;
+    tracing::info!(user_id = %user_id, "account purged");
+    Ok(())
+}
+
+/// Purge due accounts in the background every hour, along with OIDC login
+/// states nobody came back for
+pub fn spawn_account_purger(pool: Pool<Postgres>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(StdDuration::from_secs(PURGE_INTERVAL_SECONDS));
+        loop {
+            interval.tick().await;
+            match purge_due_accounts(&pool).await {
+                Ok(0) => {}
+                Ok(purged) => tracing::info!(purged, "purged deleted accounts"),
+                Err(e) => tracing::error!("account purge failed: {}", e),
+            }
+            if let Err(e) = purge_expired_login_states(&pool).await {
+                tracing::error!("OIDC login state purge failed: {}", e);
+            }
+        }
+    })
+}
@@ -0,0 +1,215 @@
+// src/auth/admin.rs
+//! Site administration.
+//!
+//! Actions site admins take on other users' accounts and groups. Each one
+//! runs in the caller's transaction, together with its entry in
+//! `admin_audit_log` naming the acting admin, the target and the client
+//! address, so no action goes unrecorded.
+//!
+//! For support, an admin can impersonate a user with a short-lived access
+//! token naming both of them. Impersonated requests are read-only unless the
+//! admin asks for writes, never reach the user's account or the admin API,
+//! and each one is audited by the auth middleware.
+
+use chrono::Utc;
+use sqlx::{Pool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        password_reset::send_password_reset_email,
+        provisioning::unusable_password_hash,
+        revocation::revoke_all_tokens_in,
+    },
+    errors::AppError,
+    models::{
+        admin_audit_log::{AdminAuditEntry, NewAdminAuditEntry},
+        group::Group,
+        personal_access_token::PersonalAccessToken,
+        user::User,
+    },
+    permissions::PlatformRole,
+    utils::shared_jwt_manager,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdminAction {
+    DisableUser,
+    EnableUser,
+    ForcePasswordReset,
+    ChangeRole,
+    DeleteGroup,
+    TransferGroupOwnership,
+    Impersonate,
+    ImpersonatedRequest,
+}
+
+impl AdminAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminAction::DisableUser => "disable_user",
+            AdminAction::EnableUser => "enable_user",
+            AdminAction::ForcePasswordReset => "force_password_reset",
+            AdminAction::ChangeRole => "change_role",
+            AdminAction::DeleteGroup => "delete_group",
+            AdminAction::TransferGroupOwnership => "transfer_group_ownership",
+            AdminAction::Impersonate => "impersonate",
+            AdminAction::ImpersonatedRequest => "impersonated_request",
+        }
+    }
+}
+
+/// What an audited action was taken on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditTarget {
+    User(Uuid),
+    Group(Uuid),
+}
+
+impl AuditTarget {
+    fn type_and_id(&self) -> (&'static str, String) {
+        match self {
+            AuditTarget::User(id) => ("user", id.to_string()),
+            AuditTarget::Group(id) => ("group", id.to_string()),
+        }
+    }
+}
+
+/// Append an entry to the audit trail. `actor_id` is `None` for changes made
+/// from the command line.
+pub async fn record_admin_action(
+    executor: impl sqlx::PgExecutor<'_>,
+    actor_id: Option<Uuid>,
+    action: AdminAction,
+    target: AuditTarget,
+    details: Option<serde_json::Value>,
+    ip_address: Option<String>,
+) -> Result<AdminAuditEntry, AppError> {
+    let (target_type, target_id) = target.type_and_id();
+    let entry = AdminAuditEntry::create(executor, NewAdminAuditEntry {
+        actor_id,
+        action: action.as_str().to_string(),
+        target_type: target_type.to_string(),
+        target_id,
+        details,
+        ip_address,
+    }).await?;
+
+    tracing::info!(actor_id = ?actor_id, action = action.as_str(), target = %entry.target_id, "admin action");
+    Ok(entry)
+}
+
+/// Reject users without the site admin role
+pub async fn require_site_admin(pool: &Pool<Postgres>, user_id: Uuid) -> Result<User, AppError> {
+    let user = User::find_by_id(pool, user_id)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    if !user.is_admin() {
+        return Err(AppError::PermissionDenied("Site administrator role required".to_string()));
+    }
+    Ok(user)
+}
+
+/// Admins cannot lock themselves out through the admin API
+fn ensure_not_self(admin_id: Uuid, target_id: Uuid) -> Result<(), AppError> {
+    if admin_id == target_id {
+        return Err(AppError::ValidationError("Admins cannot do this to their own account".to_string()));
+    }
+    Ok(())
+}
+
+/// Block sign-in and end every session and API token of the user
+pub async fn disable_user(
+    tx: &mut Transaction<'_, Postgres>,
+    admin_id: Uuid,
+    user_id: Uuid,
+) -> Result<User, AppError> {
+    ensure_not_self(admin_id, user_id)?;
+
+    User::set_disabled_at(&mut **tx, user_id, Some(Utc::now())).await?;
+    PersonalAccessToken::delete_all_for_user(&mut **tx, user_id).await?;
+    let user = revoke_all_tokens_in(tx, user_id).await?;
+    Ok(user)
+}
+
+pub async fn enable_user(tx: &mut Transaction<'_, Postgres>, user_id: Uuid) -> Result<User, AppError> {
+    let user = User::set_disabled_at(&mut **tx, user_id, None).await?;
+    Ok(user)
+}
+
+/// Replace the password with one nobody knows and sign the user out
+/// everywhere. Send the reset link with [`send_forced_reset_email`] once
+/// this is committed.
+pub async fn force_password_reset(
+    tx: &mut Transaction<'_, Postgres>,
+    admin_id: Uuid,
+    user: &User,
+) -> Result<(), AppError> {
+    ensure_not_self(admin_id, user.id)?;
+
+    User::update_password(&mut **tx, user.id, unusable_password_hash()?).await?;
+    PersonalAccessToken::delete_all_for_user(&mut **tx, user.id).await?;
+    revoke_all_tokens_in(tx, user.id).await?;
+    Ok(())
+}
+
+/// Mail the user a reset link after a forced reset. A failed email is only
+/// logged, the account is locked either way.
+pub async fn send_forced_reset_email(pool: &Pool<Postgres>, user: &User) {
+    if let Err(e) = send_password_reset_email(pool, user).await {
+        tracing::error!(user_id = %user.id, "failed to send password reset email: {}", e);
+    }
+}
+
+pub async fn change_role(
+    tx: &mut Transaction<'_, Postgres>,
+    admin_id: Uuid,
+    user_id: Uuid,
+    role: PlatformRole,
+) -> Result<User, AppError> {
+    ensure_not_self(admin_id, user_id)?;
+
+    let user = User::update_role(&mut **tx, user_id, role.as_str().to_string()).await?;
+    Ok(user)
+}
+
+/// Force `new_owner_id` to become the owner of the group, whether or not
+/// they are a member or the owner agrees
+pub async fn transfer_group_ownership(
+    tx: &mut Transaction<'_, Postgres>,
+    group_id: Uuid,
+    new_owner_id: Uuid,
+) -> Result<Group, AppError> {
+    User::find_by_id(&mut **tx, new_owner_id)
+        .await?
+        .ok_or(AppError::ValidationError("New owner does not exist".to_string()))?;
+
+    let group = Group::transfer_ownership(&mut **tx, group_id, new_owner_id).await?;
+    Ok(group)
+}
+
+/// Delete the group with everything in it
+pub async fn delete_group(tx: &mut Transaction<'_, Postgres>, group_id: Uuid) -> Result<(), AppError> {
+    Group::delete(&mut **tx, group_id).await?;
+    Ok(())
+}
+
+/// Mint an impersonation access token for `user`. Other admins and disabled
+/// accounts cannot be impersonated.
+pub fn impersonation_token(admin_id: Uuid, user: &User, allow_writes: bool) -> Result<String, AppError> {
+    ensure_not_self(admin_id, user.id)?;
+    if user.is_admin() {
+        return Err(AppError::PermissionDenied("Site admins cannot be impersonated".to_string()));
+    }
+    if user.is_disabled() {
+        return Err(AppError::ValidationError("Disabled accounts cannot be impersonated".to_string()));
+    }
+
+    let jwt_manager = shared_jwt_manager()
+        .map_err(|e| AppError::ValidationError(format!("JWT keys not configured: {}", e)))?;
+    let token = jwt_manager
+        .generate_impersonation_token(user.id, user.token_version, admin_id, allow_writes)
+        .map_err(|e| AppError::ValidationError(format!("Token generation failed: {}", e)))?;
+    Ok(token)
+}
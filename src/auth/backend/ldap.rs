@@ -0,0 +1,214 @@
+// src/auth/backend/ldap.rs
+//! LDAP simple bind.
+//!
+//! The login is substituted into `LDAP_USER_DN_TEMPLATE` and the password is
+//! checked by binding as that DN. On success the entry's email and username
+//! attributes are read and the user is provisioned like any other external
+//! identity, with the directory URL as issuer and the entry DN as subject.
+//! Users can often edit their own `mail`, so the address is only trusted for
+//! linking to an existing account with `LDAP_TRUST_EMAIL=true`.
+//!
+//! For local testing:
+//!
+//! ```text
+//! docker run -p 389:389 -e LDAP_DOMAIN=example.org -e LDAP_ADMIN_PASSWORD=admin osixia/openldap
+//! LDAP_URL=ldap://localhost:389
+//! LDAP_USER_DN_TEMPLATE=uid={username},ou=people,dc=example,dc=org
+//! ```
+
+use std::{collections::HashMap, time::Duration};
+
+use async_trait::async_trait;
+use ldap3::{dn_escape, LdapConnAsync, LdapConnSettings, Scope, SearchEntry};
+use sqlx::{Pool, Postgres};
+
+use super::AuthBackend;
+use crate::{
+    auth::provisioning::{provision_external_user, ExternalIdentity},
+    errors::AppError,
+    models::user::User,
+};
+
+const CONNECT_TIMEOUT_SECONDS: u64 = 10;
+const USERNAME_PLACEHOLDER: &str = "{username}";
+/// Result code for a wrong DN or password (RFC 4511)
+const INVALID_CREDENTIALS: u32 = 49;
+
+#[derive(Debug, Clone)]
+pub struct LdapConfig {
+    pub url: String,
+    pub user_dn_template: String,
+    pub email_attribute: String,
+    pub username_attribute: String,
+    pub starttls: bool,
+    /// Whether the directory vouches for the email attribute
+    pub trust_email: bool,
+}
+
+impl LdapConfig {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let url = std::env::var("LDAP_URL")
+            .map_err(|_| anyhow::anyhow!("LDAP_URL environment variable not set"))?;
+        let user_dn_template = std::env::var("LDAP_USER_DN_TEMPLATE")
+            .map_err(|_| anyhow::anyhow!("LDAP_USER_DN_TEMPLATE environment variable not set"))?;
+        if !user_dn_template.contains(USERNAME_PLACEHOLDER) {
+            return Err(anyhow::anyhow!("LDAP_USER_DN_TEMPLATE must contain {}", USERNAME_PLACEHOLDER));
+        }
+
+        Ok(Self {
+            url,
+            user_dn_template,
+            email_attribute: std::env::var("LDAP_EMAIL_ATTRIBUTE").unwrap_or_else(|_| "mail".to_string()),
+            username_attribute: std::env::var("LDAP_USERNAME_ATTRIBUTE").unwrap_or_else(|_| "uid".to_string()),
+            starttls: std::env::var("LDAP_STARTTLS").is_ok_and(|v| v == "true" || v == "1"),
+            trust_email: std::env::var("LDAP_TRUST_EMAIL").is_ok_and(|v| v == "true" || v == "1"),
+        })
+    }
+
+    /// The DN to bind as, with the login escaped so it cannot add RDNs
+    fn user_dn(&self, login: &str) -> String {
+        self.user_dn_template.replace(USERNAME_PLACEHOLDER, &dn_escape(login.trim()))
+    }
+}
+
+/// Directory accounts, signed in by the name the DN template expects
+#[derive(Debug, Clone)]
+pub struct LdapBackend {
+    config: LdapConfig,
+}
+
+impl LdapBackend {
+    pub fn new(config: LdapConfig) -> Self {
+        Self { config }
+    }
+
+    /// Bind as the user and read their entry, `None` on a wrong password
+    async fn bind_and_fetch(&self, dn: &str, password: &str) -> Result<Option<SearchEntry>, ldap3::LdapError> {
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECONDS))
+            .set_starttls(self.config.starttls);
+        let (conn, mut ldap) = LdapConnAsync::with_settings(settings, &self.config.url).await?;
+        ldap3::drive!(conn);
+
+        let bind = ldap.simple_bind(dn, password).await?;
+        if bind.rc == INVALID_CREDENTIALS {
+            let _ = ldap.unbind().await;
+            return Ok(None);
+        }
+        bind.success()?;
+
+        let attributes = [self.config.email_attribute.as_str(), self.config.username_attribute.as_str()];
+        let (entries, _) = ldap
+            .search(dn, Scope::Base, "(objectClass=*)", attributes.to_vec())
+            .await?
+            .success()?;
+        let _ = ldap.unbind().await;
+
+        Ok(entries.into_iter().next().map(SearchEntry::construct))
+    }
+
+    fn identity(&self, entry: &SearchEntry) -> ExternalIdentity {
+        ExternalIdentity {
+            issuer: self.config.url.clone(),
+            subject: entry.dn.clone(),
+            email: first_attribute(&entry.attrs, &self.config.email_attribute),
+            email_verified: self.config.trust_email,
+            preferred_username: first_attribute(&entry.attrs, &self.config.username_attribute),
+        }
+    }
+}
+
+#[async_trait]
+impl AuthBackend for LdapBackend {
+    fn name(&self) -> &'static str {
+        "ldap"
+    }
+
+    async fn authenticate(
+        &self,
+        pool: &Pool<Postgres>,
+        login: &str,
+        password: &str,
+    ) -> Result<Option<User>, AppError> {
+        // An empty password would be an unauthenticated bind, which
+        // directories accept for any DN
+        if login.trim().is_empty() || password.is_empty() {
+            return Ok(None);
+        }
+
+        let dn = self.config.user_dn(login);
+        let entry = match self.bind_and_fetch(&dn, password).await {
+            Ok(Some(entry)) => entry,
+            Ok(None) => return Ok(None),
+            Err(e) => {
+                tracing::error!(url = %self.config.url, "LDAP authentication failed: {}", e);
+                return Err(AppError::Anyhow(anyhow::anyhow!("Directory service unavailable")));
+            }
+        };
+
+        let user = provision_external_user(pool, self.identity(&entry)).await?;
+
+        Ok(Some(user))
+    }
+}
+
+/// Attribute names are case-insensitive, servers return them as stored
+fn first_attribute(attrs: &HashMap<String, Vec<String>>, name: &str) -> Option<String> {
+    attrs
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .and_then(|(_, values)| values.first().cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> LdapConfig {
+        LdapConfig {
+            url: "ldap://localhost:389".to_string(),
+            user_dn_template: "uid={username},ou=people,dc=example,dc=org".to_string(),
+            email_attribute: "mail".to_string(),
+            username_attribute: "uid".to_string(),
+            starttls: false,
+            trust_email: false,
+        }
+    }
+
+    #[test]
+    fn test_user_dn_from_template() {
+        assert_eq!(config().user_dn(" jdoe "), "uid=jdoe,ou=people,dc=example,dc=org");
+    }
+
+    #[test]
+    fn test_user_dn_escapes_login() {
+        let dn = config().user_dn("jdoe,ou=admins");
+        assert_eq!(dn, "uid=jdoe\\2cou\\3dadmins,ou=people,dc=example,dc=org");
+    }
+
+    #[test]
+    fn test_first_attribute_ignores_case() {
+        let attrs = HashMap::from([("Mail".to_string(), vec!["jdoe@example.org".to_string()])]);
+        assert_eq!(first_attribute(&attrs, "mail").as_deref(), Some("jdoe@example.org"));
+        assert_eq!(first_attribute(&attrs, "uid"), None);
+    }
+
+    #[test]
+    fn test_identity_trusts_email_only_when_configured() {
+        let entry = SearchEntry {
+            dn: "uid=jdoe,ou=people,dc=example,dc=org".to_string(),
+            attrs: HashMap::from([
+                ("mail".to_string(), vec!["admin@example.org".to_string()]),
+                ("uid".to_string(), vec!["jdoe".to_string()]),
+            ]),
+            bin_attrs: HashMap::new(),
+        };
+
+        let identity = LdapBackend::new(config()).identity(&entry);
+        assert_eq!(identity.email.as_deref(), Some("admin@example.org"));
+        assert!(!identity.email_verified);
+
+        let trusted = LdapBackend::new(LdapConfig { trust_email: true, ..config() }).identity(&entry);
+        assert!(trusted.email_verified);
+    }
+}
@@ -0,0 +1,83 @@
+// src/auth/backend/local.rs
+use std::sync::LazyLock;
+
+use async_trait::async_trait;
+use sqlx::{Pool, Postgres};
+
+use super::AuthBackend;
+use crate::{
+    errors::AppError,
+    models::user::User,
+    utils::{EmailUtils, PasswordUtils},
+};
+
+/// Verified against when the email is unknown so the response time does not
+/// reveal whether an account exists
+static DUMMY_PASSWORD_HASH: LazyLock<String> = LazyLock::new(|| {
+    PasswordUtils::hash_password("dummy password for timing").unwrap_or_default()
+});
+
+/// Accounts with a password hash in `users`, signed in by email
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalBackend;
+
+#[async_trait]
+impl AuthBackend for LocalBackend {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn authenticate(
+        &self,
+        pool: &Pool<Postgres>,
+        login: &str,
+        password: &str,
+    ) -> Result<Option<User>, AppError> {
+        let user = if EmailUtils::is_valid_email(login) {
+            User::find_by_email(pool, login.to_string()).await?
+        } else {
+            None
+        };
+
+        if !verify_credentials(user.as_ref(), password)? {
+            return Ok(None);
+        }
+        match user {
+            Some(user) => Ok(Some(upgrade_password_hash(pool, user, password).await)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Replace a legacy or weak hash while the plaintext is at hand. A failure
+/// is only logged, the sign-in itself has succeeded.
+async fn upgrade_password_hash(pool: &Pool<Postgres>, user: User, password: &str) -> User {
+    if !PasswordUtils::needs_rehash(&user.password_hash) {
+        return user;
+    }
+
+    let upgraded = match PasswordUtils::hash_password(password) {
+        Ok(password_hash) => User::update_password(pool, user.id, password_hash).await.map_err(anyhow::Error::from),
+        Err(e) => Err(e),
+    };
+    match upgraded {
+        Ok(updated) => {
+            tracing::info!(user_id = %updated.id, "password hash upgraded");
+            updated
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user.id, "failed to upgrade password hash: {}", e);
+            user
+        }
+    }
+}
+
+/// Check a password without revealing through timing whether the user exists
+/// or has a password at all
+pub fn verify_credentials(user: Option<&User>, password: &str) -> Result<bool, AppError> {
+    let user = user.filter(|u| PasswordUtils::has_usable_password(&u.password_hash));
+    let hash = user.map_or(DUMMY_PASSWORD_HASH.as_str(), |u| u.password_hash.as_str());
+    let is_valid = PasswordUtils::verify_password(password, hash)
+        .map_err(|e| AppError::ValidationError(format!("Password verification failed: {}", e)))?;
+    Ok(is_valid && user.is_some())
+}
@@ -0,0 +1,92 @@
+// src/auth/backend/mod.rs
+//! Password authentication backends.
+//!
+//! `login_handler` checks passwords through the `AuthBackend` trait. The
+//! backends are listed in `AUTH_BACKENDS` (comma separated, tried in order):
+//! - `local` (default): password hashes stored in `users`
+//! - `ldap`: simple bind against a directory (`LDAP_*` variables)
+
+pub mod local;
+pub mod ldap;
+
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::{Pool, Postgres};
+
+use crate::{errors::AppError, models::user::User};
+
+pub use local::LocalBackend;
+pub use ldap::{LdapBackend, LdapConfig};
+
+#[async_trait]
+pub trait AuthBackend: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Check the credentials and return the local user they belong to.
+    /// `Ok(None)` means this backend does not accept them.
+    async fn authenticate(
+        &self,
+        pool: &Pool<Postgres>,
+        login: &str,
+        password: &str,
+    ) -> Result<Option<User>, AppError>;
+}
+
+/// Build the backends configured by the environment
+pub fn auth_backends_from_env() -> Result<Vec<Box<dyn AuthBackend>>> {
+    let names = std::env::var("AUTH_BACKENDS").unwrap_or_else(|_| "local".to_string());
+
+    let mut backends: Vec<Box<dyn AuthBackend>> = Vec::new();
+    for name in names.split(',').map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()) {
+        match name.as_str() {
+            "local" => backends.push(Box::new(LocalBackend)),
+            "ldap" => backends.push(Box::new(LdapBackend::new(LdapConfig::from_env()?))),
+            other => return Err(anyhow::anyhow!("Unknown AUTH_BACKENDS entry: {}", other)),
+        }
+    }
+    if backends.is_empty() {
+        return Err(anyhow::anyhow!("AUTH_BACKENDS must list at least one backend"));
+    }
+    Ok(backends)
+}
+
+/// Process-wide backends, built from the environment on first use
+pub fn shared_auth_backends() -> Result<&'static [Box<dyn AuthBackend>]> {
+    static AUTH_BACKENDS: OnceLock<Vec<Box<dyn AuthBackend>>> = OnceLock::new();
+
+    if let Some(backends) = AUTH_BACKENDS.get() {
+        return Ok(backends);
+    }
+    let backends = auth_backends_from_env()?;
+    Ok(AUTH_BACKENDS.get_or_init(|| backends))
+}
+
+/// Try each configured backend in order and return the first user that
+/// the credentials authenticate. A backend that fails, say because its
+/// directory is down, is logged and skipped; the attempt only errors if
+/// every backend failed rather than rejected the credentials.
+pub async fn authenticate(pool: &Pool<Postgres>, login: &str, password: &str) -> Result<Option<User>, AppError> {
+    let mut rejected = false;
+    let mut last_error = None;
+    for backend in shared_auth_backends()? {
+        match backend.authenticate(pool, login, password).await {
+            Ok(Some(user)) => {
+                tracing::debug!(backend = backend.name(), user_id = %user.id, "password accepted");
+                return Ok(Some(user));
+            }
+            Ok(None) => rejected = true,
+            Err(e) => {
+                tracing::warn!(backend = backend.name(), "authentication backend failed: {}", e);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    match last_error {
+        Some(e) if !rejected => Err(e),
+        _ => Ok(None),
+    }
+}
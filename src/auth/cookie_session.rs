@@ -0,0 +1,253 @@
+// src/auth/cookie_session.rs
+//! Cookie-based browser sessions.
+//!
+//! Browsers opt in by sending `X-Auth-Mode: cookie` when they register, log
+//! in, pass the MFA step or finish an OIDC login. The access and refresh
+//! tokens are then set as HttpOnly cookies instead of returned in the body,
+//! and `/refresh` and `/logout` take the refresh token from its cookie.
+//!
+//! A readable `csrf_token` cookie is set alongside. Requests authenticated
+//! by cookie that change state must echo it in the `X-CSRF-Token` header
+//! (double submit); a cross-site form can send the cookies but cannot read
+//! them to forge the header.
+//!
+//! An OIDC login is bound to the browser that started it by an HttpOnly
+//! `oidc_state` cookie holding its `state`, set on any client by
+//! `/oidc/authorize` and checked by `/oidc/callback`. Without it a victim
+//! could be sent a callback for the attacker's own login.
+//!
+//! `AUTH_COOKIE_SECURE=false` drops the `Secure` flag for plain http
+//! development and `AUTH_COOKIE_SAME_SITE` (`strict` by default, `lax`,
+//! `none`) sets the `SameSite` attribute.
+
+use axum::http::{header, HeaderMap, HeaderValue, Method};
+use cookie::{time::Duration, Cookie, SameSite};
+
+use crate::{
+    auth::{oidc::OIDC_STATE_TTL_MINUTES, SessionTokens},
+    errors::AppError,
+    utils::{TokenUtils, ACCESS_TOKEN_TTL_MINUTES, REFRESH_TOKEN_TTL_MINUTES},
+};
+
+pub const AUTH_MODE_HEADER: &str = "x-auth-mode";
+pub const CSRF_HEADER: &str = "x-csrf-token";
+pub const ACCESS_TOKEN_COOKIE: &str = "access_token";
+pub const REFRESH_TOKEN_COOKIE: &str = "refresh_token";
+pub const CSRF_COOKIE: &str = "csrf_token";
+pub const OIDC_STATE_COOKIE: &str = "oidc_state";
+
+/// The refresh token is only needed by `/refresh` and `/logout`
+const REFRESH_TOKEN_COOKIE_PATH: &str = "/api/auth";
+const OIDC_STATE_COOKIE_PATH: &str = "/api/auth/oidc";
+
+/// Whether the client asked for its tokens as cookies
+pub fn wants_cookie_session(headers: &HeaderMap) -> bool {
+    headers
+        .get(AUTH_MODE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("cookie"))
+}
+
+/// Value of a request cookie
+pub fn read_cookie(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(Cookie::split_parse)
+        .filter_map(Result::ok)
+        .find(|c| c.name() == name)
+        .map(|c| c.value().to_string())
+}
+
+/// Whether the request carries a cookie session
+pub fn has_cookie_session(headers: &HeaderMap) -> bool {
+    read_cookie(headers, ACCESS_TOKEN_COOKIE).is_some() || read_cookie(headers, REFRESH_TOKEN_COOKIE).is_some()
+}
+
+/// Reject a cookie-authenticated request that changes state unless the
+/// CSRF header matches the CSRF cookie
+pub fn verify_csrf(method: &Method, headers: &HeaderMap) -> Result<(), AppError> {
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return Ok(());
+    }
+
+    let cookie = read_cookie(headers, CSRF_COOKIE);
+    let header = headers.get(CSRF_HEADER).and_then(|v| v.to_str().ok());
+    match (cookie, header) {
+        // Compare digests so the time taken says nothing about the cookie
+        (Some(cookie), Some(header))
+            if !cookie.is_empty() && TokenUtils::hash_token(&cookie) == TokenUtils::hash_token(header) =>
+        {
+            Ok(())
+        }
+        _ => Err(AppError::PermissionDenied("Missing or invalid CSRF token".to_string())),
+    }
+}
+
+/// Reject an OIDC callback from a browser other than the one that asked
+/// for the `state`
+pub fn verify_oidc_state(headers: &HeaderMap, state: &str) -> Result<(), AppError> {
+    match read_cookie(headers, OIDC_STATE_COOKIE) {
+        Some(cookie) if !cookie.is_empty() && TokenUtils::hash_token(&cookie) == TokenUtils::hash_token(state) => {
+            Ok(())
+        }
+        _ => Err(AppError::ValidationError("Invalid or expired login state".to_string())),
+    }
+}
+
+/// `Set-Cookie` header binding a new OIDC login to this browser
+pub fn oidc_state_cookie(state: &str) -> HeaderMap {
+    let settings = CookieSettings::from_env();
+
+    set_cookie_headers([
+        settings.cookie(OIDC_STATE_COOKIE, state.to_string(), OIDC_STATE_COOKIE_PATH, true)
+            .max_age(Duration::minutes(OIDC_STATE_TTL_MINUTES))
+            .build(),
+    ])
+}
+
+/// `Set-Cookie` header deleting the OIDC state once it has been used
+pub fn clear_oidc_state_cookie() -> HeaderMap {
+    let settings = CookieSettings::from_env();
+
+    set_cookie_headers([
+        settings.cookie(OIDC_STATE_COOKIE, String::new(), OIDC_STATE_COOKIE_PATH, true).removal().build(),
+    ])
+}
+
+/// `Set-Cookie` headers for a new or rotated session, with a fresh CSRF token
+pub fn session_cookies(tokens: &SessionTokens) -> HeaderMap {
+    let settings = CookieSettings::from_env();
+    let refresh_max_age = Duration::minutes(REFRESH_TOKEN_TTL_MINUTES);
+
+    set_cookie_headers([
+        settings.cookie(ACCESS_TOKEN_COOKIE, tokens.access_token.clone(), "/", true)
+            .max_age(Duration::minutes(ACCESS_TOKEN_TTL_MINUTES))
+            .build(),
+        settings.cookie(REFRESH_TOKEN_COOKIE, tokens.refresh_token.clone(), REFRESH_TOKEN_COOKIE_PATH, true)
+            .max_age(refresh_max_age)
+            .build(),
+        // Readable by the frontend so it can echo it back
+        settings.cookie(CSRF_COOKIE, TokenUtils::generate_token(), "/", false)
+            .max_age(refresh_max_age)
+            .build(),
+    ])
+}
+
+/// `Set-Cookie` headers that delete the session cookies
+pub fn clear_session_cookies() -> HeaderMap {
+    let settings = CookieSettings::from_env();
+
+    set_cookie_headers([
+        settings.cookie(ACCESS_TOKEN_COOKIE, String::new(), "/", true).removal().build(),
+        settings.cookie(REFRESH_TOKEN_COOKIE, String::new(), REFRESH_TOKEN_COOKIE_PATH, true).removal().build(),
+        settings.cookie(CSRF_COOKIE, String::new(), "/", false).removal().build(),
+    ])
+}
+
+fn set_cookie_headers<const N: usize>(cookies: [Cookie<'static>; N]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for cookie in cookies {
+        if let Ok(value) = HeaderValue::from_str(&cookie.to_string()) {
+            headers.append(header::SET_COOKIE, value);
+        }
+    }
+    headers
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CookieSettings {
+    secure: bool,
+    same_site: SameSite,
+}
+
+impl CookieSettings {
+    fn from_env() -> Self {
+        let secure = std::env::var("AUTH_COOKIE_SECURE").map_or(true, |v| v != "false" && v != "0");
+        let same_site = match std::env::var("AUTH_COOKIE_SAME_SITE").unwrap_or_default().to_lowercase().as_str() {
+            "lax" => SameSite::Lax,
+            // Browsers drop SameSite=None cookies without Secure
+            "none" if secure => SameSite::None,
+            _ => SameSite::Strict,
+        };
+        Self { secure, same_site }
+    }
+
+    fn cookie(
+        &self,
+        name: &'static str,
+        value: String,
+        path: &'static str,
+        http_only: bool,
+    ) -> cookie::CookieBuilder<'static> {
+        Cookie::build((name, value))
+            .path(path)
+            .http_only(http_only)
+            .secure(self.secure)
+            .same_site(self.same_site)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_read_cookie() {
+        let headers = headers(&[("cookie", "theme=dark; access_token=abc"), ("cookie", "csrf_token=xyz")]);
+        assert_eq!(read_cookie(&headers, ACCESS_TOKEN_COOKIE).as_deref(), Some("abc"));
+        assert_eq!(read_cookie(&headers, CSRF_COOKIE).as_deref(), Some("xyz"));
+        assert_eq!(read_cookie(&headers, REFRESH_TOKEN_COOKIE), None);
+    }
+
+    #[test]
+    fn test_csrf_required_for_writes() {
+        let valid = headers(&[("cookie", "csrf_token=xyz"), ("x-csrf-token", "xyz")]);
+        let forged = headers(&[("cookie", "csrf_token=xyz"), ("x-csrf-token", "abc")]);
+        let missing = headers(&[("cookie", "csrf_token=xyz")]);
+
+        assert!(verify_csrf(&Method::POST, &valid).is_ok());
+        assert!(verify_csrf(&Method::POST, &forged).is_err());
+        assert!(verify_csrf(&Method::DELETE, &missing).is_err());
+        assert!(verify_csrf(&Method::GET, &missing).is_ok());
+    }
+
+    #[test]
+    fn test_session_cookie_attributes() {
+        let tokens = SessionTokens { access_token: "a".to_string(), refresh_token: "r".to_string() };
+        let cookies: Vec<String> = session_cookies(&tokens)
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .map(|v| v.to_str().unwrap().to_string())
+            .collect();
+
+        assert_eq!(cookies.len(), 3);
+        assert!(cookies[0].starts_with("access_token=a;") && cookies[0].contains("HttpOnly"));
+        assert!(cookies[1].contains("Path=/api/auth") && cookies[1].contains("HttpOnly"));
+        assert!(cookies[2].starts_with("csrf_token=") && !cookies[2].contains("HttpOnly"));
+        assert!(cookies.iter().all(|c| c.contains("SameSite=Strict")));
+    }
+
+    #[test]
+    fn test_oidc_state_bound_to_cookie() {
+        let cookie = oidc_state_cookie("abc");
+        let set_cookie = cookie.get(header::SET_COOKIE).unwrap().to_str().unwrap();
+        assert!(set_cookie.starts_with("oidc_state=abc;") && set_cookie.contains("HttpOnly"));
+        assert!(set_cookie.contains("Path=/api/auth/oidc"));
+
+        let browser = headers(&[("cookie", "oidc_state=abc")]);
+        let other_browser = headers(&[("cookie", "oidc_state=xyz")]);
+        assert!(verify_oidc_state(&browser, "abc").is_ok());
+        assert!(verify_oidc_state(&other_browser, "abc").is_err());
+        assert!(verify_oidc_state(&headers(&[]), "abc").is_err());
+    }
+}
@@ -0,0 +1,223 @@
+// src/auth/mfa.rs
+//! TOTP (RFC 6238) two-factor authentication.
+//!
+//! Enrollment is a two-step process: `begin_totp_enrollment` stores an
+//! unconfirmed secret and returns its provisioning URI, and
+//! `confirm_totp_enrollment` enables it once the user submits a first valid
+//! code, returning a set of single-use recovery codes.
+
+use chrono::Utc;
+use rand::{rngs::OsRng, RngCore};
+use sqlx::{Pool, Postgres};
+use totp_rs::{Algorithm, Secret, TOTP};
+use uuid::Uuid;
+
+use crate::{
+    errors::AppError,
+    models::{mfa_recovery_code::MfaRecoveryCode, user::User, user_totp::UserTotp},
+    utils::TokenUtils,
+};
+
+pub const MFA_ISSUER: &str = "StudySphere";
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+const TOTP_DIGITS: usize = 6;
+const TOTP_STEP_SECONDS: u64 = 30;
+/// Accept codes from one step before or after the current one
+const TOTP_ALLOWED_DRIFT: i64 = 1;
+
+#[derive(Debug)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub provisioning_uri: String,
+}
+
+pub async fn begin_totp_enrollment(pool: &Pool<Postgres>, user: &User) -> Result<TotpEnrollment, AppError> {
+    if is_mfa_enabled(pool, user.id).await? {
+        return Err(AppError::ValidationError("Two-factor authentication is already enabled".to_string()));
+    }
+
+    let secret = Secret::generate_secret().to_encoded().to_string();
+    let totp = build_totp(&secret, &user.email)?;
+
+    UserTotp::upsert_pending(pool, user.id, secret.clone()).await?;
+
+    Ok(TotpEnrollment {
+        secret,
+        provisioning_uri: totp.get_url(),
+    })
+}
+
+/// Enable TOTP after checking a first code. Returns the plaintext recovery
+/// codes, which are never retrievable again.
+pub async fn confirm_totp_enrollment(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    code: &str,
+) -> Result<Vec<String>, AppError> {
+    let user_totp = UserTotp::find_by_user_id(pool, user_id)
+        .await?
+        .ok_or(AppError::ValidationError("No two-factor enrollment in progress".to_string()))?;
+
+    if user_totp.confirmed_at.is_some() {
+        return Err(AppError::ValidationError("Two-factor authentication is already enabled".to_string()));
+    }
+
+    if !verify_totp_code(pool, &user_totp, code).await? {
+        return Err(AppError::ValidationError("Invalid authentication code".to_string()));
+    }
+
+    UserTotp::confirm(pool, user_id).await?;
+    regenerate_recovery_codes(pool, user_id).await
+}
+
+pub async fn regenerate_recovery_codes(pool: &Pool<Postgres>, user_id: Uuid) -> Result<Vec<String>, AppError> {
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| generate_recovery_code()).collect();
+    let hashes = codes
+        .iter()
+        .map(|code| TokenUtils::hash_token(&normalize_recovery_code(code)))
+        .collect();
+
+    MfaRecoveryCode::replace_for_user(pool, user_id, hashes).await?;
+    Ok(codes)
+}
+
+pub async fn is_mfa_enabled(pool: &Pool<Postgres>, user_id: Uuid) -> Result<bool, AppError> {
+    let user_totp = UserTotp::find_by_user_id(pool, user_id).await?;
+    Ok(user_totp.is_some_and(|t| t.confirmed_at.is_some()))
+}
+
+/// Check a TOTP code or, failing that, a recovery code
+pub async fn verify_second_factor(pool: &Pool<Postgres>, user_id: Uuid, code: &str) -> Result<bool, AppError> {
+    let Some(user_totp) = UserTotp::find_by_user_id(pool, user_id).await? else {
+        return Ok(false);
+    };
+
+    if user_totp.confirmed_at.is_none() {
+        return Ok(false);
+    }
+
+    let code = code.trim();
+    if code.len() == TOTP_DIGITS && code.chars().all(|c| c.is_ascii_digit()) {
+        return verify_totp_code(pool, &user_totp, code).await;
+    }
+
+    let code_hash = TokenUtils::hash_token(&normalize_recovery_code(code));
+    Ok(MfaRecoveryCode::consume(pool, user_id, code_hash).await?)
+}
+
+pub async fn disable_totp(pool: &Pool<Postgres>, user_id: Uuid, code: &str) -> Result<(), AppError> {
+    if !verify_second_factor(pool, user_id, code).await? {
+        return Err(AppError::ValidationError("Invalid authentication code".to_string()));
+    }
+
+    UserTotp::delete(pool, user_id).await?;
+    MfaRecoveryCode::delete_for_user(pool, user_id).await?;
+    Ok(())
+}
+
+async fn verify_totp_code(pool: &Pool<Postgres>, user_totp: &UserTotp, code: &str) -> Result<bool, AppError> {
+    let totp = build_totp(&user_totp.secret, "")?;
+    let now = Utc::now().timestamp() as u64;
+
+    match matching_step(&totp, code.trim(), now, user_totp.last_used_step) {
+        Some(step) => Ok(UserTotp::record_used_step(pool, user_totp.user_id, step).await?),
+        None => Ok(false),
+    }
+}
+
+/// Find the time step a code belongs to, skipping steps at or before the
+/// last one used so every code is accepted at most once
+fn matching_step(totp: &TOTP, code: &str, now: u64, last_used_step: Option<i64>) -> Option<i64> {
+    let current_step = (now / TOTP_STEP_SECONDS) as i64;
+
+    (current_step - TOTP_ALLOWED_DRIFT..=current_step + TOTP_ALLOWED_DRIFT)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| totp.check(code, *step as u64 * TOTP_STEP_SECONDS))
+}
+
+fn build_totp(secret: &str, account_name: &str) -> Result<TOTP, AppError> {
+    let secret_bytes = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|e| anyhow::anyhow!("Invalid TOTP secret: {:?}", e))?;
+
+    TOTP::new(
+        Algorithm::SHA1,
+        TOTP_DIGITS,
+        0,
+        TOTP_STEP_SECONDS,
+        secret_bytes,
+        Some(MFA_ISSUER.to_string()),
+        account_name.to_string(),
+    )
+    .map_err(|e| AppError::Anyhow(anyhow::anyhow!("Failed to build TOTP: {}", e)))
+}
+
+/// Ten hex characters shown as `xxxxx-xxxxx`
+fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; 5];
+    OsRng.fill_bytes(&mut bytes);
+    let code = hex::encode(bytes);
+    format!("{}-{}", &code[..5], &code[5..])
+}
+
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_totp() -> TOTP {
+        build_totp("JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP", "user@example.com").unwrap()
+    }
+
+    #[test]
+    fn test_matching_step_accepts_drift() {
+        let totp = test_totp();
+        let now = 1_700_000_000;
+        let current_step = (now / TOTP_STEP_SECONDS) as i64;
+
+        let current = totp.generate(now);
+        let previous = totp.generate(now - TOTP_STEP_SECONDS);
+        let too_old = totp.generate(now - 3 * TOTP_STEP_SECONDS);
+
+        assert_eq!(matching_step(&totp, &current, now, None), Some(current_step));
+        assert_eq!(matching_step(&totp, &previous, now, None), Some(current_step - 1));
+        assert_eq!(matching_step(&totp, &too_old, now, None), None);
+    }
+
+    #[test]
+    fn test_matching_step_rejects_replay() {
+        let totp = test_totp();
+        let now = 1_700_000_000;
+        let current_step = (now / TOTP_STEP_SECONDS) as i64;
+        let code = totp.generate(now);
+
+        assert_eq!(matching_step(&totp, &code, now, Some(current_step)), None);
+        assert_eq!(matching_step(&totp, &code, now, Some(current_step - 1)), Some(current_step));
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let uri = test_totp().get_url();
+        assert!(uri.starts_with("otpauth://totp/StudySphere:user%40example.com?"));
+        assert!(uri.contains("secret=JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP"));
+        assert!(uri.contains("issuer=StudySphere"));
+    }
+
+    #[test]
+    fn test_recovery_codes() {
+        let code = generate_recovery_code();
+        assert_eq!(code.len(), 11);
+        assert_eq!(code.chars().nth(5), Some('-'));
+        assert_ne!(code, generate_recovery_code());
+
+        assert_eq!(normalize_recovery_code(" ABCDE-12345 "), "abcde12345");
+        assert_eq!(normalize_recovery_code(&code), code.replace('-', ""));
+    }
+}
@@ -4,6 +4,52 @@
 //! This module will contain JWT token verification, user authentication,
 //! and authorization logic.
 
+pub mod session;
+pub mod revocation;
+pub mod verification;
+pub mod mfa;
+pub mod personal_token;
+pub mod throttle;
+pub mod oidc;
+pub mod provisioning;
+pub mod backend;
+pub mod cookie_session;
+pub mod account_deletion;
+pub mod password_reset;
+pub mod admin;
+
+pub use session::{
+    start_session, rotate_session, end_session, list_sessions, revoke_session, revoke_other_sessions,
+    SessionTokens,
+};
+pub use revocation::{
+    revoke_access_token, is_revoked, is_access_token_revoked, is_session_revoked, load_revoked_access_tokens,
+    revoke_all_tokens, consume_single_use_token,
+};
+pub use verification::{send_verification_email, verify_email, require_verified_email};
+pub use password_reset::send_password_reset_email;
+pub use mfa::{
+    begin_totp_enrollment, confirm_totp_enrollment, disable_totp, is_mfa_enabled,
+    verify_second_factor, TotpEnrollment,
+};
+pub use personal_token::{
+    create_personal_access_token, authenticate_personal_access_token, is_personal_access_token,
+    scopes_permit, TokenScope,
+};
+pub use throttle::{
+    begin_login_attempt, record_login_failure, record_first_factor_success, record_login_success, LoginAttempt,
+    begin_password_reset_request,
+};
+pub use oidc::{begin_oidc_login, complete_oidc_login, purge_expired_login_states, OidcAuthorization};
+pub use provisioning::{provision_external_user, ExternalIdentity};
+pub use backend::{authenticate, shared_auth_backends, AuthBackend};
+pub use cookie_session::{
+    wants_cookie_session, has_cookie_session, read_cookie, verify_csrf, session_cookies,
+    clear_session_cookies, verify_oidc_state, oidc_state_cookie, clear_oidc_state_cookie,
+    ACCESS_TOKEN_COOKIE, REFRESH_TOKEN_COOKIE,
+};
+pub use account_deletion::{
+    schedule_account_deletion, cancel_account_deletion, purge_due_accounts, spawn_account_purger,
+};
+
 // TODO: Implement authentication middleware
-// TODO: Implement JWT token verification
-// TODO: Implement user session management
\ No newline at end of file
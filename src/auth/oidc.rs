@@ -0,0 +1,330 @@
+// src/auth/oidc.rs
+//! OpenID Connect login.
+//!
+//! Authorization code flow with PKCE against the provider configured by
+//! `OIDC_ISSUER_URL`. The frontend sends the user to the authorization URL
+//! from `begin_oidc_login` and posts the returned `code` and `state` back,
+//! which `complete_oidc_login` exchanges for a validated ID token and a
+//! local user. The handlers also tie the `state` to the browser with a
+//! cookie, see `cookie_session`.
+//!
+//! Each address can have `MAX_PENDING_LOGINS_PER_IP` logins in flight; the
+//! states of abandoned ones are purged once expired.
+//!
+//! Identities are linked by `iss` + `sub`. The first login needs an email
+//! the provider marks as verified, see `provisioning`.
+
+use std::time::Duration as StdDuration;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres};
+use tokio::sync::OnceCell;
+
+use crate::{
+    auth::provisioning::{provision_external_user, ExternalIdentity},
+    errors::AppError,
+    models::{
+        oidc_login_state::{NewOidcLoginState, OidcLoginState},
+        user::User,
+    },
+    utils::TokenUtils,
+};
+
+pub const OIDC_STATE_TTL_MINUTES: i64 = 10;
+const MAX_PENDING_LOGINS_PER_IP: i64 = 20;
+const HTTP_TIMEOUT_SECONDS: u64 = 10;
+
+/// Signature algorithms accepted on ID tokens. HMAC is excluded: it would
+/// make the client secret a signing key.
+const ID_TOKEN_ALGORITHMS: [Algorithm; 7] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+static PROVIDER: OnceCell<ProviderMetadata> = OnceCell::const_new();
+
+#[derive(Debug, Clone)]
+struct OidcConfig {
+    issuer_url: String,
+    client_id: String,
+    client_secret: Option<String>,
+    redirect_url: String,
+    scopes: String,
+}
+
+impl OidcConfig {
+    fn from_env() -> Result<Self, AppError> {
+        // Without an issuer the feature is simply off
+        let issuer_url = std::env::var("OIDC_ISSUER_URL").map_err(|_| AppError::NotFound)?;
+        let env = |name: &str| {
+            std::env::var(name)
+                .map_err(|_| AppError::ValidationError(format!("{} environment variable not set", name)))
+        };
+
+        Ok(Self {
+            issuer_url: issuer_url.trim_end_matches('/').to_string(),
+            client_id: env("OIDC_CLIENT_ID")?,
+            client_secret: std::env::var("OIDC_CLIENT_SECRET").ok().filter(|s| !s.is_empty()),
+            redirect_url: env("OIDC_REDIRECT_URL")?,
+            scopes: std::env::var("OIDC_SCOPES").unwrap_or_else(|_| "openid email profile".to_string()),
+        })
+    }
+}
+
+/// The parts of the provider's discovery document we use
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub preferred_username: Option<String>,
+}
+
+fn http_client() -> Result<reqwest::Client, AppError> {
+    reqwest::Client::builder()
+        .timeout(StdDuration::from_secs(HTTP_TIMEOUT_SECONDS))
+        .build()
+        .map_err(|e| AppError::Anyhow(e.into()))
+}
+
+/// Fetch the discovery document once per process
+async fn provider(config: &OidcConfig) -> Result<&'static ProviderMetadata, AppError> {
+    PROVIDER
+        .get_or_try_init(|| async {
+            let url = format!("{}/.well-known/openid-configuration", config.issuer_url);
+            let metadata: ProviderMetadata = http_client()?
+                .get(&url)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| AppError::Anyhow(anyhow::anyhow!("OIDC discovery failed: {}", e)))?
+                .json()
+                .await
+                .map_err(|e| AppError::Anyhow(anyhow::anyhow!("Invalid OIDC discovery document: {}", e)))?;
+
+            if metadata.issuer.trim_end_matches('/') != config.issuer_url {
+                return Err(AppError::Anyhow(anyhow::anyhow!(
+                    "OIDC discovery issuer {} does not match {}", metadata.issuer, config.issuer_url
+                )));
+            }
+            Ok(metadata)
+        })
+        .await
+}
+
+/// S256 code challenge for a PKCE code verifier
+fn pkce_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// A login in flight: where to send the user, and the `state` the provider
+/// will hand back
+#[derive(Debug)]
+pub struct OidcAuthorization {
+    pub url: String,
+    pub state: String,
+}
+
+/// Start a login and return the provider URL to send the user to
+pub async fn begin_oidc_login(
+    pool: &Pool<Postgres>,
+    ip_address: Option<&str>,
+) -> Result<OidcAuthorization, AppError> {
+    let config = OidcConfig::from_env()?;
+    let provider = provider(&config).await?;
+
+    if let Some(ip) = ip_address
+        && OidcLoginState::count_pending_for_ip(pool, ip).await? >= MAX_PENDING_LOGINS_PER_IP
+    {
+        return Err(AppError::TooManyRequests(
+            "Too many sign-in attempts in progress. Try again later".to_string(),
+        ));
+    }
+
+    let state = TokenUtils::generate_token();
+    let nonce = TokenUtils::generate_token();
+    let code_verifier = TokenUtils::generate_token();
+
+    OidcLoginState::create(pool, NewOidcLoginState {
+        state_hash: TokenUtils::hash_token(&state),
+        code_verifier: code_verifier.clone(),
+        nonce: nonce.clone(),
+        expires_at: Utc::now() + Duration::minutes(OIDC_STATE_TTL_MINUTES),
+        ip_address: ip_address.map(str::to_string),
+    }).await?;
+
+    let url = reqwest::Url::parse_with_params(&provider.authorization_endpoint, &[
+        ("response_type", "code"),
+        ("client_id", config.client_id.as_str()),
+        ("redirect_uri", config.redirect_url.as_str()),
+        ("scope", config.scopes.as_str()),
+        ("state", state.as_str()),
+        ("nonce", nonce.as_str()),
+        ("code_challenge", pkce_challenge(&code_verifier).as_str()),
+        ("code_challenge_method", "S256"),
+    ])
+    .map_err(|e| AppError::Anyhow(anyhow::anyhow!("Invalid authorization endpoint: {}", e)))?;
+
+    Ok(OidcAuthorization { url: url.into(), state })
+}
+
+/// Remove the states of logins that were never finished. Returns how many
+/// were removed.
+pub async fn purge_expired_login_states(pool: &Pool<Postgres>) -> Result<u64, AppError> {
+    Ok(OidcLoginState::delete_expired(pool).await?)
+}
+
+/// Finish a login from the provider callback and return the local user
+pub async fn complete_oidc_login(pool: &Pool<Postgres>, code: &str, state: &str) -> Result<User, AppError> {
+    let config = OidcConfig::from_env()?;
+    let provider = provider(&config).await?;
+
+    let login_state = OidcLoginState::consume(pool, TokenUtils::hash_token(state))
+        .await?
+        .ok_or(AppError::ValidationError("Invalid or expired login state".to_string()))?;
+
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", config.redirect_url.as_str()),
+        ("client_id", config.client_id.as_str()),
+        ("code_verifier", login_state.code_verifier.as_str()),
+    ];
+    if let Some(secret) = &config.client_secret {
+        form.push(("client_secret", secret.as_str()));
+    }
+
+    let response = http_client()?
+        .post(&provider.token_endpoint)
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| AppError::Anyhow(anyhow::anyhow!("OIDC token request failed: {}", e)))?;
+    if !response.status().is_success() {
+        tracing::warn!(status = %response.status(), "OIDC code exchange rejected");
+        return Err(AppError::Unauthorized);
+    }
+    let tokens: TokenResponse = response
+        .json()
+        .await
+        .map_err(|e| AppError::Anyhow(anyhow::anyhow!("Invalid OIDC token response: {}", e)))?;
+
+    let jwks: JwkSet = http_client()?
+        .get(&provider.jwks_uri)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| AppError::Anyhow(anyhow::anyhow!("OIDC JWKS request failed: {}", e)))?
+        .json()
+        .await
+        .map_err(|e| AppError::Anyhow(anyhow::anyhow!("Invalid OIDC JWKS: {}", e)))?;
+
+    let claims = validate_id_token(&tokens.id_token, &jwks, &provider.issuer, &config.client_id, &login_state.nonce)?;
+    resolve_user(pool, &claims).await
+}
+
+/// Check the ID token signature, issuer, audience, expiry and nonce
+fn validate_id_token(
+    id_token: &str,
+    jwks: &JwkSet,
+    issuer: &str,
+    client_id: &str,
+    nonce: &str,
+) -> Result<IdTokenClaims, AppError> {
+    let header = decode_header(id_token).map_err(|_| AppError::Unauthorized)?;
+    if !ID_TOKEN_ALGORITHMS.contains(&header.alg) {
+        return Err(AppError::Unauthorized);
+    }
+
+    let jwk = match &header.kid {
+        Some(kid) => jwks.find(kid),
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    }
+    .ok_or(AppError::Unauthorized)?;
+    let key = DecodingKey::from_jwk(jwk).map_err(|_| AppError::Unauthorized)?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_issuer(&[issuer]);
+    validation.set_audience(&[client_id]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+    let claims = decode::<IdTokenClaims>(id_token, &key, &validation)
+        .map_err(|e| {
+            tracing::warn!("rejected OIDC ID token: {}", e);
+            AppError::Unauthorized
+        })?
+        .claims;
+
+    if claims.nonce.as_deref() != Some(nonce) {
+        return Err(AppError::Unauthorized);
+    }
+    Ok(claims)
+}
+
+async fn resolve_user(pool: &Pool<Postgres>, claims: &IdTokenClaims) -> Result<User, AppError> {
+    provision_external_user(pool, ExternalIdentity {
+        issuer: claims.iss.clone(),
+        subject: claims.sub.clone(),
+        email: claims.email.clone(),
+        email_verified: claims.email_verified,
+        preferred_username: claims.preferred_username.clone(),
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pkce_challenge_matches_rfc_7636() {
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn test_id_token_with_unknown_key_rejected() {
+        let jwks = JwkSet { keys: Vec::new() };
+        let result = validate_id_token("eyJhbGciOiJSUzI1NiJ9.e30.sig", &jwks, "iss", "client", "nonce");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_hmac_id_token_rejected() {
+        let token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::new(Algorithm::HS256),
+            &serde_json::json!({ "sub": "x" }),
+            &jsonwebtoken::EncodingKey::from_secret(b"client-secret"),
+        )
+        .unwrap();
+        let jwks = JwkSet { keys: Vec::new() };
+        assert!(validate_id_token(&token, &jwks, "iss", "client", "nonce").is_err());
+    }
+}
@@ -0,0 +1,47 @@
+// src/auth/password_reset.rs
+//! Password reset links.
+//!
+//! Users request one at `/password/forgot`; admins can force one, which also
+//! locks the current password out.
+
+use chrono::{Duration, Utc};
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    errors::AppError,
+    mail::{self, MailMessage},
+    models::{
+        password_reset_token::{NewPasswordResetToken, PasswordResetToken},
+        user::User,
+    },
+    utils::TokenUtils,
+};
+
+pub const PASSWORD_RESET_TTL_MINUTES: i64 = 60;
+
+/// Issue a new reset token for the user and email the link
+pub async fn send_password_reset_email(pool: &Pool<Postgres>, user: &User) -> Result<(), AppError> {
+    let token = TokenUtils::generate_token();
+
+    PasswordResetToken::invalidate_for_user(pool, user.id).await?;
+    PasswordResetToken::create(pool, NewPasswordResetToken {
+        user_id: user.id,
+        token_hash: TokenUtils::hash_token(&token),
+        expires_at: Utc::now() + Duration::minutes(PASSWORD_RESET_TTL_MINUTES),
+    }).await?;
+
+    let message = MailMessage {
+        to: user.email.clone(),
+        subject: "Reset your StudySphere password".to_string(),
+        body: format!(
+            "Hi {},\n\nUse the link below to choose a new password. It expires in {} minutes.\n\n{}/reset-password?token={}\n\nIf you did not request a reset, you can ignore this email.",
+            user.username,
+            PASSWORD_RESET_TTL_MINUTES,
+            mail::frontend_url(),
+            token
+        ),
+    };
+
+    mail::mailer_from_env()?.send(message).await?;
+    Ok(())
+}
@@ -0,0 +1,190 @@
+// src/auth/personal_token.rs
+//! Personal access tokens.
+//!
+//! Long-lived bearer tokens for scripts and integrations, accepted by the
+//! auth middleware next to JWT access tokens. Each token carries scopes;
+//! reads are allowed with any scope, writes need the scope covering the
+//! resource. Account routes under `/api/auth` (profile, sessions, tokens,
+//! the data export) are only readable with `account:read` and never
+//! writable with a personal access token.
+
+use axum::http::Method;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    errors::AppError,
+    models::personal_access_token::{NewPersonalAccessToken, PersonalAccessToken},
+    utils::TokenUtils,
+};
+
+/// Prefix that tells personal access tokens apart from JWTs
+pub const PERSONAL_ACCESS_TOKEN_PREFIX: &str = "ssp_";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TokenScope {
+    #[serde(rename = "read-only")]
+    ReadOnly,
+    #[serde(rename = "groups:write")]
+    GroupsWrite,
+    #[serde(rename = "materials:write")]
+    MaterialsWrite,
+    #[serde(rename = "comments:write")]
+    CommentsWrite,
+    #[serde(rename = "account:read")]
+    AccountRead,
+}
+
+impl TokenScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::ReadOnly => "read-only",
+            TokenScope::GroupsWrite => "groups:write",
+            TokenScope::MaterialsWrite => "materials:write",
+            TokenScope::CommentsWrite => "comments:write",
+            TokenScope::AccountRead => "account:read",
+        }
+    }
+
+    pub fn parse(scope: &str) -> Option<Self> {
+        match scope {
+            "read-only" => Some(TokenScope::ReadOnly),
+            "groups:write" => Some(TokenScope::GroupsWrite),
+            "materials:write" => Some(TokenScope::MaterialsWrite),
+            "comments:write" => Some(TokenScope::CommentsWrite),
+            "account:read" => Some(TokenScope::AccountRead),
+            _ => None,
+        }
+    }
+
+    /// Whether this scope allows `method` on the full request `path`
+    pub fn permits(&self, method: &Method, path: &str) -> bool {
+        if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+            return !is_account_path(path) || *self == TokenScope::AccountRead;
+        }
+        write_scope_for(path) == Some(*self)
+    }
+}
+
+fn is_account_path(path: &str) -> bool {
+    path == "/api/auth" || path.starts_with("/api/auth/")
+}
+
+/// The scope needed to modify the resource at `path`, if any scope can
+fn write_scope_for(path: &str) -> Option<TokenScope> {
+    if path.starts_with("/api/materials") {
+        if path.contains("/comments") {
+            return Some(TokenScope::CommentsWrite);
+        }
+        return Some(TokenScope::MaterialsWrite);
+    }
+    if path.starts_with("/api/groups") {
+        return Some(TokenScope::GroupsWrite);
+    }
+    None
+}
+
+/// Whether any of the token's scopes allows the request
+pub fn scopes_permit(scopes: &[TokenScope], method: &Method, path: &str) -> bool {
+    scopes.iter().any(|scope| scope.permits(method, path))
+}
+
+pub fn is_personal_access_token(token: &str) -> bool {
+    token.starts_with(PERSONAL_ACCESS_TOKEN_PREFIX)
+}
+
+/// Create a token for the user. Returns the stored row and the plaintext
+/// token, which cannot be recovered later.
+pub async fn create_personal_access_token(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    name: String,
+    scopes: &[TokenScope],
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<(PersonalAccessToken, String), AppError> {
+    let token = format!("{}{}", PERSONAL_ACCESS_TOKEN_PREFIX, TokenUtils::generate_token());
+
+    let stored = PersonalAccessToken::create(pool, NewPersonalAccessToken {
+        user_id,
+        name,
+        token_hash: TokenUtils::hash_token(&token),
+        scopes: scopes.iter().map(|s| s.as_str().to_string()).collect(),
+        expires_at,
+    }).await?;
+
+    Ok((stored, token))
+}
+
+/// Resolve a presented token to its row and scopes
+pub async fn authenticate_personal_access_token(
+    pool: &Pool<Postgres>,
+    token: &str,
+) -> Result<(PersonalAccessToken, Vec<TokenScope>), AppError> {
+    let stored = PersonalAccessToken::touch_by_hash(pool, TokenUtils::hash_token(token))
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    let scopes = stored.scopes.iter().filter_map(|s| TokenScope::parse(s)).collect();
+
+    Ok((stored, scopes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_allowed_with_any_scope() {
+        for scope in [TokenScope::ReadOnly, TokenScope::MaterialsWrite] {
+            assert!(scope.permits(&Method::GET, "/api/materials/abc"));
+            assert!(scope.permits(&Method::GET, "/api/groups/rust"));
+        }
+    }
+
+    #[test]
+    fn test_account_reads_need_account_scope() {
+        assert!(!TokenScope::MaterialsWrite.permits(&Method::GET, "/api/auth/me/export"));
+        assert!(!TokenScope::ReadOnly.permits(&Method::GET, "/api/auth/sessions"));
+        assert!(!TokenScope::ReadOnly.permits(&Method::HEAD, "/api/auth/tokens"));
+        assert!(TokenScope::AccountRead.permits(&Method::GET, "/api/auth/me/export"));
+    }
+
+    #[test]
+    fn test_read_only_denies_writes() {
+        assert!(!TokenScope::ReadOnly.permits(&Method::POST, "/api/materials"));
+        assert!(!TokenScope::ReadOnly.permits(&Method::DELETE, "/api/groups/rust"));
+    }
+
+    #[test]
+    fn test_write_scopes_match_resource() {
+        assert!(TokenScope::MaterialsWrite.permits(&Method::POST, "/api/materials"));
+        assert!(TokenScope::MaterialsWrite.permits(&Method::POST, "/api/materials/abc/labels"));
+        assert!(!TokenScope::MaterialsWrite.permits(&Method::POST, "/api/materials/abc/comments"));
+        assert!(TokenScope::CommentsWrite.permits(&Method::PUT, "/api/materials/abc/comments/1"));
+        assert!(TokenScope::GroupsWrite.permits(&Method::POST, "/api/groups/rust/courses"));
+        assert!(!TokenScope::GroupsWrite.permits(&Method::POST, "/api/materials"));
+    }
+
+    #[test]
+    fn test_account_routes_never_writable() {
+        let all = [
+            TokenScope::ReadOnly,
+            TokenScope::GroupsWrite,
+            TokenScope::MaterialsWrite,
+            TokenScope::CommentsWrite,
+            TokenScope::AccountRead,
+        ];
+        assert!(!scopes_permit(&all, &Method::POST, "/api/auth/tokens"));
+        assert!(!scopes_permit(&all, &Method::PATCH, "/api/auth/me"));
+    }
+
+    #[test]
+    fn test_scope_round_trip() {
+        for scope in ["read-only", "groups:write", "materials:write", "comments:write", "account:read"] {
+            assert_eq!(TokenScope::parse(scope).unwrap().as_str(), scope);
+        }
+        assert!(TokenScope::parse("admin").is_none());
+    }
+}
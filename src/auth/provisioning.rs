@@ -0,0 +1,189 @@
+// src/auth/provisioning.rs
+//! Just-in-time accounts for users who sign in through an external identity
+//! source (OIDC provider, LDAP directory).
+//!
+//! External identities are linked by issuer + subject. On first login an
+//! existing local account with the same email is linked only when the source
+//! vouches for the address and the account has neither a password nor the
+//! admin role; any other match is refused rather than taken over. Without a
+//! match a new account is created.
+
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    errors::AppError,
+    models::{
+        user::{NewUser, User},
+        user_identity::{NewUserIdentity, UserIdentity},
+    },
+    utils::{EmailUtils, PasswordUtils, TokenUtils},
+};
+
+/// A user as asserted by an external identity source
+#[derive(Debug, Clone)]
+pub struct ExternalIdentity {
+    pub issuer: String,
+    pub subject: String,
+    pub email: Option<String>,
+    /// Whether the source vouches that the user owns `email`
+    pub email_verified: bool,
+    pub preferred_username: Option<String>,
+}
+
+/// What to do with the local account that has the identity's email
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EmailMatch {
+    Create,
+    Link,
+    Refuse,
+}
+
+fn email_match(existing: Option<&User>, email_verified: bool) -> EmailMatch {
+    match existing {
+        None => EmailMatch::Create,
+        Some(user) if email_verified
+            && user.email_verified_at.is_some()
+            && !user.is_admin()
+            && !PasswordUtils::has_usable_password(&user.password_hash) => EmailMatch::Link,
+        Some(_) => EmailMatch::Refuse,
+    }
+}
+
+/// Find the linked user, link a matching password-less account, or create a
+/// new one
+pub async fn provision_external_user(pool: &Pool<Postgres>, identity: ExternalIdentity) -> Result<User, AppError> {
+    if let Some(linked) = UserIdentity::find_by_subject(pool, &identity.issuer, &identity.subject).await? {
+        return User::find_by_id(pool, linked.user_id).await?.ok_or(AppError::Unauthorized);
+    }
+
+    let email = match &identity.email {
+        Some(email) if EmailUtils::is_valid_email(email) => EmailUtils::normalize_email(email),
+        _ => {
+            return Err(AppError::ValidationError(
+                "Your identity provider did not share an email address".to_string(),
+            ));
+        }
+    };
+
+    let existing = User::find_by_email(pool, email.clone()).await?;
+    let user = match (email_match(existing.as_ref(), identity.email_verified), existing) {
+        (EmailMatch::Link, Some(user)) => user,
+        (EmailMatch::Create, _) => {
+            let username = available_username(pool, identity.preferred_username.as_deref(), &email).await?;
+            let user = User::create(pool, NewUser {
+                email: email.clone(),
+                username,
+                password_hash: PasswordUtils::unusable_password(),
+                bio: None,
+                image_url: None,
+            }).await?;
+            if identity.email_verified {
+                User::mark_email_verified(pool, user.id).await?
+            } else {
+                user
+            }
+        }
+        _ => {
+            return Err(AppError::Conflict(
+                "An account with this email already exists. Sign in with its password instead".to_string(),
+            ));
+        }
+    };
+
+    UserIdentity::create(pool, NewUserIdentity {
+        user_id: user.id,
+        issuer: identity.issuer,
+        subject: identity.subject,
+        email: Some(email),
+    }).await?;
+
+    Ok(user)
+}
+
+/// A hash of a random secret nobody knows; the password reset flow can set
+/// a real one
+pub(crate) fn unusable_password_hash() -> Result<String, AppError> {
+    PasswordUtils::hash_password(&TokenUtils::generate_token())
+        .map_err(|e| AppError::ValidationError(format!("Password hashing failed: {}", e)))
+}
+
+/// Username base from the source's preferred username or the email local
+/// part, reduced to the characters usernames allow
+fn username_base(preferred_username: Option<&str>, email: &str) -> String {
+    let source = preferred_username.unwrap_or_else(|| email.split('@').next().unwrap_or_default());
+
+    let mut base: String = source
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .take(24)
+        .collect();
+    if base.len() < 3 {
+        base = format!("user{}", base);
+    }
+    base
+}
+
+async fn available_username(
+    pool: &Pool<Postgres>,
+    preferred_username: Option<&str>,
+    email: &str,
+) -> Result<String, AppError> {
+    let base = username_base(preferred_username, email);
+    if User::find_by_username(pool, base.clone()).await?.is_none() {
+        return Ok(base);
+    }
+    for suffix in 2..100 {
+        let candidate = format!("{}-{}", base, suffix);
+        if User::find_by_username(pool, candidate.clone()).await?.is_none() {
+            return Ok(candidate);
+        }
+    }
+    Ok(format!("{}-{}", base, &TokenUtils::generate_token()[..5]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_username_base() {
+        assert_eq!(username_base(Some("jane.doe"), "x@uni.edu"), "janedoe");
+        assert_eq!(username_base(None, "s1234567@uni.edu"), "s1234567");
+        assert_eq!(username_base(None, "ab@uni.edu"), "userab");
+        assert_eq!(username_base(Some(&"a".repeat(40)), "x@uni.edu").len(), 24);
+    }
+
+    fn user(password_hash: String, verified: bool, role: &str) -> User {
+        User {
+            id: uuid::Uuid::new_v4(),
+            email: "jdoe@uni.edu".to_string(),
+            username: "jdoe".to_string(),
+            bio: None,
+            image_url: None,
+            created_at: chrono::Utc::now(),
+            password_hash,
+            token_version: 0,
+            email_verified_at: verified.then(chrono::Utc::now),
+            role: role.to_string(),
+            disabled_at: None,
+        }
+    }
+
+    #[test]
+    fn test_email_match() {
+        let passwordless = user(PasswordUtils::unusable_password(), true, "user");
+        assert_eq!(email_match(None, false), EmailMatch::Create);
+        assert_eq!(email_match(None, true), EmailMatch::Create);
+        assert_eq!(email_match(Some(&passwordless), true), EmailMatch::Link);
+        assert_eq!(email_match(Some(&passwordless), false), EmailMatch::Refuse);
+
+        let with_password = user(PasswordUtils::hash_password("StrongPass123!").unwrap(), true, "user");
+        assert_eq!(email_match(Some(&with_password), true), EmailMatch::Refuse);
+
+        let admin = user(PasswordUtils::unusable_password(), true, "admin");
+        assert_eq!(email_match(Some(&admin), true), EmailMatch::Refuse);
+
+        let unverified = user(PasswordUtils::unusable_password(), false, "user");
+        assert_eq!(email_match(Some(&unverified), true), EmailMatch::Refuse);
+    }
+}
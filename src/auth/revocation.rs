@@ -0,0 +1,167 @@
+// src/auth/revocation.rs
+//! Access-token revocation.
+//!
+//! Access tokens are stateless, so two mechanisms cut them short:
+//! - a per-user `token_version` that is bumped on "sign out everywhere" and
+//!   credential changes, compared against the user row the auth middleware
+//!   already loads;
+//! - a denylist for single-token and single-session logout in
+//!   `revoked_access_tokens`, checked on every request so a logout on one
+//!   instance takes effect on all of them. Hits are cached in memory to skip
+//!   the query for tokens that keep being presented. It holds access token
+//!   `jti`s and session ids, checked against `sid`, and the `jti`s of
+//!   redeemed MFA-pending tokens.
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    errors::AppError,
+    models::{
+        refresh_token::RefreshToken,
+        revoked_access_token::RevokedAccessToken,
+        session::Session,
+        user::User,
+    },
+    utils::ACCESS_TOKEN_TTL_MINUTES,
+};
+
+static REVOKED_ACCESS_TOKENS: LazyLock<RevokedTokenCache> = LazyLock::new(RevokedTokenCache::default);
+
+/// In-process set of revoked access token ids and when they expire anyway
+#[derive(Debug, Default)]
+pub struct RevokedTokenCache {
+    entries: RwLock<HashMap<Uuid, DateTime<Utc>>>,
+}
+
+impl RevokedTokenCache {
+    pub fn insert(&self, token_id: Uuid, expires_at: DateTime<Utc>) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        let now = Utc::now();
+        entries.retain(|_, exp| *exp > now);
+        entries.insert(token_id, expires_at);
+    }
+
+    pub fn contains(&self, token_id: &Uuid) -> bool {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        entries.get(token_id).is_some_and(|exp| *exp > Utc::now())
+    }
+}
+
+/// Deny a single access token until it would have expired
+pub async fn revoke_access_token(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    token_id: Uuid,
+) -> Result<(), AppError> {
+    let expires_at = Utc::now() + Duration::minutes(ACCESS_TOKEN_TTL_MINUTES);
+
+    RevokedAccessToken::delete_expired(pool).await?;
+    RevokedAccessToken::create(pool, RevokedAccessToken {
+        jti: token_id,
+        user_id,
+        expires_at,
+    }).await?;
+
+    REVOKED_ACCESS_TOKENS.insert(token_id, expires_at);
+    Ok(())
+}
+
+/// Deny a single-use token from now on. Returns false if it was already
+/// used, so two concurrent requests cannot both redeem it.
+pub async fn consume_single_use_token(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    token_id: Uuid,
+    expires_at: DateTime<Utc>,
+) -> Result<bool, AppError> {
+    RevokedAccessToken::delete_expired(pool).await?;
+    let consumed = RevokedAccessToken::create_if_absent(pool, RevokedAccessToken {
+        jti: token_id,
+        user_id,
+        expires_at,
+    }).await?;
+
+    REVOKED_ACCESS_TOKENS.insert(token_id, expires_at);
+    Ok(consumed)
+}
+
+/// Deny every access token issued to the session until they would have expired
+pub async fn revoke_session_access_tokens(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    session_id: Uuid,
+) -> Result<(), AppError> {
+    revoke_access_token(pool, user_id, session_id).await
+}
+
+/// Whether the token or its session was revoked on any instance
+pub async fn is_revoked(
+    pool: &Pool<Postgres>,
+    token_id: Uuid,
+    session_id: Option<Uuid>,
+) -> Result<bool, sqlx::Error> {
+    if is_access_token_revoked(&token_id) || session_id.as_ref().is_some_and(is_session_revoked) {
+        return Ok(true);
+    }
+
+    let ids: Vec<Uuid> = std::iter::once(token_id).chain(session_id).collect();
+    let revoked = RevokedAccessToken::find_unexpired_by_ids(pool, &ids).await?;
+    for entry in &revoked {
+        REVOKED_ACCESS_TOKENS.insert(entry.jti, entry.expires_at);
+    }
+    Ok(!revoked.is_empty())
+}
+
+pub fn is_access_token_revoked(token_id: &Uuid) -> bool {
+    REVOKED_ACCESS_TOKENS.contains(token_id)
+}
+
+pub fn is_session_revoked(session_id: &Uuid) -> bool {
+    REVOKED_ACCESS_TOKENS.contains(session_id)
+}
+
+/// Warm the in-process denylist from the database at startup
+pub async fn load_revoked_access_tokens(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
+    for revoked in RevokedAccessToken::find_unexpired(pool).await? {
+        REVOKED_ACCESS_TOKENS.insert(revoked.jti, revoked.expires_at);
+    }
+    Ok(())
+}
+
+/// Invalidate every access and refresh token issued to the user so far
+pub async fn revoke_all_tokens(pool: &Pool<Postgres>, user_id: Uuid) -> Result<User, AppError> {
+    let mut conn = pool.acquire().await?;
+    revoke_all_tokens_in(&mut conn, user_id).await
+}
+
+/// `revoke_all_tokens` on the caller's connection or transaction
+pub(crate) async fn revoke_all_tokens_in(conn: &mut sqlx::PgConnection, user_id: Uuid) -> Result<User, AppError> {
+    let user = User::invalidate_tokens(&mut *conn, user_id).await?;
+    Session::revoke_all_except(&mut *conn, user_id, None).await?;
+    RefreshToken::revoke_all_for_user(&mut *conn, user_id).await?;
+    Ok(user)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_revoked_token_cache() {
+        let cache = RevokedTokenCache::default();
+        let revoked = Uuid::new_v4();
+        let expired = Uuid::new_v4();
+
+        cache.insert(revoked, Utc::now() + Duration::minutes(5));
+        cache.insert(expired, Utc::now() - Duration::minutes(1));
+
+        assert!(cache.contains(&revoked));
+        assert!(!cache.contains(&expired));
+        assert!(!cache.contains(&Uuid::new_v4()));
+    }
+}
@@ -0,0 +1,197 @@
+// src/auth/session.rs
+//! Refresh-token sessions.
+//!
+//! Every login starts a session, recorded with the device it came from, and
+//! a refresh token family whose id is the session id. Each refresh rotates
+//! the presented token into a new one of the same family, and presenting a
+//! token that was already rotated or revoked revokes the whole session.
+//!
+//! Access tokens carry the session id as `sid`, so revoking a session also
+//! denies its outstanding access tokens.
+
+use chrono::{Duration, Utc};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    auth::{account_deletion::cancel_account_deletion, revocation::revoke_session_access_tokens},
+    errors::AppError,
+    middleware::ClientInfo,
+    models::{
+        refresh_token::{NewRefreshToken, RefreshToken},
+        session::{NewSession, Session},
+        user::User,
+    },
+    utils::{JwtManager, REFRESH_TOKEN_TTL_MINUTES},
+};
+
+#[derive(Debug)]
+pub struct SessionTokens {
+    pub access_token: String,
+    pub refresh_token: String,
+}
+
+/// Start a session for a freshly authenticated user and issue its first
+/// access/refresh pair. Disabled accounts cannot sign in; signing in cancels
+/// a pending account deletion.
+pub async fn start_session(
+    pool: &Pool<Postgres>,
+    jwt_manager: &JwtManager,
+    user: &User,
+    client: &ClientInfo,
+) -> Result<SessionTokens, AppError> {
+    if user.is_disabled() {
+        return Err(AppError::PermissionDenied("This account has been disabled".to_string()));
+    }
+    cancel_account_deletion(pool, user.id).await?;
+
+    let session = Session::create(pool, NewSession {
+        id: Uuid::new_v4(),
+        user_id: user.id,
+        user_agent: client.user_agent.clone(),
+        ip_address: client.ip_address.clone(),
+        expires_at: Utc::now() + Duration::minutes(REFRESH_TOKEN_TTL_MINUTES),
+    }).await?;
+
+    let (_, tokens) = issue_tokens(pool, jwt_manager, user, session.id).await?;
+    Ok(tokens)
+}
+
+/// Exchange a refresh token for a new pair, revoking the presented token
+pub async fn rotate_session(
+    pool: &Pool<Postgres>,
+    jwt_manager: &JwtManager,
+    refresh_token: &str,
+) -> Result<(User, SessionTokens), AppError> {
+    let stored = find_presented_token(pool, jwt_manager, refresh_token).await?;
+
+    if stored.revoked_at.is_some() {
+        tracing::warn!(
+            user_id = %stored.user_id,
+            family_id = %stored.family_id,
+            "refresh token reuse detected, revoking token family"
+        );
+        end_session_if_active(pool, stored.user_id, stored.family_id).await?;
+        return Err(AppError::Unauthorized);
+    }
+
+    if stored.expires_at <= Utc::now() {
+        return Err(AppError::Unauthorized);
+    }
+
+    // Verify user still exists
+    let user = User::find_by_id(pool, stored.user_id)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    let (new_token_id, tokens) = issue_tokens(pool, jwt_manager, &user, stored.family_id).await?;
+
+    // Another request rotated the same token between our read and this update
+    if !RefreshToken::revoke(pool, stored.id, Some(new_token_id)).await? {
+        end_session_if_active(pool, stored.user_id, stored.family_id).await?;
+        return Err(AppError::Unauthorized);
+    }
+
+    Session::touch(pool, stored.family_id, Utc::now() + Duration::minutes(REFRESH_TOKEN_TTL_MINUTES)).await?;
+
+    Ok((user, tokens))
+}
+
+/// End the session of the presented refresh token on behalf of its owner
+pub async fn end_session(
+    pool: &Pool<Postgres>,
+    jwt_manager: &JwtManager,
+    user_id: Uuid,
+    refresh_token: &str,
+) -> Result<(), AppError> {
+    let stored = find_presented_token(pool, jwt_manager, refresh_token).await?;
+
+    if stored.user_id != user_id {
+        return Err(AppError::Forbidden);
+    }
+
+    end_session_if_active(pool, user_id, stored.family_id).await
+}
+
+/// Active sessions of the user, most recently used first
+pub async fn list_sessions(pool: &Pool<Postgres>, user_id: Uuid) -> Result<Vec<Session>, AppError> {
+    Ok(Session::list_active_by_user(pool, user_id).await?)
+}
+
+/// Sign one of the user's sessions out, invalidating its refresh tokens and
+/// outstanding access tokens
+pub async fn revoke_session(pool: &Pool<Postgres>, user_id: Uuid, session_id: Uuid) -> Result<(), AppError> {
+    if !Session::revoke(pool, session_id, user_id).await? {
+        return Err(AppError::NotFound);
+    }
+    RefreshToken::revoke_family(pool, session_id).await?;
+    revoke_session_access_tokens(pool, user_id, session_id).await
+}
+
+/// Like `revoke_session`, but a session that was already revoked is not an error
+async fn end_session_if_active(pool: &Pool<Postgres>, user_id: Uuid, session_id: Uuid) -> Result<(), AppError> {
+    match revoke_session(pool, user_id, session_id).await {
+        Err(AppError::NotFound) => Ok(()),
+        result => result,
+    }
+}
+
+/// Sign every session of the user out except `current`
+pub async fn revoke_other_sessions(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    current: Option<Uuid>,
+) -> Result<usize, AppError> {
+    let revoked = Session::revoke_all_except(pool, user_id, current).await?;
+    for session_id in &revoked {
+        RefreshToken::revoke_family(pool, *session_id).await?;
+        revoke_session_access_tokens(pool, user_id, *session_id).await?;
+    }
+    Ok(revoked.len())
+}
+
+async fn find_presented_token(
+    pool: &Pool<Postgres>,
+    jwt_manager: &JwtManager,
+    refresh_token: &str,
+) -> Result<RefreshToken, AppError> {
+    let claims = jwt_manager
+        .verify_refresh_token(refresh_token)
+        .map_err(|_| AppError::Unauthorized)?;
+
+    let user_id = claims.user_id().map_err(|_| AppError::Unauthorized)?;
+    let token_id = claims.token_id().map_err(|_| AppError::Unauthorized)?;
+
+    let stored = RefreshToken::find_by_id(pool, token_id)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    if stored.user_id != user_id {
+        return Err(AppError::Unauthorized);
+    }
+
+    Ok(stored)
+}
+
+async fn issue_tokens(
+    pool: &Pool<Postgres>,
+    jwt_manager: &JwtManager,
+    user: &User,
+    session_id: Uuid,
+) -> Result<(Uuid, SessionTokens), AppError> {
+    let token_id = Uuid::new_v4();
+
+    RefreshToken::create(pool, NewRefreshToken {
+        id: token_id,
+        family_id: session_id,
+        user_id: user.id,
+        expires_at: Utc::now() + Duration::minutes(REFRESH_TOKEN_TTL_MINUTES),
+    }).await?;
+
+    let access_token = jwt_manager.generate_access_token(user.id, user.token_version, session_id)
+        .map_err(|e| AppError::ValidationError(format!("Token generation failed: {}", e)))?;
+    let refresh_token = jwt_manager.generate_refresh_token(user.id, token_id)
+        .map_err(|e| AppError::ValidationError(format!("Token generation failed: {}", e)))?;
+
+    Ok((token_id, SessionTokens { access_token, refresh_token }))
+}
@@ -0,0 +1,330 @@
+// src/auth/throttle.rs
+//! Sign-in brute-force protection.
+//!
+//! Attempts are counted per account and per client IP. The account key is
+//! the user id once the login resolves to a user, so the password and
+//! second-factor steps share one budget; otherwise it is the normalized
+//! login, so unknown names are throttled the same as real ones.
+//! Each attempt is counted before the credentials are checked, so parallel
+//! guesses cannot all pass on the same count, and is taken back if it
+//! succeeds. Past a number of free attempts each further one locks the key
+//! for an exponentially growing period. Lockouts and the first successful
+//! sign-in after one are recorded as `auth_events` for admins to review.
+//!
+//! Password reset requests use the same table under their own scopes: a few
+//! per address and per client IP each window, registered or not.
+
+use chrono::{Duration, Utc};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    errors::AppError,
+    models::{
+        auth_event::{AuthEvent, NewAuthEvent},
+        login_throttle::LoginThrottle,
+    },
+};
+
+/// Failures older than this no longer count
+const FAILURE_WINDOW_SECONDS: i64 = 24 * 60 * 60;
+const BASE_LOCKOUT_SECONDS: i64 = 30;
+const MAX_LOCKOUT_SECONDS: i64 = 60 * 60;
+/// Reset requests are allowed a fixed number per window
+const RESET_WINDOW_SECONDS: i64 = 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ThrottleScope {
+    Account,
+    Ip,
+    ResetAccount,
+    ResetIp,
+}
+
+impl ThrottleScope {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ThrottleScope::Account => "account",
+            ThrottleScope::Ip => "ip",
+            ThrottleScope::ResetAccount => "reset_account",
+            ThrottleScope::ResetIp => "reset_ip",
+        }
+    }
+
+    /// Failures allowed before the first lockout. An address is shared by
+    /// many users behind NAT so it gets more slack than a single account.
+    fn free_attempts(&self) -> i32 {
+        match self {
+            ThrottleScope::Account => 5,
+            ThrottleScope::Ip => 20,
+            ThrottleScope::ResetAccount => 3,
+            ThrottleScope::ResetIp => 10,
+        }
+    }
+}
+
+/// How long to lock a key after its `failed_count`-th consecutive failure
+fn lockout_duration(failed_count: i32, free_attempts: i32) -> Option<Duration> {
+    if failed_count < free_attempts {
+        return None;
+    }
+    let exponent = (failed_count - free_attempts).min(16) as u32;
+    let seconds = (BASE_LOCKOUT_SECONDS << exponent).min(MAX_LOCKOUT_SECONDS);
+    Some(Duration::seconds(seconds))
+}
+
+fn normalize_login(login: &str) -> String {
+    login.trim().to_lowercase()
+}
+
+fn account_key(user_id: Option<Uuid>, login: &str) -> String {
+    user_id.map_or_else(|| normalize_login(login), |id| id.to_string())
+}
+
+fn throttle_keys(user_id: Option<Uuid>, login: &str, ip_address: Option<&str>) -> Vec<(ThrottleScope, String)> {
+    let mut keys = vec![(ThrottleScope::Account, account_key(user_id, login))];
+    if let Some(ip) = ip_address {
+        keys.push((ThrottleScope::Ip, ip.to_string()));
+    }
+    keys
+}
+
+/// An attempt counted against its throttle keys, to be settled with
+/// [`record_login_failure`] or [`record_login_success`]
+#[derive(Debug)]
+pub struct LoginAttempt {
+    counted: Vec<(ThrottleScope, LoginThrottle)>,
+    ip_address: Option<String>,
+}
+
+/// Count the attempt against the account and the client address, rejecting
+/// it while either is locked. `user_id` is the user the login belongs to, if
+/// known before the credentials are checked.
+pub async fn begin_login_attempt(
+    pool: &Pool<Postgres>,
+    user_id: Option<Uuid>,
+    login: &str,
+    ip_address: Option<&str>,
+) -> Result<LoginAttempt, AppError> {
+    let mut attempt = LoginAttempt { counted: Vec::new(), ip_address: ip_address.map(str::to_string) };
+
+    for (scope, key) in throttle_keys(user_id, login, ip_address) {
+        // Locked for the longest period until the count is known below
+        let counted = LoginThrottle::record_attempt(
+            pool, scope.as_str(), &key, FAILURE_WINDOW_SECONDS, scope.free_attempts(), MAX_LOCKOUT_SECONDS,
+        ).await?;
+
+        let Some(mut throttle) = counted else {
+            refund(pool, &attempt).await?;
+            return Err(locked_error(pool, scope, &key, "failed sign-in attempts").await?);
+        };
+        if let Some(duration) = lockout_duration(throttle.failed_count, scope.free_attempts()) {
+            let locked_until = Utc::now() + duration;
+            LoginThrottle::lock_until(pool, scope.as_str(), &key, locked_until).await?;
+            throttle.locked_until = Some(locked_until);
+        }
+        attempt.counted.push((scope, throttle));
+    }
+    Ok(attempt)
+}
+
+fn reset_keys(email: &str, ip_address: Option<&str>) -> Vec<(ThrottleScope, String)> {
+    let mut keys = vec![(ThrottleScope::ResetAccount, normalize_login(email))];
+    if let Some(ip) = ip_address {
+        keys.push((ThrottleScope::ResetIp, ip.to_string()));
+    }
+    keys
+}
+
+/// Count a password reset request against the address and the client IP,
+/// rejecting it once either has used up its requests for the window
+pub async fn begin_password_reset_request(
+    pool: &Pool<Postgres>,
+    email: &str,
+    ip_address: Option<&str>,
+) -> Result<(), AppError> {
+    for (scope, key) in reset_keys(email, ip_address) {
+        let counted = LoginThrottle::record_attempt(
+            pool, scope.as_str(), &key, RESET_WINDOW_SECONDS, scope.free_attempts(), RESET_WINDOW_SECONDS,
+        ).await?;
+        if counted.is_none() {
+            return Err(locked_error(pool, scope, &key, "password reset requests").await?);
+        }
+    }
+    Ok(())
+}
+
+async fn locked_error(pool: &Pool<Postgres>, scope: ThrottleScope, key: &str, what: &str) -> Result<AppError, AppError> {
+    let locked_until = LoginThrottle::find(pool, scope.as_str(), key)
+        .await?
+        .and_then(|throttle| throttle.locked_until)
+        .unwrap_or_else(Utc::now);
+    let seconds = (locked_until - Utc::now()).num_seconds().max(1);
+    Ok(AppError::TooManyRequests(format!(
+        "Too many {}. Try again in {} seconds", what, seconds
+    )))
+}
+
+/// Take back the attempt's count on every key
+async fn refund(pool: &Pool<Postgres>, attempt: &LoginAttempt) -> Result<(), AppError> {
+    for (scope, throttle) in &attempt.counted {
+        LoginThrottle::refund_attempt(pool, scope.as_str(), &throttle.key, scope.free_attempts()).await?;
+    }
+    Ok(())
+}
+
+/// The attempt was counted already; record the lockouts it caused
+pub async fn record_login_failure(
+    pool: &Pool<Postgres>,
+    attempt: &LoginAttempt,
+    user_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    for (scope, throttle) in &attempt.counted {
+        if throttle.locked_until.is_none() {
+            continue;
+        }
+        tracing::warn!(scope = scope.as_str(), key = %throttle.key, failures = throttle.failed_count, "sign-in locked");
+
+        AuthEvent::create(pool, NewAuthEvent {
+            user_id: if *scope == ThrottleScope::Account { user_id } else { None },
+            event_type: format!("{}_locked", scope.as_str()),
+            subject: throttle.key.clone(),
+            ip_address: attempt.ip_address.clone(),
+        }).await?;
+    }
+    Ok(())
+}
+
+/// The first factor was right but a second one is pending. The user's
+/// counter is kept for the second step; a counter keyed by a login that did
+/// not resolve to the user beforehand is cleared, nothing else would.
+pub async fn record_first_factor_success(
+    pool: &Pool<Postgres>,
+    attempt: &LoginAttempt,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    for (scope, throttle) in &attempt.counted {
+        if *scope == ThrottleScope::Account && throttle.key != user_id.to_string() {
+            LoginThrottle::clear(pool, scope.as_str(), &throttle.key).await?;
+        } else {
+            LoginThrottle::refund_attempt(pool, scope.as_str(), &throttle.key, scope.free_attempts()).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Forget the account's failures after a successful sign-in, under the
+/// user id and the login the attempt was counted against. Address failures
+/// are kept, one valid account must not reset an attacker's budget; only
+/// this attempt is taken back.
+pub async fn record_login_success(
+    pool: &Pool<Postgres>,
+    attempt: &LoginAttempt,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    for (scope, throttle) in &attempt.counted {
+        if *scope != ThrottleScope::Account {
+            LoginThrottle::refund_attempt(pool, scope.as_str(), &throttle.key, scope.free_attempts()).await?;
+            continue;
+        }
+
+        let user_key = user_id.to_string();
+        let mut keys = vec![throttle.key.as_str()];
+        if throttle.key != user_key {
+            keys.push(&user_key);
+        }
+        for key in keys {
+            // Locked by an earlier failure, not just by counting this attempt
+            let counted_now = if key == throttle.key { 1 } else { 0 };
+            if let Some(cleared) = LoginThrottle::clear(pool, scope.as_str(), key).await?
+                && cleared.failed_count - counted_now >= scope.free_attempts()
+            {
+                AuthEvent::create(pool, NewAuthEvent {
+                    user_id: Some(user_id),
+                    event_type: "account_unlocked".to_string(),
+                    subject: key.to_string(),
+                    ip_address: attempt.ip_address.clone(),
+                }).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_free_attempts_are_not_locked() {
+        for failed_count in 1..5 {
+            assert!(lockout_duration(failed_count, 5).is_none());
+        }
+    }
+
+    #[test]
+    fn test_lockout_grows_exponentially() {
+        assert_eq!(lockout_duration(5, 5), Some(Duration::seconds(30)));
+        assert_eq!(lockout_duration(6, 5), Some(Duration::seconds(60)));
+        assert_eq!(lockout_duration(7, 5), Some(Duration::seconds(120)));
+    }
+
+    #[test]
+    fn test_lockout_is_capped() {
+        assert_eq!(lockout_duration(12, 5), Some(Duration::seconds(MAX_LOCKOUT_SECONDS)));
+        assert_eq!(lockout_duration(i32::MAX, 5), Some(Duration::seconds(MAX_LOCKOUT_SECONDS)));
+    }
+
+    #[test]
+    fn test_account_key_is_normalized() {
+        let keys = throttle_keys(None, "  Alice@Example.COM ", Some("10.0.0.1"));
+        assert_eq!(keys[0], (ThrottleScope::Account, "alice@example.com".to_string()));
+        assert_eq!(keys[1], (ThrottleScope::Ip, "10.0.0.1".to_string()));
+        assert_eq!(throttle_keys(None, "a@b.c", None).len(), 1);
+    }
+
+    #[test]
+    fn test_reset_keys_are_separate_from_login_keys() {
+        let keys = reset_keys(" Alice@Example.COM", Some("10.0.0.1"));
+        assert_eq!(keys[0], (ThrottleScope::ResetAccount, "alice@example.com".to_string()));
+        assert_eq!(keys[1], (ThrottleScope::ResetIp, "10.0.0.1".to_string()));
+        // Scopes are stored in a VARCHAR(16) column
+        for (scope, _) in keys {
+            assert!(scope.as_str().len() <= 16);
+            assert_ne!(scope.as_str(), ThrottleScope::Account.as_str());
+            assert_ne!(scope.as_str(), ThrottleScope::Ip.as_str());
+        }
+    }
+
+    #[test]
+    fn test_account_key_prefers_user_id() {
+        let user_id = Uuid::new_v4();
+        // An LDAP login and the user's email share the user's budget
+        assert_eq!(account_key(Some(user_id), "jdoe"), user_id.to_string());
+        assert_eq!(account_key(Some(user_id), "jdoe@uni.edu"), account_key(Some(user_id), "jdoe"));
+        assert_eq!(account_key(None, " JDoe "), "jdoe");
+    }
+
+    #[test]
+    fn test_spoofed_forwarded_for_does_not_change_ip_key() {
+        use axum::http::HeaderMap;
+        use crate::middleware::client_info::client_ip;
+
+        let peer: std::net::IpAddr = "203.0.113.7".parse().unwrap();
+        let proxy: std::net::IpAddr = "10.0.0.2".parse().unwrap();
+        let ip_key = |forwarded_for: &str, peer, trusted: &[std::net::IpAddr]| {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-forwarded-for", forwarded_for.parse().unwrap());
+            let ip = client_ip(&headers, Some(peer), trusted).map(|ip| ip.to_string());
+            throttle_keys(None, "a@b.c", ip.as_deref()).pop().unwrap()
+        };
+
+        // Straight from the client, every forged header maps to the peer
+        assert_eq!(ip_key("1.1.1.1", peer, &[]), ip_key("2.2.2.2", peer, &[]));
+        assert_eq!(ip_key("1.1.1.1", peer, &[]).1, "203.0.113.7");
+
+        // Behind a trusted proxy only the hop it appended counts
+        assert_eq!(ip_key("1.1.1.1, 203.0.113.7", proxy, &[proxy]).1, "203.0.113.7");
+        assert_eq!(ip_key("2.2.2.2, 203.0.113.7", proxy, &[proxy]).1, "203.0.113.7");
+    }
+}
@@ -0,0 +1,83 @@
+// src/auth/verification.rs
+//! Email address verification.
+//!
+//! A verification link is mailed on signup and can be re-sent. When
+//! `REQUIRE_EMAIL_VERIFICATION` is enabled, unverified users cannot create
+//! or join groups.
+
+use chrono::{Duration, Utc};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    errors::AppError,
+    mail::{self, MailMessage},
+    models::{
+        email_verification_token::{EmailVerificationToken, NewEmailVerificationToken},
+        user::User,
+    },
+    utils::TokenUtils,
+};
+
+pub const EMAIL_VERIFICATION_TTL_MINUTES: i64 = 24 * 60;
+
+/// Issue a new verification token for the user and email the link
+pub async fn send_verification_email(pool: &Pool<Postgres>, user: &User) -> Result<(), AppError> {
+    let token = TokenUtils::generate_token();
+
+    EmailVerificationToken::invalidate_for_user(pool, user.id).await?;
+    EmailVerificationToken::create(pool, NewEmailVerificationToken {
+        user_id: user.id,
+        token_hash: TokenUtils::hash_token(&token),
+        expires_at: Utc::now() + Duration::minutes(EMAIL_VERIFICATION_TTL_MINUTES),
+    }).await?;
+
+    let message = MailMessage {
+        to: user.email.clone(),
+        subject: "Verify your StudySphere email address".to_string(),
+        body: format!(
+            "Hi {},\n\nPlease confirm your email address by opening the link below. It expires in 24 hours.\n\n{}/verify-email?token={}\n\nIf you did not create a StudySphere account, you can ignore this email.",
+            user.username,
+            mail::frontend_url(),
+            token
+        ),
+    };
+
+    mail::mailer_from_env()?.send(message).await?;
+    Ok(())
+}
+
+/// Consume a verification token and mark the owner's address as verified
+pub async fn verify_email(pool: &Pool<Postgres>, token: &str) -> Result<User, AppError> {
+    let verification = EmailVerificationToken::consume(pool, TokenUtils::hash_token(token))
+        .await?
+        .ok_or(AppError::ValidationError("Invalid or expired verification token".to_string()))?;
+
+    let user = User::mark_email_verified(pool, verification.user_id).await?;
+    Ok(user)
+}
+
+pub fn email_verification_required() -> bool {
+    std::env::var("REQUIRE_EMAIL_VERIFICATION")
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Reject unverified users when `REQUIRE_EMAIL_VERIFICATION` is enabled
+pub async fn require_verified_email(pool: &Pool<Postgres>, user_id: Uuid) -> Result<(), AppError> {
+    if !email_verification_required() {
+        return Ok(());
+    }
+
+    let user = User::find_by_id(pool, user_id)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    if user.email_verified_at.is_none() {
+        return Err(AppError::PermissionDenied(
+            "Please verify your email address first".to_string(),
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+//! Import users, groups and materials from a Django `dumpdata` export.
+//!
+//! ```text
+//! python manage.py dumpdata users groups_courses materials --indent 2 > dump.json
+//! cargo run --bin import_django -- dump.json [--dry-run]
+//! ```
+//!
+//! Everything is imported in one transaction; `--dry-run` rolls it back
+//! after printing the report.
+
+use anyhow::Context;
+use rusty_studyshpere::{db, import::django};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+    let Some(path) = args.iter().find(|arg| !arg.starts_with("--")) else {
+        anyhow::bail!("usage: import_django <dump.json> [--dry-run]");
+    };
+
+    let json = std::fs::read_to_string(path).with_context(|| format!("Cannot read {}", path))?;
+    let dump = django::parse_dump(&json)?;
+    for model in &dump.unknown_models {
+        eprintln!("warning: ignoring records of unknown model {}", model);
+    }
+
+    let pool = db::init_db_pool().await?;
+    let mut tx = pool.begin().await?;
+    let report = django::import_dump(&mut tx, &dump).await?;
+
+    if dry_run {
+        tx.rollback().await?;
+        println!("Dry run, nothing was saved.");
+    } else {
+        tx.commit().await?;
+    }
+    print!("{}", report);
+    Ok(())
+}
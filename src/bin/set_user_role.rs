@@ -0,0 +1,47 @@
+//! Grant or revoke the site admin role from the command line, e.g. to
+//! create the first admin.
+//!
+//! ```text
+//! cargo run --bin set_user_role -- alice@example.com admin
+//! ```
+//!
+//! The change is recorded in the admin audit log without an actor.
+
+use rusty_studyshpere::{
+    auth::admin::{record_admin_action, AdminAction, AuditTarget},
+    db,
+    models::user::User,
+    permissions::PlatformRole,
+    utils::EmailUtils,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [email, role] = args.as_slice() else {
+        anyhow::bail!("usage: set_user_role <email> <user|admin>");
+    };
+    let Some(role) = PlatformRole::parse(&role.to_lowercase()) else {
+        anyhow::bail!("role must be user or admin");
+    };
+
+    let pool = db::init_db_pool().await?;
+    let Some(user) = User::find_by_email(&pool, EmailUtils::normalize_email(email)).await? else {
+        anyhow::bail!("no user with email {}", email);
+    };
+
+    let mut tx = pool.begin().await?;
+    let updated = User::update_role(&mut *tx, user.id, role.as_str().to_string()).await?;
+    record_admin_action(
+        &mut *tx,
+        None,
+        AdminAction::ChangeRole,
+        AuditTarget::User(user.id),
+        Some(serde_json::json!({ "from": user.role, "to": updated.role, "source": "cli" })),
+        None,
+    ).await?;
+    tx.commit().await?;
+
+    println!("{} is now {}", updated.email, updated.role);
+    Ok(())
+}
@@ -21,6 +21,12 @@ pub enum AppError {
     Unauthorized,
     #[error("Forbidden")]
     Forbidden,
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
     #[error("Anyhow error: {0}")]
     Anyhow(#[from] anyhow::Error),
 }
@@ -38,6 +44,9 @@ impl IntoResponse for AppError {
             AppError::ValidationError(msg) => (StatusCode::BAD_REQUEST, msg),
             AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized".to_string()),
             AppError::Forbidden => (StatusCode::FORBIDDEN, "Forbidden".to_string()),
+            AppError::PermissionDenied(msg) => (StatusCode::FORBIDDEN, msg),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            AppError::TooManyRequests(msg) => (StatusCode::TOO_MANY_REQUESTS, msg),
             AppError::Anyhow(e) => {
                 (StatusCode::INTERNAL_SERVER_ERROR, format!("Internal server error: {}", e))
             }
@@ -0,0 +1,119 @@
+// src/handlers/access_token.rs
+use axum::{
+    extract::{State, Path, Json},
+    http::StatusCode,
+};
+use sqlx::{Pool, Postgres};
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{
+    auth::{self, TokenScope},
+    errors::AppError,
+    middleware::AuthenticatedUser,
+    models::personal_access_token::PersonalAccessToken,
+};
+
+const MAX_TOKEN_NAME_LENGTH: usize = 100;
+const MAX_TOKEN_EXPIRY_DAYS: i64 = 365;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateAccessTokenRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccessTokenResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<PersonalAccessToken> for AccessTokenResponse {
+    fn from(token: PersonalAccessToken) -> Self {
+        Self {
+            id: token.id,
+            name: token.name,
+            scopes: token.scopes,
+            expires_at: token.expires_at,
+            last_used_at: token.last_used_at,
+            created_at: token.created_at,
+        }
+    }
+}
+
+/// Returned once on creation, the plaintext token is not stored
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreatedAccessTokenResponse {
+    #[serde(flatten)]
+    pub token: AccessTokenResponse,
+    pub access_token: String,
+}
+
+pub async fn list_access_tokens_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: AuthenticatedUser,
+) -> Result<Json<Vec<AccessTokenResponse>>, AppError> {
+    let tokens = PersonalAccessToken::list_by_user(&pool, user.id).await?;
+    Ok(Json(tokens.into_iter().map(AccessTokenResponse::from).collect()))
+}
+
+pub async fn create_access_token_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: AuthenticatedUser,
+    Json(payload): Json<CreateAccessTokenRequest>,
+) -> Result<(StatusCode, Json<CreatedAccessTokenResponse>), AppError> {
+    let name = payload.name.trim().to_string();
+    if name.is_empty() || name.len() > MAX_TOKEN_NAME_LENGTH {
+        return Err(AppError::ValidationError(format!(
+            "Token name must be between 1 and {} characters", MAX_TOKEN_NAME_LENGTH
+        )));
+    }
+
+    if payload.scopes.is_empty() {
+        return Err(AppError::ValidationError("At least one scope is required".to_string()));
+    }
+    let mut scopes = Vec::with_capacity(payload.scopes.len());
+    for scope in &payload.scopes {
+        let scope = TokenScope::parse(scope)
+            .ok_or_else(|| AppError::ValidationError(format!("Unknown scope: {}", scope)))?;
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+
+    let expires_at = match payload.expires_in_days {
+        Some(days) if (1..=MAX_TOKEN_EXPIRY_DAYS).contains(&days) => Some(Utc::now() + Duration::days(days)),
+        Some(_) => {
+            return Err(AppError::ValidationError(format!(
+                "Token expiry must be between 1 and {} days", MAX_TOKEN_EXPIRY_DAYS
+            )));
+        }
+        None => None,
+    };
+
+    let (stored, access_token) =
+        auth::create_personal_access_token(&pool, user.id, name, &scopes, expires_at).await?;
+
+    Ok((StatusCode::CREATED, Json(CreatedAccessTokenResponse {
+        token: stored.into(),
+        access_token,
+    })))
+}
+
+pub async fn revoke_access_token_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: AuthenticatedUser,
+    Path(token_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    if !PersonalAccessToken::delete(&pool, token_id, user.id).await? {
+        return Err(AppError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,255 @@
+// src/handlers/account.rs
+use std::io::{Cursor, Write};
+
+use axum::{
+    extract::{State, Json, Query},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use sqlx::{Pool, Postgres};
+use serde::{Serialize, Deserialize};
+use chrono::{DateTime, Duration, Utc};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::{
+    auth,
+    errors::AppError,
+    handlers::auth::UserResponse,
+    middleware::AuthenticatedUser,
+    models::{
+        account_deletion::AccountDeletion,
+        comment::Comment,
+        group_member::GroupMember,
+        join_request::JoinRequest,
+        material::Material,
+        session::Session,
+        user::User,
+    },
+    utils::PasswordUtils,
+};
+
+/// How recently the session must have signed in to delete the account
+/// without entering the password
+const RECENT_SIGN_IN_MINUTES: i64 = 10;
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    /// `json` (default) or `zip`
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountExport {
+    pub exported_at: DateTime<Utc>,
+    pub profile: UserResponse,
+    pub memberships: Vec<GroupMember>,
+    pub join_requests: Vec<JoinRequest>,
+    pub materials: Vec<Material>,
+    pub comments: Vec<Comment>,
+    pub pending_deletion: Option<AccountDeletion>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteAccountRequest {
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountDeletionResponse {
+    pub message: String,
+    pub purge_after: DateTime<Utc>,
+}
+
+pub async fn export_me_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: AuthenticatedUser,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, AppError> {
+    let zip_requested = match query.format.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("json") => false,
+        Some("zip") => true,
+        Some(_) => return Err(AppError::ValidationError("format must be json or zip".to_string())),
+    };
+
+    let user = User::find_by_id(&pool, user.id)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    let export = AccountExport {
+        exported_at: Utc::now(),
+        memberships: GroupMember::find_by_user(&pool, user.id).await?,
+        join_requests: JoinRequest::find_by_user(&pool, user.id).await?,
+        materials: Material::find_by_creator(&pool, user.id).await?,
+        comments: Comment::find_by_user(&pool, user.id).await?,
+        pending_deletion: AccountDeletion::find_by_user(&pool, user.id).await?,
+        profile: user.into(),
+    };
+
+    let file_stem = format!("studysphere-{}-{}", export.profile.username, export.exported_at.format("%Y%m%d"));
+    let (content_type, file_name, body) = if zip_requested {
+        ("application/zip", format!("{}.zip", file_stem), export_zip(&export)?)
+    } else {
+        let json = serde_json::to_vec_pretty(&export).map_err(anyhow::Error::from)?;
+        ("application/json", format!("{}.json", file_stem), json)
+    };
+
+    let disposition = HeaderValue::from_str(&format!("attachment; filename=\"{}\"", file_name))
+        .map_err(anyhow::Error::from)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static(content_type)),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    ).into_response())
+}
+
+/// One JSON file per section, so each can be opened on its own
+fn export_zip(export: &AccountExport) -> Result<Vec<u8>, AppError> {
+    fn add_file<T: Serialize>(zip: &mut ZipWriter<Cursor<Vec<u8>>>, name: &str, value: &T) -> anyhow::Result<()> {
+        zip.start_file(name, SimpleFileOptions::default())?;
+        zip.write_all(&serde_json::to_vec_pretty(value)?)?;
+        Ok(())
+    }
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    add_file(&mut zip, "profile.json", &export.profile)?;
+    add_file(&mut zip, "memberships.json", &export.memberships)?;
+    add_file(&mut zip, "join_requests.json", &export.join_requests)?;
+    add_file(&mut zip, "materials.json", &export.materials)?;
+    add_file(&mut zip, "comments.json", &export.comments)?;
+    if let Some(deletion) = &export.pending_deletion {
+        add_file(&mut zip, "pending_deletion.json", deletion)?;
+    }
+
+    let archive = zip.finish().map_err(anyhow::Error::from)?;
+    Ok(archive.into_inner())
+}
+
+/// Check the user really means to delete the account: the password if one
+/// was given, otherwise a session that signed in at `signed_in_at`, recently
+fn confirm_deletion(
+    password: Option<&str>,
+    password_hash: &str,
+    signed_in_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    if let Some(password) = password {
+        let is_valid = PasswordUtils::verify_password(password, password_hash)
+            .map_err(|e| AppError::ValidationError(format!("Password verification failed: {}", e)))?;
+        if !is_valid {
+            return Err(AppError::ValidationError("Password is incorrect".to_string()));
+        }
+        return Ok(());
+    }
+
+    let recent = signed_in_at.is_some_and(|at| at > now - Duration::minutes(RECENT_SIGN_IN_MINUTES));
+    if !recent {
+        return Err(AppError::PermissionDenied(
+            "Confirm with your password or sign in again to delete your account".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Schedule the account for deletion. Needs the password, or a session that
+/// signed in within the last few minutes for accounts without one (OIDC,
+/// LDAP).
+pub async fn delete_me_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: AuthenticatedUser,
+    headers: HeaderMap,
+    payload: Option<Json<DeleteAccountRequest>>,
+) -> Result<(StatusCode, HeaderMap, Json<AccountDeletionResponse>), AppError> {
+    let session_id = user.session_id;
+    let user = User::find_by_id(&pool, user.id)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    let password = payload.and_then(|Json(payload)| payload.password);
+    let signed_in_at = match (&password, session_id) {
+        (None, Some(session_id)) => Session::find_by_id(&pool, session_id).await?.map(|s| s.created_at),
+        _ => None,
+    };
+    confirm_deletion(password.as_deref(), &user.password_hash, signed_in_at, Utc::now())?;
+
+    let deletion = auth::schedule_account_deletion(&pool, &user).await?;
+
+    let cookies = if auth::has_cookie_session(&headers) { auth::clear_session_cookies() } else { HeaderMap::new() };
+    Ok((StatusCode::ACCEPTED, cookies, Json(AccountDeletionResponse {
+        message: "Your account will be deleted. Sign in before then to cancel.".to_string(),
+        purge_after: deletion.purge_after,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use uuid::Uuid;
+    use zip::ZipArchive;
+
+    use super::*;
+
+    fn export(pending_deletion: Option<AccountDeletion>) -> AccountExport {
+        AccountExport {
+            exported_at: Utc::now(),
+            profile: UserResponse {
+                id: Uuid::new_v4(),
+                email: "ann@example.com".to_string(),
+                username: "ann".to_string(),
+                bio: None,
+                image_url: None,
+                email_verified_at: None,
+                created_at: Utc::now(),
+            },
+            memberships: Vec::new(),
+            join_requests: Vec::new(),
+            materials: Vec::new(),
+            comments: Vec::new(),
+            pending_deletion,
+        }
+    }
+
+    #[test]
+    fn test_export_json_sections() {
+        let json = serde_json::to_value(export(None)).unwrap();
+        let mut keys: Vec<&str> = json.as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, [
+            "comments", "exported_at", "join_requests", "materials", "memberships", "pending_deletion", "profile",
+        ]);
+        assert_eq!(json["profile"]["username"], "ann");
+        assert!(json["profile"].get("password_hash").is_none());
+    }
+
+    #[test]
+    fn test_export_zip_files() {
+        let mut archive = ZipArchive::new(Cursor::new(export_zip(&export(None)).unwrap())).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(names, ["comments.json", "join_requests.json", "materials.json", "memberships.json", "profile.json"]);
+
+        let mut profile = String::new();
+        archive.by_name("profile.json").unwrap().read_to_string(&mut profile).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&profile).unwrap()["email"], "ann@example.com");
+
+        let deletion = AccountDeletion { user_id: Uuid::new_v4(), requested_at: Utc::now(), purge_after: Utc::now() };
+        let archive = ZipArchive::new(Cursor::new(export_zip(&export(Some(deletion))).unwrap())).unwrap();
+        assert!(archive.file_names().any(|name| name == "pending_deletion.json"));
+    }
+
+    #[test]
+    fn test_deletion_needs_password_or_recent_sign_in() {
+        let hash = PasswordUtils::hash_password("StrongPass123!").unwrap();
+        let now = Utc::now();
+        let recent = Some(now - Duration::minutes(1));
+        let stale = Some(now - Duration::minutes(RECENT_SIGN_IN_MINUTES + 1));
+
+        assert!(confirm_deletion(Some("StrongPass123!"), &hash, None, now).is_ok());
+        assert!(confirm_deletion(Some("wrong"), &hash, recent, now).is_err());
+        assert!(confirm_deletion(None, &hash, recent, now).is_ok());
+        assert!(confirm_deletion(None, &hash, stale, now).is_err());
+        assert!(confirm_deletion(None, &hash, None, now).is_err());
+    }
+}
@@ -0,0 +1,291 @@
+// src/handlers/admin.rs
+use axum::{
+    extract::{State, Path, Json, Query},
+    http::StatusCode,
+};
+use sqlx::{Pool, Postgres};
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    auth::admin::{self, AdminAction, AuditTarget},
+    errors::AppError,
+    handlers::{auth::MessageResponse, group::{find_group, GroupResponse}},
+    middleware::{AuthenticatedUser, ClientInfo},
+    models::{
+        admin_audit_log::AdminAuditEntry,
+        auth_event::AuthEvent,
+        user::User,
+    },
+    permissions::PlatformRole,
+    utils::{PaginationParams, IMPERSONATION_TOKEN_TTL_MINUTES},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct UserSearchQuery {
+    /// Part of an email address or username
+    pub q: Option<String>,
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditLogQuery {
+    pub actor_id: Option<Uuid>,
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuthEventQuery {
+    pub user_id: Option<Uuid>,
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangeRoleRequest {
+    pub role: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransferOwnershipRequest {
+    pub new_owner_id: Uuid,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ImpersonateRequest {
+    /// Let the token make changes as the user, not only read
+    #[serde(default)]
+    pub allow_writes: bool,
+    /// Why support needs to see the account, kept in the audit log
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImpersonationResponse {
+    pub access_token: String,
+    pub expires_in: usize,
+    pub allow_writes: bool,
+    pub user: AdminUserResponse,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdminUserResponse {
+    pub id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub role: String,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<User> for AdminUserResponse {
+    fn from(user: User) -> Self {
+        AdminUserResponse {
+            id: user.id,
+            email: user.email,
+            username: user.username,
+            role: user.role,
+            email_verified_at: user.email_verified_at,
+            disabled_at: user.disabled_at,
+            created_at: user.created_at,
+        }
+    }
+}
+
+async fn find_user(pool: &Pool<Postgres>, user_id: Uuid) -> Result<User, AppError> {
+    User::find_by_id(pool, user_id).await?.ok_or(AppError::NotFound)
+}
+
+pub async fn list_users_handler(
+    State(pool): State<Pool<Postgres>>,
+    Query(query): Query<UserSearchQuery>,
+) -> Result<Json<Vec<AdminUserResponse>>, AppError> {
+    let search = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty()).map(str::to_string);
+    let page = PaginationParams::new(query.page, query.page_size);
+    let users = User::search(&pool, search, page.page_size.into(), page.offset).await?;
+    Ok(Json(users.into_iter().map(Into::into).collect()))
+}
+
+pub async fn get_user_handler(
+    State(pool): State<Pool<Postgres>>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<AdminUserResponse>, AppError> {
+    Ok(Json(find_user(&pool, user_id).await?.into()))
+}
+
+pub async fn disable_user_handler(
+    State(pool): State<Pool<Postgres>>,
+    admin_user: AuthenticatedUser,
+    client: ClientInfo,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<AdminUserResponse>, AppError> {
+    find_user(&pool, user_id).await?;
+    let mut tx = pool.begin().await?;
+    let user = admin::disable_user(&mut tx, admin_user.id, user_id).await?;
+    admin::record_admin_action(
+        &mut *tx, Some(admin_user.id), AdminAction::DisableUser, AuditTarget::User(user_id), None, client.ip_address,
+    ).await?;
+    tx.commit().await?;
+
+    Ok(Json(user.into()))
+}
+
+pub async fn enable_user_handler(
+    State(pool): State<Pool<Postgres>>,
+    admin_user: AuthenticatedUser,
+    client: ClientInfo,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<AdminUserResponse>, AppError> {
+    find_user(&pool, user_id).await?;
+    let mut tx = pool.begin().await?;
+    let user = admin::enable_user(&mut tx, user_id).await?;
+    admin::record_admin_action(
+        &mut *tx, Some(admin_user.id), AdminAction::EnableUser, AuditTarget::User(user_id), None, client.ip_address,
+    ).await?;
+    tx.commit().await?;
+
+    Ok(Json(user.into()))
+}
+
+pub async fn force_password_reset_handler(
+    State(pool): State<Pool<Postgres>>,
+    admin_user: AuthenticatedUser,
+    client: ClientInfo,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<MessageResponse>, AppError> {
+    let user = find_user(&pool, user_id).await?;
+    let mut tx = pool.begin().await?;
+    admin::force_password_reset(&mut tx, admin_user.id, &user).await?;
+    admin::record_admin_action(
+        &mut *tx, Some(admin_user.id), AdminAction::ForcePasswordReset, AuditTarget::User(user_id), None, client.ip_address,
+    ).await?;
+    tx.commit().await?;
+    admin::send_forced_reset_email(&pool, &user).await;
+
+    Ok(Json(MessageResponse {
+        message: "Password cleared, sessions revoked and a reset link sent to the user".to_string(),
+    }))
+}
+
+pub async fn change_role_handler(
+    State(pool): State<Pool<Postgres>>,
+    admin_user: AuthenticatedUser,
+    client: ClientInfo,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<ChangeRoleRequest>,
+) -> Result<Json<AdminUserResponse>, AppError> {
+    let role = PlatformRole::parse(&payload.role.to_lowercase())
+        .ok_or(AppError::ValidationError("Role must be user or admin".to_string()))?;
+    let previous = find_user(&pool, user_id).await?;
+
+    let mut tx = pool.begin().await?;
+    let user = admin::change_role(&mut tx, admin_user.id, user_id, role).await?;
+    admin::record_admin_action(
+        &mut *tx,
+        Some(admin_user.id),
+        AdminAction::ChangeRole,
+        AuditTarget::User(user_id),
+        Some(serde_json::json!({ "from": previous.role, "to": user.role })),
+        client.ip_address,
+    ).await?;
+    tx.commit().await?;
+
+    Ok(Json(user.into()))
+}
+
+pub async fn impersonate_user_handler(
+    State(pool): State<Pool<Postgres>>,
+    admin_user: AuthenticatedUser,
+    client: ClientInfo,
+    Path(user_id): Path<Uuid>,
+    payload: Option<Json<ImpersonateRequest>>,
+) -> Result<Json<ImpersonationResponse>, AppError> {
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    let user = find_user(&pool, user_id).await?;
+
+    let access_token = admin::impersonation_token(admin_user.id, &user, payload.allow_writes)?;
+    admin::record_admin_action(
+        &pool,
+        Some(admin_user.id),
+        AdminAction::Impersonate,
+        AuditTarget::User(user_id),
+        Some(serde_json::json!({ "allow_writes": payload.allow_writes, "reason": payload.reason })),
+        client.ip_address,
+    ).await?;
+
+    Ok(Json(ImpersonationResponse {
+        access_token,
+        expires_in: (IMPERSONATION_TOKEN_TTL_MINUTES * 60) as usize,
+        allow_writes: payload.allow_writes,
+        user: user.into(),
+    }))
+}
+
+pub async fn delete_group_handler(
+    State(pool): State<Pool<Postgres>>,
+    admin_user: AuthenticatedUser,
+    client: ClientInfo,
+    Path(group_ref): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let group = find_group(&pool, &group_ref).await?;
+
+    let mut tx = pool.begin().await?;
+    admin::delete_group(&mut tx, group.id).await?;
+    admin::record_admin_action(
+        &mut *tx,
+        Some(admin_user.id),
+        AdminAction::DeleteGroup,
+        AuditTarget::Group(group.id),
+        Some(serde_json::json!({ "name": group.name, "owner_id": group.owner_id, "description": group.description })),
+        client.ip_address,
+    ).await?;
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn transfer_group_handler(
+    State(pool): State<Pool<Postgres>>,
+    admin_user: AuthenticatedUser,
+    client: ClientInfo,
+    Path(group_ref): Path<String>,
+    Json(payload): Json<TransferOwnershipRequest>,
+) -> Result<Json<GroupResponse>, AppError> {
+    let previous = find_group(&pool, &group_ref).await?;
+
+    let mut tx = pool.begin().await?;
+    let group = admin::transfer_group_ownership(&mut tx, previous.id, payload.new_owner_id).await?;
+    admin::record_admin_action(
+        &mut *tx,
+        Some(admin_user.id),
+        AdminAction::TransferGroupOwnership,
+        AuditTarget::Group(group.id),
+        Some(serde_json::json!({ "from": previous.owner_id, "to": group.owner_id })),
+        client.ip_address,
+    ).await?;
+    tx.commit().await?;
+
+    Ok(Json(group.into()))
+}
+
+pub async fn list_audit_log_handler(
+    State(pool): State<Pool<Postgres>>,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<Json<Vec<AdminAuditEntry>>, AppError> {
+    let page = PaginationParams::new(query.page, query.page_size);
+    let entries = AdminAuditEntry::list(&pool, query.actor_id, page.page_size.into(), page.offset).await?;
+    Ok(Json(entries))
+}
+
+pub async fn list_auth_events_handler(
+    State(pool): State<Pool<Postgres>>,
+    Query(query): Query<AuthEventQuery>,
+) -> Result<Json<Vec<AuthEvent>>, AppError> {
+    let page = PaginationParams::new(query.page, query.page_size);
+    let events = AuthEvent::list(&pool, query.user_id, page.page_size.into(), page.offset).await?;
+    Ok(Json(events))
+}
@@ -1,6 +1,6 @@
 use axum::{
     extract::{State, Json},
-    http::StatusCode,
+    http::{HeaderMap, Method, StatusCode},
 };
 use sqlx::{Pool, Postgres};
 use serde::{Serialize, Deserialize};
@@ -8,11 +8,19 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
 use crate::{
+    auth,
     errors::AppError,
-    models::user::{User, NewUser},
-    utils::{JwtManager, PasswordUtils, EmailUtils, UsernameUtils},
+    middleware::ClientInfo,
+    models::{
+        password_reset_token::PasswordResetToken,
+        personal_access_token::PersonalAccessToken,
+        user::{User, NewUser},
+    },
+    utils::{JwtManager, shared_jwt_manager, MFA_PENDING_TOKEN_TTL_MINUTES, PasswordUtils, EmailUtils, UsernameUtils, TextUtils, TokenUtils},
 };
 
+const MAX_BIO_LENGTH: usize = 500;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RegisterRequest {
     pub email: String,
@@ -22,28 +30,97 @@ pub struct RegisterRequest {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoginRequest {
+    /// Email for local accounts, or the directory username with LDAP
+    #[serde(alias = "username")]
     pub email: String,
     pub password: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RefreshTokenRequest {
-    pub refresh_token: String,
+    /// Omitted by cookie sessions, which send it as a cookie
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateProfileRequest {
+    pub username: Option<String>,
+    pub bio: Option<String>,
+    pub image_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AuthResponse {
     pub user: UserResponse,
+    // Empty for cookie sessions, the tokens are in cookies instead
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub access_token: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub refresh_token: String,
     pub expires_in: usize, // in seconds
 }
 
+impl AuthResponse {
+    pub(crate) fn take_cookies(&mut self, cookie_mode: bool) -> HeaderMap {
+        move_tokens_to_cookies(cookie_mode, &mut self.access_token, &mut self.refresh_token)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaChallengeResponse {
+    pub mfa_required: bool,
+    pub mfa_token: String,
+    pub expires_in: usize, // in seconds
+}
+
+/// Login either completes or asks for a second factor
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Authenticated(AuthResponse),
+    MfaRequired(MfaChallengeResponse),
+}
+
+impl LoginResponse {
+    pub(crate) fn take_cookies(&mut self, cookie_mode: bool) -> HeaderMap {
+        match self {
+            LoginResponse::Authenticated(response) => response.take_cookies(cookie_mode),
+            LoginResponse::MfaRequired(_) => HeaderMap::new(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserResponse {
     pub id: Uuid,
     pub email: String,
     pub username: String,
+    pub bio: Option<String>,
+    pub image_url: Option<String>,
+    pub email_verified_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -53,6 +130,9 @@ impl From<User> for UserResponse {
             id: user.id,
             email: user.email,
             username: user.username,
+            bio: user.bio,
+            image_url: user.image_url,
+            email_verified_at: user.email_verified_at,
             created_at: user.created_at,
         }
     }
@@ -60,11 +140,47 @@ impl From<User> for UserResponse {
 
 #[derive(Debug, Serialize)]
 pub struct TokenResponse {
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub access_token: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub refresh_token: String,
     pub expires_in: usize,
 }
 
+impl TokenResponse {
+    fn take_cookies(&mut self, cookie_mode: bool) -> HeaderMap {
+        move_tokens_to_cookies(cookie_mode, &mut self.access_token, &mut self.refresh_token)
+    }
+}
+
+/// For a cookie session move the tokens out of the body into `Set-Cookie`
+/// headers; bearer clients keep getting them in the body
+fn move_tokens_to_cookies(cookie_mode: bool, access_token: &mut String, refresh_token: &mut String) -> HeaderMap {
+    if !cookie_mode {
+        return HeaderMap::new();
+    }
+    auth::session_cookies(&auth::SessionTokens {
+        access_token: std::mem::take(access_token),
+        refresh_token: std::mem::take(refresh_token),
+    })
+}
+
+/// The refresh token from the body, or from the session cookie once the
+/// request has passed the CSRF check. The flag tells if it was the cookie.
+fn request_refresh_token(
+    headers: &HeaderMap,
+    payload: Option<Json<RefreshTokenRequest>>,
+) -> Result<(String, bool), AppError> {
+    if let Some(token) = payload.and_then(|Json(p)| p.refresh_token) {
+        return Ok((token, false));
+    }
+
+    let token = auth::read_cookie(headers, auth::REFRESH_TOKEN_COOKIE)
+        .ok_or(AppError::ValidationError("Refresh token is required".to_string()))?;
+    auth::verify_csrf(&Method::POST, headers)?;
+    Ok((token, true))
+}
+
 #[derive(Debug, Serialize)]
 pub struct MessageResponse {
     pub message: String,
@@ -72,8 +188,10 @@ pub struct MessageResponse {
 
 pub async fn register_handler(
     State(pool): State<Pool<Postgres>>,
+    client: ClientInfo,
+    headers: HeaderMap,
     Json(payload): Json<RegisterRequest>,
-) -> Result<(StatusCode, Json<AuthResponse>), AppError> {
+) -> Result<(StatusCode, HeaderMap, Json<AuthResponse>), AppError> {
     // Validate input
     println!("Validating registration input...");
     validate_registration_input(&payload)?;
@@ -95,119 +213,327 @@ pub async fn register_handler(
     let new_user = NewUser {
         email: EmailUtils::normalize_email(payload.email.as_str()),
         username: payload.username.clone(),
-        password_hash: password_hash,
+        password_hash,
         bio: None,
         image_url: None,
     };
 
     let user = User::create(&pool, new_user).await?;
 
+    // The account is usable right away; a failed email can be re-sent later
+    if let Err(e) = auth::send_verification_email(&pool, &user).await {
+        tracing::error!(user_id = %user.id, "failed to send verification email: {}", e);
+    }
+
     // Generate tokens
     let jwt_manager = get_jwt_manager()?;
-    let access_token = jwt_manager.generate_access_token(user.id)
-        .map_err(|e| AppError::ValidationError(format!("Token generation failed: {}", e)))?;
-    let refresh_token = jwt_manager.generate_refresh_token(user.id)
-        .map_err(|e| AppError::ValidationError(format!("Token generation failed: {}", e)))?;
+    let tokens = auth::start_session(&pool, jwt_manager, &user, &client).await?;
 
-    let auth_response = AuthResponse {
+    let mut auth_response = AuthResponse {
         user: user.into(),
-        access_token,
-        refresh_token,
+        access_token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
         expires_in: 15 * 60, // 15 minutes
     };
+    let cookies = auth_response.take_cookies(auth::wants_cookie_session(&headers));
 
-    Ok((StatusCode::CREATED, Json(auth_response)))
+    Ok((StatusCode::CREATED, cookies, Json(auth_response)))
 }
 
 pub async fn login_handler(
     State(pool): State<Pool<Postgres>>,
+    client: ClientInfo,
+    headers: HeaderMap,
     Json(payload): Json<LoginRequest>,
-) -> Result<Json<AuthResponse>, AppError> {
-    // Validate email format
-    if !EmailUtils::is_valid_email(&payload.email) {
-        return Err(AppError::ValidationError("Invalid email format".to_string()));
+) -> Result<(HeaderMap, Json<LoginResponse>), AppError> {
+    if payload.email.trim().is_empty() {
+        return Err(AppError::ValidationError("Email or username is required".to_string()));
     }
 
-    // Find user by email
-    let user = User::find_by_email(&pool, payload.email)
-        .await?
-        .ok_or(AppError::ValidationError("User not found".to_string()))?;
+    let user_id = User::find_by_email(&pool, payload.email.clone()).await?.map(|u| u.id);
+    let attempt = auth::begin_login_attempt(&pool, user_id, &payload.email, client.ip_address.as_deref()).await?;
 
-    // Verify password
-    let is_valid = PasswordUtils::verify_password(&payload.password, &user.password_hash)
-        .map_err(|e| AppError::ValidationError(format!("Password verification failed: {}", e)))?;
+    // Unknown login and wrong password look the same to the caller
+    let Some(user) = auth::authenticate(&pool, &payload.email, &payload.password).await? else {
+        auth::record_login_failure(&pool, &attempt, user_id).await?;
+        return Err(AppError::ValidationError("Invalid credentials".to_string()));
+    };
 
-    if !is_valid {
-        return Err(AppError::ValidationError("Invalid password".to_string()));
+    let user_id = user.id;
+    let mut response = complete_login(&pool, user, &client).await?;
+
+    // With two-factor pending the user's counter is cleared at /login/mfa
+    match &response {
+        LoginResponse::Authenticated(_) => auth::record_login_success(&pool, &attempt, user_id).await?,
+        LoginResponse::MfaRequired(_) => auth::record_first_factor_success(&pool, &attempt, user_id).await?,
     }
 
-    // Generate tokens
+    let cookies = response.take_cookies(auth::wants_cookie_session(&headers));
+    Ok((cookies, Json(response)))
+}
+
+/// Finish a first-factor login: ask for the second factor if the user has
+/// one, otherwise start a session
+pub(crate) async fn complete_login(
+    pool: &Pool<Postgres>,
+    user: User,
+    client: &ClientInfo,
+) -> Result<LoginResponse, AppError> {
     let jwt_manager = get_jwt_manager()?;
-    let access_token = jwt_manager.generate_access_token(user.id)
-        .map_err(|e| AppError::ValidationError(format!("Token generation failed: {}", e)))?;
-    let refresh_token = jwt_manager.generate_refresh_token(user.id)
-        .map_err(|e| AppError::ValidationError(format!("Token generation failed: {}", e)))?;
 
-    let auth_response = AuthResponse {
+    // Users with two-factor enabled must exchange this token at /login/mfa
+    if auth::is_mfa_enabled(pool, user.id).await? {
+        let mfa_token = jwt_manager.generate_mfa_pending_token(user.id, user.token_version)
+            .map_err(|e| AppError::ValidationError(format!("Token generation failed: {}", e)))?;
+
+        return Ok(LoginResponse::MfaRequired(MfaChallengeResponse {
+            mfa_required: true,
+            mfa_token,
+            expires_in: (MFA_PENDING_TOKEN_TTL_MINUTES * 60) as usize,
+        }));
+    }
+
+    // Generate tokens
+    let tokens = auth::start_session(pool, jwt_manager, &user, client).await?;
+
+    Ok(LoginResponse::Authenticated(AuthResponse {
         user: user.into(),
-        access_token,
-        refresh_token,
+        access_token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+        expires_in: 15 * 60, // 15 minutes
+    }))
+}
+
+pub async fn refresh_token_handler(
+    State(pool): State<Pool<Postgres>>,
+    headers: HeaderMap,
+    payload: Option<Json<RefreshTokenRequest>>,
+) -> Result<(HeaderMap, Json<TokenResponse>), AppError> {
+    let jwt_manager = get_jwt_manager()?;
+    let (refresh_token, cookie_mode) = request_refresh_token(&headers, payload)?;
+
+    // Rotate the presented refresh token
+    let (_user, tokens) = auth::rotate_session(&pool, jwt_manager, &refresh_token).await?;
+
+    let mut token_response = TokenResponse {
+        access_token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
         expires_in: 15 * 60, // 15 minutes
     };
+    let cookies = token_response.take_cookies(cookie_mode);
 
-    Ok(Json(auth_response))
+    Ok((cookies, Json(token_response)))
 }
 
-pub async fn refresh_token_handler(
+pub async fn logout_handler(
     State(pool): State<Pool<Postgres>>,
-    Json(payload): Json<RefreshTokenRequest>,
-) -> Result<Json<TokenResponse>, AppError> {
+    user: crate::middleware::AuthenticatedUser,
+    headers: HeaderMap,
+    payload: Option<Json<RefreshTokenRequest>>,
+) -> Result<(HeaderMap, Json<MessageResponse>), AppError> {
     let jwt_manager = get_jwt_manager()?;
+    let (refresh_token, cookie_mode) = request_refresh_token(&headers, payload)?;
+    auth::end_session(&pool, jwt_manager, user.id, &refresh_token).await?;
+    auth::revoke_access_token(&pool, user.id, user.token_id).await?;
+
+    let cookies = if cookie_mode { auth::clear_session_cookies() } else { HeaderMap::new() };
+    Ok((cookies, Json(MessageResponse {
+        message: "Successfully logged out".to_string(),
+    })))
+}
+
+pub async fn logout_all_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: crate::middleware::AuthenticatedUser,
+    headers: HeaderMap,
+) -> Result<(HeaderMap, Json<MessageResponse>), AppError> {
+    auth::revoke_all_tokens(&pool, user.id).await?;
+
+    let cookies = if auth::has_cookie_session(&headers) { auth::clear_session_cookies() } else { HeaderMap::new() };
+    Ok((cookies, Json(MessageResponse {
+        message: "Successfully logged out of all sessions".to_string(),
+    })))
+}
 
-    // Verify refresh token
-    let claims = jwt_manager.verify_refresh_token(&payload.refresh_token)
-        .map_err(|_| AppError::Unauthorized)?;
+pub async fn me_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: crate::middleware::AuthenticatedUser, // Use the AuthenticatedUser extractor
+) -> Result<Json<UserResponse>, AppError> {
+    let user = User::find_by_id(&pool, user.id)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
 
-    let user_id = claims.user_id()
-        .map_err(|_| AppError::Unauthorized)?;
+    Ok(Json(user.into()))
+}
 
-    // Verify user still exists
-    let _user = User::find_by_id(&pool, user_id)
+pub async fn update_me_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: crate::middleware::AuthenticatedUser,
+    Json(payload): Json<UpdateProfileRequest>,
+) -> Result<Json<UserResponse>, AppError> {
+    let user = User::find_by_id(&pool, user.id)
         .await?
         .ok_or(AppError::Unauthorized)?;
 
-    // Generate new tokens
-    let access_token = jwt_manager.generate_access_token(user_id)
-        .map_err(|e| AppError::ValidationError(format!("Token generation failed: {}", e)))?;
-    let refresh_token = jwt_manager.generate_refresh_token(user_id)
-        .map_err(|e| AppError::ValidationError(format!("Token generation failed: {}", e)))?;
+    let username = match payload.username {
+        Some(username) if username != user.username => {
+            UsernameUtils::validate_username_characters(&username)
+                .map_err(AppError::ValidationError)?;
+
+            if User::find_by_username(&pool, username.clone()).await?.is_some() {
+                return Err(AppError::ValidationError("Username already taken".to_string()));
+            }
+            username
+        }
+        _ => user.username,
+    };
+
+    // An empty string clears the field
+    let bio = match payload.bio {
+        Some(bio) => Some(TextUtils::sanitize_text(&bio, MAX_BIO_LENGTH)).filter(|b| !b.is_empty()),
+        None => user.bio,
+    };
+
+    let image_url = match payload.image_url {
+        Some(url) => {
+            let url = url.trim().to_string();
+            if !url.is_empty() && !is_valid_image_url(&url) {
+                return Err(AppError::ValidationError("Invalid image URL".to_string()));
+            }
+            Some(url).filter(|u| !u.is_empty())
+        }
+        None => user.image_url,
+    };
+
+    let updated_user = User::update_profile(&pool, user.id, username, bio, image_url).await?;
+    Ok(Json(updated_user.into()))
+}
+
+pub async fn change_password_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: crate::middleware::AuthenticatedUser,
+    client: ClientInfo,
+    headers: HeaderMap,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<(HeaderMap, Json<TokenResponse>), AppError> {
+    let user = User::find_by_id(&pool, user.id)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    let is_valid = PasswordUtils::verify_password(&payload.current_password, &user.password_hash)
+        .map_err(|e| AppError::ValidationError(format!("Password verification failed: {}", e)))?;
+
+    if !is_valid {
+        return Err(AppError::ValidationError("Current password is incorrect".to_string()));
+    }
+
+    if payload.new_password == payload.current_password {
+        return Err(AppError::ValidationError("New password must be different from the current password".to_string()));
+    }
+
+    if let Err(password_errors) = PasswordUtils::validate_password_strength(&payload.new_password) {
+        return Err(AppError::ValidationError(password_errors.join(", ")));
+    }
 
-    let token_response = TokenResponse {
-        access_token,
-        refresh_token,
+    let password_hash = PasswordUtils::hash_password(&payload.new_password)
+        .map_err(|e| AppError::ValidationError(format!("Password hashing failed: {}", e)))?;
+
+    User::update_password(&pool, user.id, password_hash).await?;
+
+    // Sign out every other session and hand this client a fresh pair. A
+    // change may follow a suspected compromise, so API tokens go too.
+    let user = auth::revoke_all_tokens(&pool, user.id).await?;
+    PersonalAccessToken::delete_all_for_user(&pool, user.id).await?;
+    let jwt_manager = get_jwt_manager()?;
+    let tokens = auth::start_session(&pool, jwt_manager, &user, &client).await?;
+
+    let mut token_response = TokenResponse {
+        access_token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
         expires_in: 15 * 60, // 15 minutes
     };
+    // A cookie session keeps going with the new pair in its cookies
+    let cookies = token_response.take_cookies(auth::has_cookie_session(&headers));
 
-    Ok(Json(token_response))
+    Ok((cookies, Json(token_response)))
 }
 
-pub async fn logout_handler() -> Result<Json<MessageResponse>, AppError> {
-    // In a real application, you might want to blacklist the tokens
-    // For now, we just return a success message
+pub async fn forgot_password_handler(
+    State(pool): State<Pool<Postgres>>,
+    client: ClientInfo,
+    Json(payload): Json<ForgotPasswordRequest>,
+) -> Result<Json<MessageResponse>, AppError> {
+    let email = EmailUtils::normalize_email(&payload.email);
+    auth::begin_password_reset_request(&pool, &email, client.ip_address.as_deref()).await?;
+
+    // Respond the same way, and as quickly, whether or not the address is
+    // registered: the token and the mail are handled in the background
+    if let Some(user) = User::find_by_email(&pool, email).await? {
+        tokio::spawn(async move {
+            if let Err(e) = auth::send_password_reset_email(&pool, &user).await {
+                tracing::error!(user_id = %user.id, "failed to send password reset email: {}", e);
+            }
+        });
+    }
+
     Ok(Json(MessageResponse {
-        message: "Successfully logged out".to_string(),
+        message: "If that email is registered, a password reset link has been sent".to_string(),
     }))
 }
 
-pub async fn me_handler(
+pub async fn reset_password_handler(
     State(pool): State<Pool<Postgres>>,
-    user: crate::middleware::AuthenticatedUser, // Use the AuthenticatedUser extractor
+    Json(payload): Json<ResetPasswordRequest>,
+) -> Result<Json<MessageResponse>, AppError> {
+    // Validate before consuming the token so a weak password doesn't burn it
+    if let Err(password_errors) = PasswordUtils::validate_password_strength(&payload.new_password) {
+        return Err(AppError::ValidationError(password_errors.join(", ")));
+    }
+
+    let reset_token = PasswordResetToken::consume(&pool, TokenUtils::hash_token(&payload.token))
+        .await?
+        .ok_or(AppError::ValidationError("Invalid or expired reset token".to_string()))?;
+
+    let password_hash = PasswordUtils::hash_password(&payload.new_password)
+        .map_err(|e| AppError::ValidationError(format!("Password hashing failed: {}", e)))?;
+
+    User::update_password(&pool, reset_token.user_id, password_hash).await?;
+
+    // Sign the user out everywhere now that the password changed. The
+    // account may have been compromised, so API tokens go too.
+    auth::revoke_all_tokens(&pool, reset_token.user_id).await?;
+    PersonalAccessToken::delete_all_for_user(&pool, reset_token.user_id).await?;
+
+    Ok(Json(MessageResponse {
+        message: "Password has been reset".to_string(),
+    }))
+}
+
+pub async fn verify_email_handler(
+    State(pool): State<Pool<Postgres>>,
+    Json(payload): Json<VerifyEmailRequest>,
 ) -> Result<Json<UserResponse>, AppError> {
+    let user = auth::verify_email(&pool, &payload.token).await?;
+    Ok(Json(user.into()))
+}
+
+pub async fn resend_verification_email_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: crate::middleware::AuthenticatedUser,
+) -> Result<Json<MessageResponse>, AppError> {
     let user = User::find_by_id(&pool, user.id)
         .await?
         .ok_or(AppError::Unauthorized)?;
 
-    Ok(Json(user.into()))
+    if user.email_verified_at.is_some() {
+        return Err(AppError::ValidationError("Email address is already verified".to_string()));
+    }
+
+    auth::send_verification_email(&pool, &user).await?;
+
+    Ok(Json(MessageResponse {
+        message: "Verification email sent".to_string(),
+    }))
 }
 
 fn validate_registration_input(payload: &RegisterRequest) -> Result<(), AppError> {
@@ -235,9 +561,13 @@ fn validate_registration_input(payload: &RegisterRequest) -> Result<(), AppError
     Ok(())
 }
 
-fn get_jwt_manager() -> Result<JwtManager, AppError> {
-    let jwt_secret = std::env::var("JWT_SECRET")
-        .map_err(|_| AppError::ValidationError("JWT_SECRET environment variable not set".to_string()))?;
-    
-    Ok(JwtManager::new(&jwt_secret))
+fn is_valid_image_url(url: &str) -> bool {
+    url.len() <= 2048
+        && (url.starts_with("https://") || url.starts_with("http://"))
+        && !url.chars().any(char::is_whitespace)
+}
+
+pub(crate) fn get_jwt_manager() -> Result<&'static JwtManager, AppError> {
+    shared_jwt_manager()
+        .map_err(|e| AppError::ValidationError(format!("JWT keys not configured: {}", e)))
 }
\ No newline at end of file
@@ -8,9 +8,22 @@ use uuid::Uuid;
 use chrono::{Utc, DateTime};
 
 use crate::{
-    errors::AppError, middleware::auth::AuthenticatedUser, models
+    errors::AppError, models,
+    permissions::{GroupAction, GroupPermissions},
 };
 
+async fn find_material(pool: &Pool<Postgres>, material_id: Uuid) -> Result<models::material::Material, AppError> {
+    models::material::Material::find_by_id(pool, material_id)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+async fn find_comment(pool: &Pool<Postgres>, material_id: Uuid, id: i32) -> Result<models::comment::Comment, AppError> {
+    models::comment::Comment::find_by_id(pool, material_id, id)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
 
 
 
@@ -49,13 +62,16 @@ impl From<models::comment::Comment> for CommentResponse {
 // Comment handlers
 pub async fn create_comment_handler(
     State(pool): State<Pool<Postgres>>,
-    user: AuthenticatedUser,
+    permissions: GroupPermissions,
     Path(material_id): Path<Uuid>,
     Json(payload): Json<CreateCommentRequest>,
 ) -> Result<(StatusCode, Json<CommentResponse>), AppError> {
+    let material = find_material(&pool, material_id).await?;
+    permissions.require(material.group_id, GroupAction::Comment).await?;
+
     let new_comment = models::comment::NewComment {
         material_id,
-        user_id: user.id, // Placeholder for now, will be replaced with actual user ID
+        user_id: permissions.user_id(),
         content: payload.content,
     };
 
@@ -76,30 +92,34 @@ pub async fn list_comments_handler(
 
 pub async fn get_comment_handler(
     State(pool): State<Pool<Postgres>>,
-    Path(material_id): Path<Uuid>,
-    Path(id): Path<i32>,
+    Path((material_id, id)): Path<(Uuid, i32)>,
 ) -> Result<Json<CommentResponse>, AppError> {
-    let comment = models::comment::Comment::find_by_id(&pool, material_id, id)
-        .await?
-        .ok_or(AppError::NotFound)?;
-    Ok(Json(comment.into()))
+    Ok(Json(find_comment(&pool, material_id, id).await?.into()))
 }
 
 pub async fn update_comment_handler(
     State(pool): State<Pool<Postgres>>,
-    Path(material_id): Path<Uuid>,
-    Path(id): Path<i32>,
+    permissions: GroupPermissions,
+    Path((material_id, id)): Path<(Uuid, i32)>,
     Json(payload): Json<UpdateCommentRequest>,
 ) -> Result<Json<CommentResponse>, AppError> {
+    let material = find_material(&pool, material_id).await?;
+    let comment = find_comment(&pool, material_id, id).await?;
+    permissions.require_on(material.group_id, GroupAction::Moderate, comment.user_id).await?;
+
     let comment = models::comment::Comment::update(&pool, material_id, id, payload.content).await?;
     Ok(Json(comment.into()))
 }
 
 pub async fn delete_comment_handler(
     State(pool): State<Pool<Postgres>>,
-    Path(material_id): Path<Uuid>,
-    Path(id): Path<i32>,
+    permissions: GroupPermissions,
+    Path((material_id, id)): Path<(Uuid, i32)>,
 ) -> Result<StatusCode, AppError> {
+    let material = find_material(&pool, material_id).await?;
+    let comment = find_comment(&pool, material_id, id).await?;
+    permissions.require_on(material.group_id, GroupAction::Moderate, comment.user_id).await?;
+
     models::comment::Comment::delete(&pool, material_id, id).await?;
     Ok(StatusCode::NO_CONTENT)
 }
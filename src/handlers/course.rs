@@ -5,17 +5,22 @@ use axum::{
 };
 use sqlx::{Pool, Postgres};
 use serde::{Serialize, Deserialize};
+use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
 use crate::{
     errors::AppError,
+    handlers::group::{find_group, validate_slug},
     models,
+    permissions::{GroupAction, GroupPermissions},
 };
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CourseResponse {
+    pub id: Uuid,
+    pub group_id: Uuid,
     pub name: String,
-    pub group_name: String,
+    pub slug: String,
     pub description: Option<String>,
     pub created_at: DateTime<Utc>,
 }
@@ -23,8 +28,10 @@ pub struct CourseResponse {
 impl From<models::course::Course> for CourseResponse {
     fn from(course: models::course::Course) -> Self {
         CourseResponse {
+            id: course.id,
+            group_id: course.group_id,
             name: course.name,
-            group_name: course.group_name,
+            slug: course.slug,
             description: course.description,
             created_at: course.created_at,
         }
@@ -40,33 +47,54 @@ pub struct CreateCoursePayload {
 #[derive(Debug, Deserialize)]
 pub struct UpdateCoursePayload {
     pub name: Option<String>,
+    pub slug: Option<String>,
     pub description: Option<String>,
 }
 
+/// The course in the path, each of group and course given as its id, slug
+/// or name
+pub(crate) async fn find_course(
+    pool: &Pool<Postgres>,
+    group_ref: &str,
+    course_ref: &str,
+) -> Result<models::course::Course, AppError> {
+    let group = find_group(pool, group_ref).await?;
+    models::course::Course::find_by_ref(pool, group.id, course_ref)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+async fn ensure_name_free(pool: &Pool<Postgres>, group_id: Uuid, name: &str) -> Result<(), AppError> {
+    if models::course::Course::find_by_group_and_name(pool, group_id, name.to_string()).await?.is_some() {
+        return Err(AppError::Conflict("The group already has a course with this name".to_string()));
+    }
+    Ok(())
+}
+
 pub async fn list_courses_handler(
     State(pool): State<Pool<Postgres>>,
-    Path(group_name): Path<String>,
+    Path(group_ref): Path<String>,
 ) -> Result<Json<Vec<CourseResponse>>, AppError> {
-    let _group = models::group::Group::find_by_name(&pool, group_name.clone())
-        .await?
-        .ok_or(AppError::NotFound)?;
+    let group = find_group(&pool, &group_ref).await?;
 
-    let courses = models::course::Course::find_by_group_name(&pool, group_name).await?;
+    let courses = models::course::Course::find_by_group(&pool, group.id).await?;
     let responses: Vec<CourseResponse> = courses.into_iter().map(Into::into).collect();
     Ok(Json(responses))
 }
 
 pub async fn create_course_handler(
     State(pool): State<Pool<Postgres>>,
-    Path(group_name): Path<String>,
+    permissions: GroupPermissions,
+    Path(group_ref): Path<String>,
     Json(payload): Json<CreateCoursePayload>,
 ) -> Result<(StatusCode, Json<CourseResponse>), AppError> {
-    let _group = models::group::Group::find_by_name(&pool, group_name.clone())
-        .await?
-        .ok_or(AppError::NotFound)?;
+    let group = find_group(&pool, &group_ref).await?;
+    permissions.require(group.id, GroupAction::Post).await?;
+    ensure_name_free(&pool, group.id, &payload.name).await?;
 
     let new_course = models::course::NewCourse {
-        group_name: group_name.to_string(),
+        group_id: group.id,
+        slug: models::course::Course::available_slug(&pool, group.id, &payload.name).await?,
         name: payload.name,
         description: payload.description,
     };
@@ -77,45 +105,59 @@ pub async fn create_course_handler(
 
 pub async fn get_course_detail_handler(
     State(pool): State<Pool<Postgres>>,
-    Path(group_name): Path<String>,
-    Path(course_name): Path<String>,
+    Path((group_ref, course_ref)): Path<(String, String)>,
 ) -> Result<Json<CourseResponse>, AppError> {
-    let course = models::course::Course::find_by_group_and_name(&pool, group_name, course_name)
-        .await?
-        .ok_or(AppError::NotFound)?;
+    let course = find_course(&pool, &group_ref, &course_ref).await?;
     Ok(Json(course.into()))
 }
 
+/// Renaming keeps the slug, like it does for groups
 pub async fn update_course_handler(
     State(pool): State<Pool<Postgres>>,
-    Path(group_name): Path<String>,
-    Path(course_name): Path<String>,
+    permissions: GroupPermissions,
+    Path((group_ref, course_ref)): Path<(String, String)>,
     Json(payload): Json<UpdateCoursePayload>,
 ) -> Result<Json<CourseResponse>, AppError> {
-    let mut course = models::course::Course::find_by_group_and_name(&pool, group_name.clone(), course_name.clone())
-        .await?
-        .ok_or(AppError::NotFound)?;
-
-    if let Some(name) = payload.name {
+    let mut course = find_course(&pool, &group_ref, &course_ref).await?;
+    permissions.require(course.group_id, GroupAction::Edit).await?;
+
+    if let Some(name) = payload.name
+        && name != course.name
+    {
+        if name.trim().is_empty() {
+            return Err(AppError::ValidationError("Course name cannot be empty".to_string()));
+        }
+        ensure_name_free(&pool, course.group_id, &name).await?;
         course.name = name;
     }
+    if let Some(slug) = payload.slug
+        && slug != course.slug
+    {
+        validate_slug(&slug)?;
+        if models::course::Course::slug_taken(&pool, course.group_id, &slug, Some(course.id)).await? {
+            return Err(AppError::Conflict("Another course of the group already uses this slug".to_string()));
+        }
+        course.slug = slug;
+    }
     if let Some(description) = payload.description {
         course.description = Some(description);
     }
 
-    let updated_course = models::course::Course::update(&pool, group_name, course_name, course).await?;
+    let updated_course = models::course::Course::update(&pool, course).await?;
     Ok(Json(updated_course.into()))
 }
 
+/// Delete a course with all of its materials. That takes being allowed to
+/// edit the course as well as to delete.
 pub async fn delete_course_handler(
     State(pool): State<Pool<Postgres>>,
-    Path(group_name): Path<String>,
-    Path(course_name): Path<String>,
+    permissions: GroupPermissions,
+    Path((group_ref, course_ref)): Path<(String, String)>,
 ) -> Result<StatusCode, AppError> {
-    let _course = models::course::Course::find_by_group_and_name(&pool, group_name.clone(), course_name.clone())
-        .await?
-        .ok_or(AppError::NotFound)?;
+    let course = find_course(&pool, &group_ref, &course_ref).await?;
+    permissions.require(course.group_id, GroupAction::Edit).await?;
+    permissions.require(course.group_id, GroupAction::Delete).await?;
 
-    models::course::Course::delete(&pool, group_name, course_name).await?;
+    models::course::Course::delete(&pool, course.id).await?;
     Ok(StatusCode::NO_CONTENT)
-}
\ No newline at end of file
+}
@@ -1,16 +1,21 @@
 // src/handlers/group.rs
 use axum::{
-    extract::{State, Json},
+    extract::{State, Json, Path},
     http::StatusCode,
 };
 use sqlx::{Pool, Postgres};
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
+use uuid::Uuid;
 
 use crate::{
+    auth,
     errors::AppError,
-    models,
+    handlers::{course::CourseResponse, group_member::GroupMemberResponse},
+    models::{self, group::JoinType, group_ownership_transfer::GroupOwnershipTransfer},
     middleware::auth::AuthenticatedUser,
+    permissions::{GroupAction, GroupPermissions, PermissionLevel},
+    utils::StringUtils,
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -18,42 +23,160 @@ pub struct CreateGroupRequest {
     pub name: String,
     pub join_type: String,
     pub description: String,
+    /// `admins` (default) or `members`
+    pub post_permission: Option<String>,
+    /// `admins` (default) or `members`
+    pub edit_permissions: Option<String>,
+}
+
+/// Fields left out are kept as they are
+#[derive(Debug, Deserialize)]
+pub struct UpdateGroupRequest {
+    pub name: Option<String>,
+    pub slug: Option<String>,
+    pub description: Option<String>,
+    pub join_type: Option<String>,
+    pub post_permission: Option<String>,
+    pub edit_permissions: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NominateOwnerRequest {
+    pub new_owner_id: Uuid,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GroupResponse {
+    pub id: Uuid,
+    pub owner_id: Uuid,
     pub name: String,
+    pub slug: String,
     pub description: String,
+    pub join_type: String,
+    pub post_permission: Option<String>,
+    pub edit_permissions: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
 impl From<models::group::Group> for GroupResponse {
     fn from(group: models::group::Group) -> Self {
         GroupResponse {
+            id: group.id,
+            owner_id: group.owner_id,
             name: group.name,
+            slug: group.slug,
             description: group.description,
+            join_type: group.join_type,
+            post_permission: group.post_permission,
+            edit_permissions: group.edit_permissions,
             created_at: group.created_at,
         }
     }
 }
 
+#[derive(Debug, Serialize)]
+pub struct GroupDetailResponse {
+    #[serde(flatten)]
+    pub group: GroupResponse,
+    pub members: Vec<GroupMemberResponse>,
+    pub courses: Vec<CourseResponse>,
+}
+
+fn parse_join_type(join_type: &str) -> Result<JoinType, AppError> {
+    JoinType::parse(&join_type.to_uppercase())
+        .ok_or(AppError::ValidationError("Invalid join type".to_string()))
+}
+
+/// Who may post or edit in a group: `admin(s)` or `member(s)`
+fn parse_permission_level(permission: &str) -> Result<PermissionLevel, AppError> {
+    match permission.to_lowercase().as_str() {
+        "admin" | "admins" => Ok(PermissionLevel::Admins),
+        "member" | "members" => Ok(PermissionLevel::Members),
+        _ => Err(AppError::ValidationError("Permission must be admins or members".to_string())),
+    }
+}
+
+/// Apply the settings given in `payload` that need no lookups, keeping the
+/// others. The name and slug are checked against other groups by the caller.
+fn apply_settings(group: &mut models::group::Group, payload: &UpdateGroupRequest) -> Result<(), AppError> {
+    if let Some(description) = &payload.description {
+        group.description = description.clone();
+    }
+    if let Some(join_type) = &payload.join_type {
+        group.join_type = parse_join_type(join_type)?.as_str().to_string();
+    }
+    if let Some(post_permission) = &payload.post_permission {
+        group.post_permission = Some(parse_permission_level(post_permission)?.as_str().to_string());
+    }
+    if let Some(edit_permissions) = &payload.edit_permissions {
+        group.edit_permissions = Some(parse_permission_level(edit_permissions)?.as_str().to_string());
+    }
+    Ok(())
+}
+
+/// A slug someone picked must look like one `StringUtils::slugify` makes
+pub(crate) fn validate_slug(slug: &str) -> Result<(), AppError> {
+    if slug.is_empty() || StringUtils::slugify(slug) != slug || Uuid::parse_str(slug).is_ok() {
+        return Err(AppError::ValidationError(
+            "Slugs may only contain lowercase letters, digits and single dashes".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// The group in the path, given as its id, slug or name
+pub(crate) async fn find_group(pool: &Pool<Postgres>, group_ref: &str) -> Result<models::group::Group, AppError> {
+    models::group::Group::find_by_ref(pool, group_ref)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+/// A name another group has, had, or uses as its slug would make links by
+/// name ambiguous
+async fn ensure_name_free(pool: &Pool<Postgres>, name: &str, except_id: Option<Uuid>) -> Result<(), AppError> {
+    if models::group::Group::name_taken(pool, name, except_id).await? {
+        return Err(AppError::Conflict("A group with this name or URL already exists".to_string()));
+    }
+    Ok(())
+}
+
+async fn group_detail(pool: &Pool<Postgres>, group: models::group::Group) -> Result<GroupDetailResponse, AppError> {
+    let members = models::group_member::GroupMember::find_by_group(pool, group.id).await?;
+    let courses = models::course::Course::find_by_group(pool, group.id).await?;
+
+    Ok(GroupDetailResponse {
+        group: group.into(),
+        members: members.into_iter().map(Into::into).collect(),
+        courses: courses.into_iter().map(Into::into).collect(),
+    })
+}
+
 pub async fn create_group_handler(
     State(pool): State<Pool<Postgres>>,
     user: AuthenticatedUser,  // Add this parameter
     Json(payload): Json<CreateGroupRequest>,
 ) -> Result<(StatusCode, Json<GroupResponse>), AppError> {
-    let join_type = match payload.join_type.to_lowercase().as_str() {
-        "open" => "OPEN",
-        "requests" => "REQUESTS",
-        "closed" => "CLOSED",
-        _ => return Err(AppError::ValidationError("Invalid join type".to_string()))
+    auth::require_verified_email(&pool, user.id).await?;
+
+    let join_type = parse_join_type(&payload.join_type)?;
+    let post_permission = match payload.post_permission {
+        Some(level) => parse_permission_level(&level)?,
+        None => PermissionLevel::default(),
     };
-    
+    let edit_permissions = match payload.edit_permissions {
+        Some(level) => parse_permission_level(&level)?,
+        None => PermissionLevel::default(),
+    };
+    ensure_name_free(&pool, &payload.name, None).await?;
+
     let new_group = models::group::NewGroup {
+        slug: models::group::Group::available_slug(&pool, &payload.name).await?,
         name: payload.name,
         description: payload.description,
         owner_id: user.id,
-        join_type: join_type.to_string()
+        join_type: join_type.as_str().to_string(),
+        post_permission: post_permission.as_str().to_string(),
+        edit_permissions: edit_permissions.as_str().to_string(),
     };
 
     let group = models::group::Group::create(&pool, new_group).await?;
@@ -61,11 +184,260 @@ pub async fn create_group_handler(
     Ok((StatusCode::CREATED, Json(group.into())))
 }
 
-
 pub async fn list_groups_handler(
     State(pool): State<Pool<Postgres>>,
 ) -> Result<Json<Vec<GroupResponse>>, AppError> {
     let groups = models::group::Group::find_all(&pool).await?;
     let group_responses: Vec<GroupResponse> = groups.into_iter().map(Into::into).collect();
     Ok(Json(group_responses))
-}
\ No newline at end of file
+}
+
+pub async fn get_group_handler(
+    State(pool): State<Pool<Postgres>>,
+    Path(group_ref): Path<String>,
+) -> Result<Json<GroupDetailResponse>, AppError> {
+    let group = find_group(&pool, &group_ref).await?;
+    Ok(Json(group_detail(&pool, group).await?))
+}
+
+/// Change the settings of a group. Only the owner gets here, see
+/// `group_admin_middleware`. Renaming keeps the slug, so links to the group
+/// keep working unless a new slug is asked for.
+pub async fn update_group_handler(
+    State(pool): State<Pool<Postgres>>,
+    Path(group_ref): Path<String>,
+    Json(payload): Json<UpdateGroupRequest>,
+) -> Result<Json<GroupDetailResponse>, AppError> {
+    let mut group = find_group(&pool, &group_ref).await?;
+
+    if let Some(name) = &payload.name
+        && *name != group.name
+    {
+        if name.trim().is_empty() {
+            return Err(AppError::ValidationError("Group name cannot be empty".to_string()));
+        }
+        ensure_name_free(&pool, name, Some(group.id)).await?;
+        group.name = name.clone();
+    }
+    if let Some(slug) = &payload.slug
+        && *slug != group.slug
+    {
+        validate_slug(slug)?;
+        if models::group::Group::slug_taken(&pool, slug, Some(group.id)).await? {
+            return Err(AppError::Conflict("Another group already uses this slug".to_string()));
+        }
+        group.slug = slug.clone();
+    }
+    apply_settings(&mut group, &payload)?;
+
+    let group = models::group::Group::update(&pool, group).await?;
+    Ok(Json(group_detail(&pool, group).await?))
+}
+
+/// Delete a group with all of its courses, materials and memberships. Only
+/// the owner gets here, see `group_admin_middleware`.
+pub async fn delete_group_handler(
+    State(pool): State<Pool<Postgres>>,
+    Path(group_ref): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let group = find_group(&pool, &group_ref).await?;
+    models::group::Group::delete(&pool, group.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The group can only be offered to another member
+fn validate_nominee(owner_id: Uuid, new_owner_id: Uuid, is_member: bool) -> Result<(), AppError> {
+    if new_owner_id == owner_id {
+        return Err(AppError::ValidationError("You already own this group".to_string()));
+    }
+    if !is_member {
+        return Err(AppError::ValidationError("The new owner must be a member of the group".to_string()));
+    }
+    Ok(())
+}
+
+/// Offer the group to another member. Nothing changes until they accept;
+/// a new offer replaces the previous one.
+pub async fn nominate_owner_handler(
+    State(pool): State<Pool<Postgres>>,
+    permissions: GroupPermissions,
+    Path(group_ref): Path<String>,
+    Json(payload): Json<NominateOwnerRequest>,
+) -> Result<(StatusCode, Json<GroupOwnershipTransfer>), AppError> {
+    let group = find_group(&pool, &group_ref).await?;
+    permissions.require(group.id, GroupAction::ManageGroup).await?;
+
+    let nominee = models::group_member::GroupMember::find_by_user_and_group(&pool, payload.new_owner_id, group.id).await?;
+    validate_nominee(permissions.user_id(), payload.new_owner_id, nominee.is_some())?;
+
+    let transfer = GroupOwnershipTransfer::create(&pool, group.id, permissions.user_id(), payload.new_owner_id).await?;
+    Ok((StatusCode::CREATED, Json(transfer)))
+}
+
+/// The pending offer, visible to the owner and the nominee only
+async fn find_transfer_for(
+    pool: &Pool<Postgres>,
+    group_id: Uuid,
+    user_id: Uuid,
+) -> Result<GroupOwnershipTransfer, AppError> {
+    GroupOwnershipTransfer::find_by_group(pool, group_id)
+        .await?
+        .filter(|t| t.is_visible_to(user_id))
+        .ok_or(AppError::NotFound)
+}
+
+pub async fn get_ownership_transfer_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: AuthenticatedUser,
+    Path(group_ref): Path<String>,
+) -> Result<Json<GroupOwnershipTransfer>, AppError> {
+    let group = find_group(&pool, &group_ref).await?;
+    Ok(Json(find_transfer_for(&pool, group.id, user.id).await?))
+}
+
+/// The owner withdraws the offer, or the nominee declines it
+pub async fn cancel_ownership_transfer_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: AuthenticatedUser,
+    Path(group_ref): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let group = find_group(&pool, &group_ref).await?;
+    find_transfer_for(&pool, group.id, user.id).await?;
+    GroupOwnershipTransfer::delete(&pool, group.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The nominee takes over the group
+pub async fn accept_ownership_transfer_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: AuthenticatedUser,
+    Path(group_ref): Path<String>,
+) -> Result<Json<GroupResponse>, AppError> {
+    let group = find_group(&pool, &group_ref).await?;
+    let transfer = find_transfer_for(&pool, group.id, user.id).await?;
+    if !transfer.can_be_accepted_by(user.id) {
+        return Err(AppError::PermissionDenied("Only the nominated member can accept".to_string()));
+    }
+
+    if models::group_member::GroupMember::find_by_user_and_group(&pool, user.id, group.id).await?.is_none() {
+        GroupOwnershipTransfer::delete(&pool, group.id).await?;
+        return Err(AppError::Conflict("You are no longer a member of this group".to_string()));
+    }
+
+    let group = models::group::Group::accept_ownership_transfer(&pool, group.id, user.id)
+        .await?
+        .ok_or(AppError::Conflict("The ownership offer was withdrawn".to_string()))?;
+    tracing::info!(group = %group.name, from = %transfer.from_user_id, to = %user.id, "group ownership transferred");
+    Ok(Json(group.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_slug() {
+        assert!(validate_slug("linear-algebra").is_ok());
+        assert!(validate_slug("café-2").is_ok());
+        assert!(validate_slug("").is_err());
+        assert!(validate_slug("Linear-Algebra").is_err());
+        assert!(validate_slug("linear--algebra").is_err());
+        assert!(validate_slug("-algebra").is_err());
+        assert!(validate_slug("linear algebra").is_err());
+        assert!(validate_slug("0b7c3d0e-4b7e-4a55-9f0e-2f5c3a1d9e10").is_err());
+    }
+
+    #[test]
+    fn test_validate_nominee() {
+        let owner = Uuid::new_v4();
+        let member = Uuid::new_v4();
+        assert!(validate_nominee(owner, member, true).is_ok());
+        assert!(matches!(validate_nominee(owner, member, false), Err(AppError::ValidationError(_))));
+        assert!(matches!(validate_nominee(owner, owner, true), Err(AppError::ValidationError(_))));
+    }
+
+    #[test]
+    fn test_parse_join_type() {
+        assert_eq!(parse_join_type("open").unwrap(), JoinType::Open);
+        assert_eq!(parse_join_type("Requests").unwrap(), JoinType::Requests);
+        assert_eq!(parse_join_type("CLOSED").unwrap(), JoinType::Closed);
+        assert!(parse_join_type("invite").is_err());
+        assert!(parse_join_type("").is_err());
+    }
+
+    #[test]
+    fn test_parse_permission_level() {
+        assert_eq!(parse_permission_level("admins").unwrap(), PermissionLevel::Admins);
+        assert_eq!(parse_permission_level("Admin").unwrap(), PermissionLevel::Admins);
+        assert_eq!(parse_permission_level("MEMBERS").unwrap(), PermissionLevel::Members);
+        assert_eq!(parse_permission_level("member").unwrap(), PermissionLevel::Members);
+        assert!(parse_permission_level("owners").is_err());
+    }
+
+    fn group() -> models::group::Group {
+        models::group::Group {
+            id: Uuid::new_v4(),
+            owner_id: Uuid::new_v4(),
+            name: "Linear Algebra".to_string(),
+            slug: "linear-algebra".to_string(),
+            description: "Vectors".to_string(),
+            join_type: "OPEN".to_string(),
+            post_permission: Some("ADMIN".to_string()),
+            edit_permissions: Some("ADMIN".to_string()),
+            created_at: Utc::now(),
+        }
+    }
+
+    fn empty_update() -> UpdateGroupRequest {
+        UpdateGroupRequest {
+            name: None,
+            slug: None,
+            description: None,
+            join_type: None,
+            post_permission: None,
+            edit_permissions: None,
+        }
+    }
+
+    fn group_with(group: &models::group::Group, change: impl FnOnce(&mut models::group::Group)) -> models::group::Group {
+        let mut group = group.clone();
+        change(&mut group);
+        group
+    }
+
+    #[test]
+    fn test_settings_left_out_are_kept() {
+        let original = group();
+        let mut updated = original.clone();
+        apply_settings(&mut updated, &empty_update()).unwrap();
+        assert_eq!(updated, original);
+    }
+
+    #[test]
+    fn test_each_setting_is_updated_on_its_own() {
+        let original = group();
+
+        let mut updated = original.clone();
+        apply_settings(&mut updated, &UpdateGroupRequest { description: Some("Matrices".to_string()), ..empty_update() }).unwrap();
+        assert_eq!(updated, group_with(&original, |g| g.description = "Matrices".to_string()));
+
+        let mut updated = original.clone();
+        apply_settings(&mut updated, &UpdateGroupRequest { join_type: Some("closed".to_string()), ..empty_update() }).unwrap();
+        assert_eq!(updated, group_with(&original, |g| g.join_type = "CLOSED".to_string()));
+
+        let mut updated = original.clone();
+        apply_settings(&mut updated, &UpdateGroupRequest { post_permission: Some("members".to_string()), ..empty_update() }).unwrap();
+        assert_eq!(updated, group_with(&original, |g| g.post_permission = Some("MEMBER".to_string())));
+
+        let mut updated = original.clone();
+        apply_settings(&mut updated, &UpdateGroupRequest { edit_permissions: Some("members".to_string()), ..empty_update() }).unwrap();
+        assert_eq!(updated, group_with(&original, |g| g.edit_permissions = Some("MEMBER".to_string())));
+    }
+
+    #[test]
+    fn test_invalid_setting_is_rejected() {
+        let mut updated = group();
+        let payload = UpdateGroupRequest { post_permission: Some("everyone".to_string()), ..empty_update() };
+        assert!(matches!(apply_settings(&mut updated, &payload), Err(AppError::ValidationError(_))));
+    }
+}
@@ -9,7 +9,9 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use crate::{
     errors::AppError, middleware::AuthenticatedUser,
-    models
+    handlers::{group::find_group, join_request::MessageResponse},
+    models::{self, group::JoinType},
+    permissions::{access::role_of, GroupAction, GroupPermissions, GroupRole},
 };
 
 
@@ -32,7 +34,20 @@ impl From<models::user::User> for UserResponseForGroupMember {
 pub struct GroupMemberResponse {
     pub user: UserResponseForGroupMember,
     pub user_role: Option<String>,
-    pub joined_at: DateTime<Utc>,
+    pub joined_at: Option<DateTime<Utc>>,
+}
+
+impl From<models::group_member::GroupMemberWithUser> for GroupMemberResponse {
+    fn from(member: models::group_member::GroupMemberWithUser) -> Self {
+        GroupMemberResponse {
+            user: UserResponseForGroupMember {
+                id: member.user_id,
+                username: member.user_name,
+            },
+            user_role: member.user_role,
+            joined_at: member.joined_at,
+        }
+    }
 }
 
 
@@ -46,106 +61,133 @@ pub struct UpdateGroupMemberPayload {
     pub user_role: String,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct DeleteGroupMemberPayload {
-    pub user_id: Uuid,
-}
-
 pub async fn list_group_members_handler(
     State(pool): State<Pool<Postgres>>,
-    Path(group_name): Path<String>,
+    Path(group_ref): Path<String>,
 ) -> Result<Json<Vec<models::group_member::GroupMemberWithUser>>, AppError> {
-    let group_members = models::group_member::GroupMember::find_by_group_name(&pool, group_name).await?;
+    let group = find_group(&pool, &group_ref).await?;
+    let group_members = models::group_member::GroupMember::find_by_group(&pool, group.id).await?;
     Ok(Json(group_members))
 }
 
+/// Invite a user to the group; they join by accepting at the join
+/// endpoint. Only the owner can invite to a CLOSED group.
 pub async fn create_group_member_handler(
     State(pool): State<Pool<Postgres>>,
-    Path(group_name): Path<String>,
+    permissions: GroupPermissions,
+    Path(group_ref): Path<String>,
     Json(payload): Json<CreateGroupMemberPayload>,
-) -> Result<StatusCode, AppError> {
-    let _group = models::group::Group::find_by_name(&pool, group_name.clone())
-        .await?
-        .ok_or(AppError::NotFound)?;
+) -> Result<(StatusCode, Json<MessageResponse>), AppError> {
+    let group = find_group(&pool, &group_ref).await?;
+    let action = match group.join_type() {
+        JoinType::Closed => GroupAction::ManageGroup,
+        JoinType::Open | JoinType::Requests => GroupAction::ManageMembers,
+    };
+    permissions.require(group.id, action).await?;
 
     models::user::User::find_by_id(&pool, payload.user_id)
         .await?
         .ok_or(AppError::NotFound)?;
 
-    models::group_member::GroupMember::create(&pool, payload.user_id, group_name).await?;
-
-    Ok(StatusCode::CREATED)
-}
-
-
+    if group.owner_id == payload.user_id
+        || models::group_member::GroupMember::find_by_user_and_group(&pool, payload.user_id, group.id).await?.is_some()
+    {
+        return Err(AppError::Conflict("User is already a member of this group".to_string()));
+    }
 
-// TODO: Implement update_group_member_handler after implementing member types
-// pub async fn update_group_member_handler(
-//     State(pool): State<Pool<Postgres>>,
-//     Path((group_name, user_id)): Path<(String, Uuid)>,
-//     Json(payload): Json<UpdateGroupMemberPayload>,
-// ) -> Result<Json<GroupMemberResponse>, AppError> {
-//     let _group = models::group::Group::find_by_name(&pool, group_name)
-//         .await?
-//         .ok_or(AppError::NotFound)?;
+    models::group_invitation::GroupInvitation::create(&pool, group.id, payload.user_id, permissions.user_id())
+        .await?
+        .ok_or(AppError::Conflict("User has already been invited to this group".to_string()))?;
 
-//     let mut group_member = models::group_member::GroupMember::find_by_user_and_group(&pool, user_id, group_name)
-//         .await?
-//         .ok_or(AppError::NotFound)?;
+    Ok((StatusCode::ACCEPTED, Json(MessageResponse { message: "Invitation sent".to_string() })))
+}
 
-//     group_member.user_role = payload.user_role;
+/// A member with the role they hold in the group; the owner is listed as
+/// `owner` whether or not they have a membership row
+async fn membership(
+    pool: &Pool<Postgres>,
+    group: &models::group::Group,
+    user_id: Uuid,
+) -> Result<GroupMemberResponse, AppError> {
+    let role = role_of(pool, group, user_id).await?.ok_or(AppError::NotFound)?;
+
+    let mut response = match models::group_member::GroupMember::find_with_user(pool, group.id, user_id).await? {
+        Some(member) => GroupMemberResponse::from(member),
+        None => {
+            let user = models::user::User::find_by_id(pool, user_id)
+                .await?
+                .ok_or(AppError::NotFound)?;
+            GroupMemberResponse { user: user.into(), user_role: None, joined_at: None }
+        }
+    };
+    response.user_role = Some(role.as_str().to_string());
+    Ok(response)
+}
 
-//     let updated_group_member = models::group_member::GroupMember::update(&pool, group_member).await?;
+pub async fn get_group_member_detail_handler(
+    State(pool): State<Pool<Postgres>>,
+    Path((group_ref, user_id)): Path<(String, Uuid)>,
+) -> Result<Json<GroupMemberResponse>, AppError> {
+    let group = find_group(&pool, &group_ref).await?;
+    Ok(Json(membership(&pool, &group, user_id).await?))
+}
 
-//     Ok(Json(GroupMemberResponse::from_group_member(&pool, updated_group_member).await?))
-// }
+/// Change a member's role. Only roles below the caller's own can be handed
+/// out, and only to members below them.
+pub async fn update_group_member_handler(
+    State(pool): State<Pool<Postgres>>,
+    permissions: GroupPermissions,
+    Path((group_ref, user_id)): Path<(String, Uuid)>,
+    Json(payload): Json<UpdateGroupMemberPayload>,
+) -> Result<Json<GroupMemberResponse>, AppError> {
+    let role = GroupRole::parse(&payload.user_role.to_lowercase())
+        .ok_or(AppError::ValidationError("Role must be member, moderator or admin".to_string()))?;
+    let group = find_group(&pool, &group_ref).await?;
+    permissions.require_over_member(group.id, user_id, Some(role)).await?;
+
+    models::group_member::GroupMember::update(&pool, user_id, group.id, role.as_str().to_string()).await?;
+
+    Ok(Json(membership(&pool, &group, user_id).await?))
+}
 
 pub async fn delete_group_member_handler(
     State(pool): State<Pool<Postgres>>,
-    Path(group_name): Path<String>,
-    Json(payload): Json<DeleteGroupMemberPayload>,
+    permissions: GroupPermissions,
+    Path((group_ref, user_id)): Path<(String, Uuid)>,
 ) -> Result<StatusCode, AppError> {
-    let _group_member = models::group_member::GroupMember::find_by_user_and_group(&pool, payload.user_id, group_name.clone())
-        .await?
-        .ok_or(AppError::NotFound)?;
+    let group = find_group(&pool, &group_ref).await?;
+    permissions.require_over_member(group.id, user_id, None).await?;
 
-    models::group_member::GroupMember::delete(&pool, payload.user_id, group_name).await?;
+    models::group_member::GroupMember::delete(&pool, user_id, group.id).await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
-// // Handler for /api/groups/<uuid:group_name>/members/self/
-// pub async fn get_self_group_membership_handler(
-//     State(pool): State<Pool<Postgres>>,
-//     user: AuthenticatedUser,
-//     Path(group_name): Path<String>,
-//     // TODO: Extract authenticated user ID here
-// ) -> Result<Json<GroupMemberResponse>, AppError> {
-//     let _group = models::group::Group::find_by_name(&pool, group_name)
-//         .await?
-//         .ok_or(AppError::NotFound)?;
-
-//     // Placeholder for authenticated user ID
-//     let current_user_id = user.id;
-
-//     let group_member = models::group_member::GroupMember::find_by_user_and_group_name(&pool, current_user_id, group_name)
-//         .await?
-//         .ok_or(AppError::NotFound)?;
-
-//     Ok(Json(GroupMemberResponse::from_group_member(&pool, group_member).await?))
-// }
+pub async fn get_self_group_membership_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: AuthenticatedUser,
+    Path(group_ref): Path<String>,
+) -> Result<Json<GroupMemberResponse>, AppError> {
+    let group = find_group(&pool, &group_ref).await?;
+    Ok(Json(membership(&pool, &group, user.id).await?))
+}
 
+/// Leave the group. The owner has to hand the group to someone else first.
 pub async fn leave_group_membership_handler(
     State(pool): State<Pool<Postgres>>,
     user: AuthenticatedUser,
-    Path(group_name): Path<String>,
+    Path(group_ref): Path<String>,
 ) -> Result<StatusCode, AppError> {
+    let group = find_group(&pool, &group_ref).await?;
+    if group.owner_id == user.id {
+        return Err(AppError::Conflict("Transfer ownership of the group before leaving it".to_string()));
+    }
 
-    let _group_member = models::group_member::GroupMember::find_by_user_and_group(&pool, user.id, group_name.clone())
+    let _group_member = models::group_member::GroupMember::find_by_user_and_group(&pool, user.id, group.id)
         .await?
         .ok_or(AppError::NotFound)?;
 
-    models::group_member::GroupMember::delete(&pool, user.id, group_name).await?;
+    models::group_member::GroupMember::delete(&pool, user.id, group.id).await?;
 
     Ok(StatusCode::NO_CONTENT)
-}
\ No newline at end of file
+}
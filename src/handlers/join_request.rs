@@ -9,9 +9,12 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
 use crate::{
+    auth,
     errors::AppError,
-    models,
+    handlers::group::find_group,
+    models::{self, group::JoinType},
     middleware::AuthenticatedUser,
+    permissions::{GroupAction, GroupPermissions},
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,15 +38,11 @@ pub struct JoinRequestResponse {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct CreateJoinRequestPayload {
-    pub user_id: Uuid, // This will likely come from authenticated user in real implementation
-}
-
 #[derive(Debug, Deserialize)]
 pub struct RespondToJoinRequestPayload {
-    pub group_name: String,
-    pub user_id: Uuid,
+    /// Optional, the group comes from the path. If given it must name the
+    /// same group, by id, slug or name.
+    pub group_name: Option<String>,
     pub action: String, // "accept" or "decline"
 }
 
@@ -54,61 +53,160 @@ pub struct MessageResponse {
 
 pub async fn list_join_requests_handler(
     State(pool): State<Pool<Postgres>>,
-    Path(group_name): Path<String>,
+    permissions: GroupPermissions,
+    Path(group_ref): Path<String>,
 ) -> Result<Json<Vec<models::join_request::JoinRequestWithUser>>, AppError> {
-    // // Check if group exists (optional, but good practice)
-    // let _group = Group::find_by_name(&pool, group_name)
-    //     .await?
-    //     .ok_or(AppError::NotFound)?;
+    let group = find_group(&pool, &group_ref).await?;
+    permissions.require(group.id, GroupAction::ManageMembers).await?;
 
-    let join_requests = models::join_request::JoinRequest::find_by_group_name(&pool, group_name).await?;
+    let join_requests = models::join_request::JoinRequest::find_by_group(&pool, group.id).await?;
     Ok(Json(join_requests))
 }
 
+/// What a join attempt does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JoinOutcome {
+    Join,
+    Request,
+    AlreadyMember,
+    AlreadyRequested,
+    Closed,
+}
+
+/// An invitation lets the user in whatever the join type; otherwise OPEN
+/// groups let them in right away, REQUESTS groups queue one request and
+/// CLOSED groups refuse
+fn join_outcome(join_type: JoinType, is_member: bool, has_request: bool, is_invited: bool) -> JoinOutcome {
+    if is_member {
+        return JoinOutcome::AlreadyMember;
+    }
+    if is_invited {
+        return JoinOutcome::Join;
+    }
+    match join_type {
+        JoinType::Open => JoinOutcome::Join,
+        JoinType::Requests if has_request => JoinOutcome::AlreadyRequested,
+        JoinType::Requests => JoinOutcome::Request,
+        JoinType::Closed => JoinOutcome::Closed,
+    }
+}
+
+/// Join a group the way its `join_type` allows, or accept an invitation to
+/// it: OPEN groups add the caller right away, REQUESTS groups queue a
+/// request for the owner and CLOSED groups only take invited users.
 pub async fn create_join_request_handler(
     State(pool): State<Pool<Postgres>>,
-    Path(group_name): Path<String>,
+    Path(group_ref): Path<String>,
     user: AuthenticatedUser,
-) -> Result<StatusCode, AppError> {
-    // Check if group exists
-    let _group = models::group::Group::find_by_name(&pool, group_name.clone())
-        .await?
-        .ok_or(AppError::NotFound)?;
+) -> Result<(StatusCode, Json<MessageResponse>), AppError> {
+    auth::require_verified_email(&pool, user.id).await?;
 
-    models::join_request::JoinRequest::create(&pool, models::join_request::JoinRequest {
-        group_name: group_name,
-        user_id: user.id,
-        created_at: None,
-    }).await?;
+    let group = find_group(&pool, &group_ref).await?;
 
-    Ok(StatusCode::CREATED)
+    let is_member = group.owner_id == user.id
+        || models::group_member::GroupMember::find_by_user_and_group(&pool, user.id, group.id).await?.is_some();
+    let has_request = models::join_request::JoinRequest::find_by_group_and_user(&pool, group.id, user.id).await?.is_some();
+    let is_invited = models::group_invitation::GroupInvitation::find_by_group_and_user(&pool, group.id, user.id).await?.is_some();
+
+    match join_outcome(group.join_type(), is_member, has_request, is_invited) {
+        JoinOutcome::Join => {
+            models::group_member::GroupMember::create(&pool, user.id, group.id).await?;
+            models::group_invitation::GroupInvitation::delete(&pool, group.id, user.id).await?;
+            models::join_request::JoinRequest::delete(&pool, group.id, user.id).await?;
+            Ok((StatusCode::CREATED, Json(MessageResponse { message: "Joined group".to_string() })))
+        }
+        JoinOutcome::Request => {
+            models::join_request::JoinRequest::create(&pool, models::join_request::JoinRequest {
+                group_id: group.id,
+                user_id: user.id,
+                created_at: None,
+            }).await?;
+            Ok((StatusCode::ACCEPTED, Json(MessageResponse { message: "Join request sent to the group owner".to_string() })))
+        }
+        JoinOutcome::AlreadyMember => Err(AppError::Conflict("You are already a member of this group".to_string())),
+        JoinOutcome::AlreadyRequested => Err(AppError::Conflict("You have already asked to join this group".to_string())),
+        JoinOutcome::Closed => Err(AppError::PermissionDenied(
+            "This group is closed; only users the owner invites can join".to_string(),
+        )),
+    }
+}
+
+/// Turn down an invitation to the group
+pub async fn decline_invitation_handler(
+    State(pool): State<Pool<Postgres>>,
+    Path(group_ref): Path<String>,
+    user: AuthenticatedUser,
+) -> Result<StatusCode, AppError> {
+    let group = find_group(&pool, &group_ref).await?;
+    if !models::group_invitation::GroupInvitation::delete(&pool, group.id, user.id).await? {
+        return Err(AppError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn respond_to_join_request_handler(
     State(pool): State<Pool<Postgres>>,
+    permissions: GroupPermissions,
+    Path((group_ref, user_id)): Path<(String, Uuid)>,
     Json(payload): Json<RespondToJoinRequestPayload>,
 ) -> Result<Json<MessageResponse>, AppError> {
-    // // Verify group exists
-    // let _group = Group::find_by_name(&pool, group_name.clone())
-    //     .await?
-    //     .ok_or(AppError::NotFound)?;
+    let group = find_group(&pool, &group_ref).await?;
+    permissions.require(group.id, GroupAction::ManageMembers).await?;
 
-    models::join_request::JoinRequest::find_by_group_and_user(&pool, payload.group_name.clone(), payload.user_id)
+    if let Some(group_name) = &payload.group_name
+        && models::group::Group::find_by_ref(&pool, group_name).await?.is_none_or(|named| named.id != group.id)
+    {
+        return Err(AppError::ValidationError("group_name does not match the group in the path".to_string()));
+    }
+
+    models::join_request::JoinRequest::find_by_group_and_user(&pool, group.id, user_id)
         .await?
         .ok_or(AppError::NotFound)?;
 
     match payload.action.to_lowercase().as_str() {
         "accept" => {
-            // Add user to group members (this would involve a new function in GroupMember model)
-            // For now, just delete the join request
-            models::join_request::JoinRequest::delete(&pool, payload.group_name.clone(), payload.user_id).await?;
-            models::group_member::GroupMember::create(&pool, payload.user_id, payload.group_name).await?;
+            models::join_request::JoinRequest::delete(&pool, group.id, user_id).await?;
+            // They may have joined through an invitation in the meantime
+            if models::group_member::GroupMember::find_by_user_and_group(&pool, user_id, group.id).await?.is_none() {
+                models::group_member::GroupMember::create(&pool, user_id, group.id).await?;
+            }
             Ok(Json(MessageResponse { message: "User added to group".to_string() }))
         }
         "decline" => {
-            models::join_request::JoinRequest::delete(&pool, payload.group_name.clone(), payload.user_id).await?;
+            models::join_request::JoinRequest::delete(&pool, group.id, user_id).await?;
             Ok(Json(MessageResponse { message: "Join request declined".to_string() }))
         }
         _ => Err(AppError::ValidationError("Invalid action. Must be 'accept' or 'decline'".to_string())),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_group_joins_right_away() {
+        assert_eq!(join_outcome(JoinType::Open, false, false, false), JoinOutcome::Join);
+    }
+
+    #[test]
+    fn test_requests_group_queues_one_request() {
+        assert_eq!(join_outcome(JoinType::Requests, false, false, false), JoinOutcome::Request);
+        assert_eq!(join_outcome(JoinType::Requests, false, true, false), JoinOutcome::AlreadyRequested);
+    }
+
+    #[test]
+    fn test_closed_group_needs_an_invitation() {
+        assert_eq!(join_outcome(JoinType::Closed, false, false, false), JoinOutcome::Closed);
+        assert_eq!(join_outcome(JoinType::Closed, false, true, false), JoinOutcome::Closed);
+        assert_eq!(join_outcome(JoinType::Closed, false, false, true), JoinOutcome::Join);
+        assert_eq!(join_outcome(JoinType::Requests, false, true, true), JoinOutcome::Join);
+    }
+
+    #[test]
+    fn test_members_cannot_join_again() {
+        for join_type in [JoinType::Open, JoinType::Requests, JoinType::Closed] {
+            assert_eq!(join_outcome(join_type, true, false, true), JoinOutcome::AlreadyMember);
+        }
+    }
+}
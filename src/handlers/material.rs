@@ -8,13 +8,16 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
 use crate::{
-    errors::AppError, middleware::AuthenticatedUser, models
+    errors::AppError, handlers::course::find_course, models,
+    permissions::{GroupAction, GroupPermissions},
 };
 
 // Material-related request/response DTOs
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateMaterialRequest {
+    /// Id, slug or name of the group
     pub group_name: String,
+    /// Id, slug or name of the course within the group
     pub course_name: String,
     pub title: String,
     pub file: Option<String>,
@@ -33,8 +36,8 @@ pub struct UpdateMaterialRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MaterialResponse {
     pub id: Uuid,
-    pub group_name: String,
-    pub course_name: String,
+    pub group_id: Uuid,
+    pub course_id: Uuid,
     
     pub title: String,
     pub file: Option<String>,
@@ -44,15 +47,15 @@ pub struct MaterialResponse {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     
-    pub creator_id: Uuid,
+    pub creator_id: Option<Uuid>,
 }
 
 impl From<models::material::Material> for MaterialResponse {
     fn from(material: models::material::Material) -> Self {
         MaterialResponse {
             id: material.id,
-            group_name: material.group_name,
-            course_name: material.course_name,
+            group_id: material.group_id,
+            course_id: material.course_id,
             
             title: material.title,
             file: material.file,
@@ -67,21 +70,28 @@ impl From<models::material::Material> for MaterialResponse {
     }
 }
 
+async fn find_material(pool: &Pool<Postgres>, id: Uuid) -> Result<models::material::Material, AppError> {
+    models::material::Material::find_by_id(pool, id)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
 // Material handlers
 pub async fn create_material_handler(
     State(pool): State<Pool<Postgres>>,
-    user: AuthenticatedUser,  
+    permissions: GroupPermissions,
     Json(payload): Json<CreateMaterialRequest>,
 ) -> Result<(StatusCode, Json<MaterialResponse>), AppError> {
-    dbg!(user.id);
+    let course = find_course(&pool, &payload.group_name, &payload.course_name).await?;
+    permissions.require(course.group_id, GroupAction::Post).await?;
+
     let new_material = models::material::NewMaterial {
-        group_name : payload.group_name,
-        course_name : payload.course_name,
+        course_id : course.id,
         title : payload.title,
         file : payload.file,
         url : payload.url,
         material_type: payload.material_type,
-        creator : user.id
+        creator : permissions.user_id()
     };
 
     let material = models::material::Material::create(&pool, new_material).await?;
@@ -90,10 +100,10 @@ pub async fn create_material_handler(
 
 pub async fn list_materials_by_course_handler(
     State(pool): State<Pool<Postgres>>,
-    Path(group_name): Path<String>,
-    Path(course_name): Path<String>,
+    Path((group_ref, course_ref)): Path<(String, String)>,
 ) -> Result<Json<Vec<MaterialResponse>>, AppError> {
-    let materials = models::material::Material::find_by_course(&pool, group_name, course_name).await?;
+    let course = find_course(&pool, &group_ref, &course_ref).await?;
+    let materials = models::material::Material::find_by_course(&pool, course.id).await?;
     let responses: Vec<MaterialResponse> = materials.into_iter().map(Into::into).collect();
     Ok(Json(responses))
 }
@@ -102,17 +112,18 @@ pub async fn get_material_handler(
     State(pool): State<Pool<Postgres>>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<MaterialResponse>, AppError> {
-    let material = models::material::Material::find_by_id(&pool, id)
-        .await?
-        .ok_or(AppError::NotFound)?;
-    Ok(Json(material.into()))
+    Ok(Json(find_material(&pool, id).await?.into()))
 }
 
 pub async fn update_material_handler(
     State(pool): State<Pool<Postgres>>,
+    permissions: GroupPermissions,
     Path(id): Path<Uuid>,
     Json(payload): Json<UpdateMaterialRequest>,
 ) -> Result<Json<MaterialResponse>, AppError> {
+    let material = find_material(&pool, id).await?;
+    permissions.require_on(material.group_id, GroupAction::Edit, material.creator).await?;
+
     let material = models::material::Material::update(
         &pool,
         id,
@@ -126,8 +137,12 @@ pub async fn update_material_handler(
 
 pub async fn delete_material_handler(
     State(pool): State<Pool<Postgres>>,
+    permissions: GroupPermissions,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, AppError> {
+    let material = find_material(&pool, id).await?;
+    permissions.require_on(material.group_id, GroupAction::Delete, material.creator).await?;
+
     models::material::Material::delete(&pool, id).await?;
     Ok(StatusCode::NO_CONTENT)
 }
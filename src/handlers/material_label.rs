@@ -7,7 +7,8 @@ use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 
 use crate::{
-    errors::AppError, models
+    errors::AppError, handlers::group::find_group, models,
+    permissions::{GroupAction, GroupPermissions},
 };
 
 
@@ -15,6 +16,7 @@ use crate::{
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateMaterialLabelRequest {
     pub label_name: String,
+    /// Id, slug or name of the material's group
     pub group_name: String,
     pub number: i32,
 }
@@ -50,17 +52,37 @@ impl From<models::material_label::MaterialLabel> for MaterialLabelResponse {
 }
 
 
+/// Labelling a material counts as editing it
+async fn require_edit(
+    pool: &Pool<Postgres>,
+    permissions: &GroupPermissions,
+    material_id: Uuid,
+) -> Result<models::material::Material, AppError> {
+    let material = models::material::Material::find_by_id(pool, material_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    permissions.require_on(material.group_id, GroupAction::Edit, material.creator).await?;
+    Ok(material)
+}
+
 // MaterialLabel handlers
 pub async fn create_material_label_handler(
     State(pool): State<Pool<Postgres>>,
+    permissions: GroupPermissions,
     Path(material_id): Path<Uuid>,
     Json(payload): Json<CreateMaterialLabelRequest>,
 ) -> Result<(StatusCode, Json<MaterialLabelResponse>), AppError> {
+    let material = require_edit(&pool, &permissions, material_id).await?;
+    let group = find_group(&pool, &payload.group_name).await?;
+    if group.id != material.group_id {
+        return Err(AppError::ValidationError("Label must belong to the material's group".to_string()));
+    }
+
     let material_label = models::material_label::MaterialLabel::create(
         &pool,
         models::material_label::MaterialLabel { 
-            material_id: material_id, 
-            group_name: payload.group_name, 
+            material_id, 
+            group_id: group.id, 
             label_name: payload.label_name, 
             number: payload.number 
         }
@@ -79,9 +101,11 @@ pub async fn list_material_labels_handler(
 
 pub async fn delete_material_label_handler(
     State(pool): State<Pool<Postgres>>,
-    Path(material_id): Path<Uuid>,
-    Json(label_name): Json<String>
+    permissions: GroupPermissions,
+    Path((material_id, label_name)): Path<(Uuid, String)>,
 ) -> Result<StatusCode, AppError> {
+    require_edit(&pool, &permissions, material_id).await?;
+
     models::material_label::MaterialLabel::delete(&pool, material_id, label_name).await?;
     Ok(StatusCode::NO_CONTENT)
 }
@@ -0,0 +1,126 @@
+// src/handlers/mfa.rs
+use axum::{
+    extract::{State, Json},
+    http::{HeaderMap, StatusCode},
+};
+use chrono::{DateTime, Utc};
+use sqlx::{Pool, Postgres};
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    auth,
+    errors::AppError,
+    handlers::auth::{get_jwt_manager, AuthResponse},
+    middleware::{AuthenticatedUser, ClientInfo},
+    models::user::User,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TotpEnrollmentResponse {
+    pub secret: String,
+    pub provisioning_uri: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaLoginRequest {
+    pub mfa_token: String,
+    pub code: String,
+}
+
+pub async fn enroll_totp_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: AuthenticatedUser,
+) -> Result<Json<TotpEnrollmentResponse>, AppError> {
+    let user = User::find_by_id(&pool, user.id)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    let enrollment = auth::begin_totp_enrollment(&pool, &user).await?;
+
+    Ok(Json(TotpEnrollmentResponse {
+        secret: enrollment.secret,
+        provisioning_uri: enrollment.provisioning_uri,
+    }))
+}
+
+pub async fn confirm_totp_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: AuthenticatedUser,
+    Json(payload): Json<MfaCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, AppError> {
+    let recovery_codes = auth::confirm_totp_enrollment(&pool, user.id, &payload.code).await?;
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+pub async fn disable_totp_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: AuthenticatedUser,
+    Json(payload): Json<MfaCodeRequest>,
+) -> Result<StatusCode, AppError> {
+    auth::disable_totp(&pool, user.id, &payload.code).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn mfa_login_handler(
+    State(pool): State<Pool<Postgres>>,
+    client: ClientInfo,
+    headers: HeaderMap,
+    Json(payload): Json<MfaLoginRequest>,
+) -> Result<(HeaderMap, Json<AuthResponse>), AppError> {
+    let jwt_manager = get_jwt_manager()?;
+
+    let claims = jwt_manager
+        .verify_mfa_pending_token(&payload.mfa_token)
+        .map_err(|_| AppError::Unauthorized)?;
+    let user_id = claims.user_id().map_err(|_| AppError::Unauthorized)?;
+    let token_id = claims.token_id().map_err(|_| AppError::Unauthorized)?;
+    if auth::is_access_token_revoked(&token_id) {
+        return Err(AppError::Unauthorized);
+    }
+
+    let user = User::find_by_id(&pool, user_id)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    // Password changed since the first step
+    if claims.token_version != user.token_version {
+        return Err(AppError::Unauthorized);
+    }
+
+    // Second factor guesses count against the same lockout as passwords
+    let attempt = auth::begin_login_attempt(&pool, Some(user.id), &user.email, client.ip_address.as_deref()).await?;
+
+    if !auth::verify_second_factor(&pool, user.id, &payload.code).await? {
+        auth::record_login_failure(&pool, &attempt, Some(user.id)).await?;
+        return Err(AppError::ValidationError("Invalid authentication code".to_string()));
+    }
+
+    // A pending token starts one session at most
+    let expires_at = DateTime::from_timestamp(claims.exp as i64, 0).unwrap_or_else(Utc::now);
+    if !auth::consume_single_use_token(&pool, user.id, token_id, expires_at).await? {
+        return Err(AppError::Unauthorized);
+    }
+    auth::record_login_success(&pool, &attempt, user.id).await?;
+
+    let tokens = auth::start_session(&pool, jwt_manager, &user, &client).await?;
+
+    let mut auth_response = AuthResponse {
+        user: user.into(),
+        access_token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+        expires_in: 15 * 60, // 15 minutes
+    };
+    let cookies = auth_response.take_cookies(auth::wants_cookie_session(&headers));
+
+    Ok((cookies, Json(auth_response)))
+}
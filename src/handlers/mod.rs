@@ -5,4 +5,11 @@ pub mod group_member;
 pub mod course;
 pub mod material;
 pub mod comment;
-pub mod material_label;
\ No newline at end of file
+pub mod material_label;
+pub mod mfa;
+pub mod access_token;
+pub mod session;
+pub mod well_known;
+pub mod oidc;
+pub mod account;
+pub mod admin;
@@ -0,0 +1,53 @@
+// src/handlers/oidc.rs
+use axum::{
+    extract::{State, Json},
+    http::{header, HeaderMap},
+};
+use sqlx::{Pool, Postgres};
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    auth,
+    errors::AppError,
+    handlers::auth::{complete_login, LoginResponse},
+    middleware::ClientInfo,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OidcAuthorizeResponse {
+    pub authorization_url: String,
+}
+
+/// Parameters the identity provider appended to the redirect URL
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OidcCallbackRequest {
+    pub code: String,
+    pub state: String,
+}
+
+pub async fn oidc_authorize_handler(
+    State(pool): State<Pool<Postgres>>,
+    client: ClientInfo,
+) -> Result<(HeaderMap, Json<OidcAuthorizeResponse>), AppError> {
+    let authorization = auth::begin_oidc_login(&pool, client.ip_address.as_deref()).await?;
+
+    let cookies = auth::oidc_state_cookie(&authorization.state);
+    Ok((cookies, Json(OidcAuthorizeResponse { authorization_url: authorization.url })))
+}
+
+pub async fn oidc_callback_handler(
+    State(pool): State<Pool<Postgres>>,
+    client: ClientInfo,
+    headers: HeaderMap,
+    Json(payload): Json<OidcCallbackRequest>,
+) -> Result<(HeaderMap, Json<LoginResponse>), AppError> {
+    auth::verify_oidc_state(&headers, &payload.state)?;
+    let user = auth::complete_oidc_login(&pool, &payload.code, &payload.state).await?;
+    let mut response = complete_login(&pool, user, &client).await?;
+
+    let mut cookies = response.take_cookies(auth::wants_cookie_session(&headers));
+    for value in auth::clear_oidc_state_cookie().get_all(header::SET_COOKIE) {
+        cookies.append(header::SET_COOKIE, value.clone());
+    }
+    Ok((cookies, Json(response)))
+}
@@ -0,0 +1,74 @@
+// src/handlers/session.rs
+use axum::{
+    extract::{State, Path, Json},
+    http::StatusCode,
+};
+use sqlx::{Pool, Postgres};
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    auth,
+    errors::AppError,
+    handlers::auth::MessageResponse,
+    middleware::AuthenticatedUser,
+    models::session::Session,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionResponse {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    /// Whether this is the session making the request
+    pub current: bool,
+}
+
+impl SessionResponse {
+    fn new(session: Session, current_session: Option<Uuid>) -> Self {
+        Self {
+            current: current_session == Some(session.id),
+            id: session.id,
+            user_agent: session.user_agent,
+            ip_address: session.ip_address,
+            created_at: session.created_at,
+            last_used_at: session.last_used_at,
+        }
+    }
+}
+
+pub async fn list_sessions_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: AuthenticatedUser,
+) -> Result<Json<Vec<SessionResponse>>, AppError> {
+    let sessions = auth::list_sessions(&pool, user.id).await?;
+    Ok(Json(
+        sessions
+            .into_iter()
+            .map(|session| SessionResponse::new(session, user.session_id))
+            .collect(),
+    ))
+}
+
+pub async fn revoke_session_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: AuthenticatedUser,
+    Path(session_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    auth::revoke_session(&pool, user.id, session_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn revoke_other_sessions_handler(
+    State(pool): State<Pool<Postgres>>,
+    user: AuthenticatedUser,
+) -> Result<Json<MessageResponse>, AppError> {
+    let revoked = auth::revoke_other_sessions(&pool, user.id, user.session_id).await?;
+
+    Ok(Json(MessageResponse {
+        message: format!("Signed out of {} other session(s)", revoked),
+    }))
+}
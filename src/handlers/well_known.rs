@@ -0,0 +1,10 @@
+// src/handlers/well_known.rs
+use axum::extract::Json;
+use jsonwebtoken::jwk::JwkSet;
+
+use crate::{errors::AppError, handlers::auth::get_jwt_manager};
+
+/// Public keys that verify our access tokens, for services checking them offline
+pub async fn jwks_handler() -> Result<Json<JwkSet>, AppError> {
+    Ok(Json(get_jwt_manager()?.jwks().clone()))
+}
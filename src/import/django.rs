@@ -0,0 +1,692 @@
+// src/import/django.rs
+//! Import of a `manage.py dumpdata` JSON export from the Django API.
+//!
+//! Records are matched by model name (`users.user`, `groups_courses.group`,
+//! `materials.materialcomment`, ...); Django's own `auth`, `admin`,
+//! `contenttypes` and `sessions` apps are ignored. Group, course, material
+//! and user ids are kept when they are UUIDs; other Django keys are turned
+//! into UUIDv5s of the model and key. Labels are keyed by group and name.
+//!
+//! Password hashes are kept as they are: `pbkdf2_sha256$...` is accepted at
+//! login and rehashed to Argon2id then. Django's `argon2$...` hashes are
+//! converted to the PHC format; anything else gets an unusable password and
+//! the user has to reset it. Django never verified email addresses, so
+//! imported users start out unverified, and users Django had deactivated
+//! (`is_active: false`) are imported disabled.
+//!
+//! Running the import twice is safe: users are matched by email and
+//! groups, courses and materials by the id derived from their Django key.
+//! Comments are only imported together with their material.
+
+use std::{collections::HashMap, fmt};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    models::{course::Course, group::Group},
+    utils::{EmailUtils, PasswordUtils, TokenUtils, DJANGO_PBKDF2_PREFIX},
+};
+
+/// Django apps whose records have no counterpart here
+const IGNORED_APPS: [&str; 5] = ["auth", "admin", "contenttypes", "sessions", "token_blacklist"];
+
+/// A primary or foreign key value, which Django dumps as a string (UUID) or
+/// a number (auto increment)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DjangoKey(String);
+
+/// Namespace of the ids derived from Django keys that are not UUIDs
+const DJANGO_KEY_NAMESPACE: Uuid = Uuid::from_u128(0x5d0f_3c2a_8e1b_4f6d_9a27_41c8_b3e5_0f92);
+
+impl DjangoKey {
+    fn as_uuid(&self) -> Option<Uuid> {
+        Uuid::parse_str(&self.0).ok()
+    }
+
+    /// The key itself when it is a UUID, otherwise a UUIDv5 of the model and
+    /// key, so every run of the import gives a record the same id
+    fn stable_id(&self, model: &str) -> Uuid {
+        self.as_uuid()
+            .unwrap_or_else(|| Uuid::new_v5(&DJANGO_KEY_NAMESPACE, format!("{}:{}", model, self.0).as_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for DjangoKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Text(String),
+            Number(i64),
+        }
+        Ok(match Raw::deserialize(deserializer)? {
+            Raw::Text(text) => DjangoKey(text),
+            Raw::Number(number) => DjangoKey(number.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for DjangoKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DumpRecord {
+    model: String,
+    pk: DjangoKey,
+    fields: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DjangoUser {
+    pub email: String,
+    pub username: String,
+    pub password: String,
+    pub date_joined: Option<String>,
+    #[serde(default = "default_is_active")]
+    pub is_active: bool,
+}
+
+fn default_is_active() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DjangoProfile {
+    pub user: DjangoKey,
+    pub bio: Option<String>,
+    #[serde(alias = "image")]
+    pub profile_picture: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DjangoGroup {
+    pub owner: DjangoKey,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub join_type: Option<String>,
+    pub post_permission: Option<String>,
+    pub edit_permissions: Option<String>,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DjangoGroupMember {
+    pub group: DjangoKey,
+    pub user: DjangoKey,
+    pub user_role: Option<String>,
+    pub joined_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DjangoCourse {
+    pub group: DjangoKey,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DjangoLabel {
+    pub group: DjangoKey,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DjangoMaterial {
+    pub course: DjangoKey,
+    pub title: String,
+    pub file: Option<String>,
+    pub url: Option<String>,
+    #[serde(rename = "type")]
+    pub material_type: String,
+    #[serde(alias = "owner")]
+    pub creator: DjangoKey,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DjangoMaterialLabel {
+    pub material: DjangoKey,
+    pub label: DjangoKey,
+    pub number: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DjangoComment {
+    pub material: DjangoKey,
+    #[serde(alias = "User")]
+    pub user: Option<DjangoKey>,
+    #[serde(alias = "Content")]
+    pub content: String,
+    #[serde(alias = "CreatedAt")]
+    pub created_at: Option<String>,
+}
+
+/// The records of a dump, sorted by kind
+#[derive(Debug, Default)]
+pub struct DjangoDump {
+    pub users: Vec<(DjangoKey, DjangoUser)>,
+    pub profiles: Vec<DjangoProfile>,
+    pub groups: Vec<(DjangoKey, DjangoGroup)>,
+    pub members: Vec<DjangoGroupMember>,
+    pub courses: Vec<(DjangoKey, DjangoCourse)>,
+    pub labels: Vec<(DjangoKey, DjangoLabel)>,
+    pub materials: Vec<(DjangoKey, DjangoMaterial)>,
+    pub material_labels: Vec<DjangoMaterialLabel>,
+    pub comments: Vec<DjangoComment>,
+    /// Model names that were neither imported nor ignored on purpose
+    pub unknown_models: Vec<String>,
+}
+
+/// What an import did
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub users: usize,
+    pub matched_users: usize,
+    pub unusable_passwords: usize,
+    pub disabled_users: usize,
+    pub groups: usize,
+    pub renamed_groups: Vec<(String, String)>,
+    pub members: usize,
+    pub courses: usize,
+    pub labels: usize,
+    pub materials: usize,
+    pub material_labels: usize,
+    pub comments: usize,
+    pub skipped: Vec<String>,
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "users:           {} new, {} matched by email", self.users, self.matched_users)?;
+        writeln!(f, "  without usable password: {}", self.unusable_passwords)?;
+        writeln!(f, "  disabled:                {}", self.disabled_users)?;
+        writeln!(f, "groups:          {}", self.groups)?;
+        writeln!(f, "members:         {}", self.members)?;
+        writeln!(f, "courses:         {}", self.courses)?;
+        writeln!(f, "labels:          {}", self.labels)?;
+        writeln!(f, "materials:       {}", self.materials)?;
+        writeln!(f, "material labels: {}", self.material_labels)?;
+        writeln!(f, "comments:        {}", self.comments)?;
+        for (from, to) in &self.renamed_groups {
+            writeln!(f, "renamed group {:?} to {:?}, the name was taken", from, to)?;
+        }
+        for reason in &self.skipped {
+            writeln!(f, "skipped {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a dump and sort its records by kind
+pub fn parse_dump(json: &str) -> Result<DjangoDump> {
+    let records: Vec<DumpRecord> = serde_json::from_str(json).context("Not a Django dumpdata JSON array")?;
+
+    let mut dump = DjangoDump::default();
+    for record in records {
+        let (app, model) = record.model.split_once('.').unwrap_or(("", record.model.as_str()));
+        if IGNORED_APPS.contains(&app) {
+            continue;
+        }
+
+        let context = || format!("Invalid {} record {}", record.model, record.pk);
+        match model.to_lowercase().as_str() {
+            "user" | "customuser" => dump.users.push((record.pk.clone(), fields(&record).with_context(context)?)),
+            "profile" | "userprofile" => dump.profiles.push(fields(&record).with_context(context)?),
+            "group" | "studygroup" => dump.groups.push((record.pk.clone(), fields(&record).with_context(context)?)),
+            "groupmember" | "membership" => dump.members.push(fields(&record).with_context(context)?),
+            "course" => dump.courses.push((record.pk.clone(), fields(&record).with_context(context)?)),
+            "label" | "grouplabel" => dump.labels.push((record.pk.clone(), fields(&record).with_context(context)?)),
+            "material" => dump.materials.push((record.pk.clone(), fields(&record).with_context(context)?)),
+            "materiallabel" => dump.material_labels.push(fields(&record).with_context(context)?),
+            "materialcomment" | "comment" => dump.comments.push(fields(&record).with_context(context)?),
+            _ if !dump.unknown_models.contains(&record.model) => dump.unknown_models.push(record.model.clone()),
+            _ => {}
+        }
+    }
+    Ok(dump)
+}
+
+fn fields<T: DeserializeOwned>(record: &DumpRecord) -> Result<T> {
+    Ok(serde_json::from_value(record.fields.clone())?)
+}
+
+/// Import a parsed dump. Run it in a transaction, a failure part way leaves
+/// the rest of the records unimported.
+pub async fn import_dump(conn: &mut PgConnection, dump: &DjangoDump) -> Result<ImportReport> {
+    let mut report = ImportReport::default();
+    // One hash of a secret nobody knows serves every unusable password
+    let unusable_password_hash = PasswordUtils::hash_password(&TokenUtils::generate_token())?;
+
+    let mut user_ids: HashMap<&DjangoKey, Uuid> = HashMap::new();
+    for (pk, user) in &dump.users {
+        let email = EmailUtils::normalize_email(&user.email);
+        let existing = sqlx::query_scalar!("SELECT id FROM users WHERE email = $1", email)
+            .fetch_optional(&mut *conn)
+            .await?;
+        if let Some(id) = existing {
+            user_ids.insert(pk, id);
+            report.matched_users += 1;
+            continue;
+        }
+
+        let password_hash = import_password_hash(&user.password).unwrap_or_else(|| {
+            report.unusable_passwords += 1;
+            unusable_password_hash.clone()
+        });
+        let id = pk.as_uuid().unwrap_or_else(Uuid::new_v4);
+        let username = available_name(&mut *conn, NameKind::Username, &user.username).await?;
+
+        let disabled_at = (!user.is_active).then(Utc::now);
+        sqlx::query!(
+            r#"
+            INSERT INTO users (id, email, username, password_hash, created_at, disabled_at)
+            VALUES ($1, $2, $3, $4, COALESCE($5, NOW()), $6)
+            "#,
+            id,
+            email,
+            username,
+            password_hash,
+            parse_timestamp(user.date_joined.as_deref()),
+            disabled_at
+        )
+        .execute(&mut *conn)
+        .await?;
+        user_ids.insert(pk, id);
+        report.users += 1;
+        if disabled_at.is_some() {
+            report.disabled_users += 1;
+        }
+    }
+
+    for profile in &dump.profiles {
+        let Some(&user_id) = user_ids.get(&profile.user) else {
+            report.skipped.push(format!("profile of unknown user {}", profile.user));
+            continue;
+        };
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET bio = COALESCE(bio, $2), image_url = COALESCE(image_url, $3)
+            WHERE id = $1
+            "#,
+            user_id,
+            profile.bio.as_deref().filter(|s| !s.is_empty()),
+            profile.profile_picture.as_deref().filter(|s| !s.is_empty())
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    let mut group_ids: HashMap<&DjangoKey, Uuid> = HashMap::new();
+    for (pk, group) in &dump.groups {
+        let Some(&owner_id) = user_ids.get(&group.owner) else {
+            report.skipped.push(format!("group {:?} with unknown owner {}", group.name, group.owner));
+            continue;
+        };
+
+        // Already imported by an earlier run, maybe under another name
+        let id = pk.stable_id("group");
+        let imported = sqlx::query_scalar!(r#"SELECT EXISTS(SELECT 1 FROM groups WHERE id = $1) as "exists!""#, id)
+            .fetch_one(&mut *conn)
+            .await?;
+        if imported {
+            group_ids.insert(pk, id);
+            continue;
+        }
+
+        let name = available_name(&mut *conn, NameKind::Group, &group.name).await?;
+        if name != group.name {
+            report.renamed_groups.push((group.name.clone(), name.clone()));
+        }
+        let slug = Group::available_slug(&mut *conn, &name).await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO groups (id, owner_id, name, slug, description, join_type, post_permission, edit_permissions, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, COALESCE($9, NOW()))
+            "#,
+            id,
+            owner_id,
+            name,
+            slug,
+            group.description,
+            normalize_join_type(group.join_type.as_deref()),
+            normalize_permission(group.post_permission.as_deref()),
+            normalize_permission(group.edit_permissions.as_deref()),
+            parse_timestamp(group.created_at.as_deref())
+        )
+        .execute(&mut *conn)
+        .await?;
+        // Owners are admin members too, as with groups created in the app
+        sqlx::query!(
+            r#"
+            INSERT INTO group_members (user_id, group_id, user_role, joined_at)
+            VALUES ($1, $2, 'admin', COALESCE($3, NOW()))
+            ON CONFLICT DO NOTHING
+            "#,
+            owner_id,
+            id,
+            parse_timestamp(group.created_at.as_deref())
+        )
+        .execute(&mut *conn)
+        .await?;
+        group_ids.insert(pk, id);
+        report.groups += 1;
+    }
+
+    for member in &dump.members {
+        let (Some(group_id), Some(user_id)) = (group_ids.get(&member.group), user_ids.get(&member.user)) else {
+            report.skipped.push(format!("membership of user {} in group {}", member.user, member.group));
+            continue;
+        };
+        let role = member.user_role.as_deref().unwrap_or("member").to_lowercase();
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO group_members (user_id, group_id, user_role, joined_at)
+            VALUES ($1, $2, $3, COALESCE($4, NOW()))
+            ON CONFLICT DO NOTHING
+            "#,
+            user_id,
+            group_id,
+            role,
+            parse_timestamp(member.joined_at.as_deref())
+        )
+        .execute(&mut *conn)
+        .await?;
+        report.members += inserted.rows_affected() as usize;
+    }
+
+    let mut course_ids: HashMap<&DjangoKey, Uuid> = HashMap::new();
+    for (pk, course) in &dump.courses {
+        let Some(&group_id) = group_ids.get(&course.group) else {
+            report.skipped.push(format!("course {:?} of unknown group {}", course.name, course.group));
+            continue;
+        };
+
+        // Imported before, or the group already has a course of that name
+        let id = pk.stable_id("course");
+        let existing = sqlx::query_scalar!(
+            "SELECT id FROM courses WHERE id = $1 OR (group_id = $2 AND name = $3) ORDER BY id = $1 DESC LIMIT 1",
+            id,
+            group_id,
+            course.name
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+        if let Some(id) = existing {
+            course_ids.insert(pk, id);
+            continue;
+        }
+
+        let slug = Course::available_slug(&mut *conn, group_id, &course.name).await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO courses (id, group_id, name, slug, description, created_at)
+            VALUES ($1, $2, $3, $4, $5, COALESCE($6, NOW()))
+            "#,
+            id,
+            group_id,
+            course.name,
+            slug,
+            course.description,
+            parse_timestamp(course.created_at.as_deref())
+        )
+        .execute(&mut *conn)
+        .await?;
+        course_ids.insert(pk, id);
+        report.courses += 1;
+    }
+
+    let mut label_keys: HashMap<&DjangoKey, (Uuid, String)> = HashMap::new();
+    for (pk, label) in &dump.labels {
+        let Some(&group_id) = group_ids.get(&label.group) else {
+            report.skipped.push(format!("label {:?} of unknown group {}", label.name, label.group));
+            continue;
+        };
+        let inserted = sqlx::query!(
+            "INSERT INTO group_labels (group_id, name) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+            group_id,
+            label.name
+        )
+        .execute(&mut *conn)
+        .await?;
+        label_keys.insert(pk, (group_id, label.name.clone()));
+        report.labels += inserted.rows_affected() as usize;
+    }
+
+    // Only materials inserted by this run get their labels and comments
+    let mut material_ids: HashMap<&DjangoKey, Uuid> = HashMap::new();
+    for (pk, material) in &dump.materials {
+        let (Some(course_id), Some(creator)) =
+            (course_ids.get(&material.course), user_ids.get(&material.creator))
+        else {
+            report.skipped.push(format!("material {:?} of unknown course or creator", material.title));
+            continue;
+        };
+        let id = pk.stable_id("material");
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO materials (id, course_id, title, file, url, type, creator, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, COALESCE($8, NOW()), COALESCE($9, $8, NOW()))
+            ON CONFLICT (id) DO NOTHING
+            "#,
+            id,
+            course_id,
+            material.title,
+            material.file.as_deref().filter(|s| !s.is_empty()),
+            material.url.as_deref().filter(|s| !s.is_empty()),
+            material.material_type,
+            creator,
+            parse_timestamp(material.created_at.as_deref()),
+            parse_timestamp(material.updated_at.as_deref())
+        )
+        .execute(&mut *conn)
+        .await?;
+        if inserted.rows_affected() > 0 {
+            material_ids.insert(pk, id);
+            report.materials += 1;
+        }
+    }
+
+    for material_label in &dump.material_labels {
+        let Some(material_id) = material_ids.get(&material_label.material) else {
+            continue;
+        };
+        let Some((group_id, label_name)) = label_keys.get(&material_label.label) else {
+            report.skipped.push(format!("unknown label {} on material {}", material_label.label, material_id));
+            continue;
+        };
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO material_labels (material_id, group_id, label_name, number)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT DO NOTHING
+            "#,
+            material_id,
+            group_id,
+            label_name,
+            material_label.number
+        )
+        .execute(&mut *conn)
+        .await?;
+        report.material_labels += inserted.rows_affected() as usize;
+    }
+
+    for comment in &dump.comments {
+        let Some(material_id) = material_ids.get(&comment.material) else {
+            continue;
+        };
+        // Comments outlive deleted authors here as they did in Django
+        let user_id = comment.user.as_ref().and_then(|user| user_ids.get(user)).copied();
+        sqlx::query!(
+            r#"
+            INSERT INTO comments (material_id, user_id, content, created_at)
+            VALUES ($1, $2, $3, COALESCE($4, NOW()))
+            "#,
+            material_id,
+            user_id,
+            comment.content,
+            parse_timestamp(comment.created_at.as_deref())
+        )
+        .execute(&mut *conn)
+        .await?;
+        report.comments += 1;
+    }
+
+    Ok(report)
+}
+
+/// A hash `PasswordUtils::verify_password` accepts, or `None` when the
+/// password cannot be carried over
+pub fn import_password_hash(django_hash: &str) -> Option<String> {
+    if django_hash.starts_with(DJANGO_PBKDF2_PREFIX) {
+        return Some(django_hash.to_string());
+    }
+    // Django prefixes the PHC string with its hasher name
+    if let Some(phc) = django_hash.strip_prefix("argon2")
+        && phc.starts_with("$argon2")
+    {
+        return Some(phc.to_string());
+    }
+    None
+}
+
+fn normalize_join_type(join_type: Option<&str>) -> &'static str {
+    match join_type.unwrap_or_default().to_lowercase().as_str() {
+        "open" => "OPEN",
+        "requests" | "request" => "REQUESTS",
+        _ => "CLOSED",
+    }
+}
+
+/// Django stored `admins`/`members`, here the role is singular upper case
+fn normalize_permission(permission: Option<&str>) -> String {
+    let permission = permission.unwrap_or("admin").trim().to_uppercase();
+    permission.strip_suffix('S').map(str::to_string).unwrap_or(permission)
+}
+
+/// Django writes aware timestamps in RFC 3339, or naive ones with USE_TZ off
+fn parse_timestamp(value: Option<&str>) -> Option<DateTime<Utc>> {
+    let value = value?;
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .ok()
+        .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").ok().map(|t| t.and_utc()))
+}
+
+#[derive(Debug, Clone, Copy)]
+enum NameKind {
+    Username,
+    Group,
+}
+
+/// The name itself if it is free, otherwise the first free `name-N`
+async fn available_name(conn: &mut PgConnection, kind: NameKind, name: &str) -> Result<String> {
+    let mut candidate = name.to_string();
+    for suffix in 2.. {
+        let taken = match kind {
+            NameKind::Username => sqlx::query_scalar!(
+                r#"SELECT EXISTS(SELECT 1 FROM users WHERE username = $1) as "exists!""#,
+                candidate
+            )
+            .fetch_one(&mut *conn)
+            .await?,
+            NameKind::Group => Group::name_taken(&mut *conn, &candidate, None).await?,
+        };
+        if !taken {
+            break;
+        }
+        candidate = format!("{}-{}", name, suffix);
+    }
+    Ok(candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = r#"[
+        {"model": "auth.group", "pk": 1, "fields": {"name": "staff"}},
+        {"model": "users.user", "pk": "0b7c3d0e-4b7e-4a55-9f0e-2f5c3a1d9e10", "fields": {
+            "email": "Ann@Uni.edu", "username": "ann", "password": "pbkdf2_sha256$600000$s$h",
+            "date_joined": "2024-02-01T09:30:00Z"}},
+        {"model": "groups_courses.group", "pk": "7e0d2f4c-1111-4c1e-8a57-9d6b2c3e4f50", "fields": {
+            "owner": "0b7c3d0e-4b7e-4a55-9f0e-2f5c3a1d9e10", "name": "Algebra", "description": "",
+            "join_type": "open", "post_permission": "members", "edit_permissions": "admins",
+            "created_at": "2024-02-02T10:00:00.123+01:00"}},
+        {"model": "materials.materialcomment", "pk": "c1", "fields": {
+            "material": "m1", "User": null, "Content": "Thanks!", "CreatedAt": "2024-03-01T12:00:00"}},
+        {"model": "materials.reaction", "pk": 3, "fields": {}}
+    ]"#;
+
+    #[test]
+    fn test_parse_dump_sorts_records() {
+        let dump = parse_dump(DUMP).unwrap();
+        assert_eq!(dump.users.len(), 1);
+        assert_eq!(dump.groups.len(), 1);
+        assert_eq!(dump.groups[0].1.owner, dump.users[0].0);
+        assert_eq!(dump.comments[0].content, "Thanks!");
+        assert!(dump.comments[0].user.is_none());
+        assert_eq!(dump.unknown_models, vec!["materials.reaction".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_is_active() {
+        let dump = parse_dump(r#"[
+            {"model": "users.user", "pk": 1, "fields": {
+                "email": "old@uni.edu", "username": "old", "password": "pbkdf2_sha256$1$s$h", "is_active": false}},
+            {"model": "users.user", "pk": 2, "fields": {
+                "email": "new@uni.edu", "username": "new", "password": "pbkdf2_sha256$1$s$h"}}
+        ]"#).unwrap();
+        assert!(!dump.users[0].1.is_active);
+        assert!(dump.users[1].1.is_active);
+    }
+
+    #[test]
+    fn test_stable_ids() {
+        let uuid = DjangoKey("7e0d2f4c-1111-4c1e-8a57-9d6b2c3e4f50".to_string());
+        assert_eq!(uuid.stable_id("group"), uuid.as_uuid().unwrap());
+
+        let number = DjangoKey("42".to_string());
+        assert_eq!(number.stable_id("material"), number.stable_id("material"));
+        assert_ne!(number.stable_id("material"), number.stable_id("course"));
+    }
+
+    #[test]
+    fn test_import_password_hash() {
+        let pbkdf2 = "pbkdf2_sha256$600000$salt$hash";
+        assert_eq!(import_password_hash(pbkdf2).as_deref(), Some(pbkdf2));
+        assert_eq!(
+            import_password_hash("argon2$argon2id$v=19$m=102400,t=2,p=8$c2FsdA$aGFzaA").as_deref(),
+            Some("$argon2id$v=19$m=102400,t=2,p=8$c2FsdA$aGFzaA")
+        );
+        assert_eq!(import_password_hash("!unusable"), None);
+        assert_eq!(import_password_hash("bcrypt_sha256$$2b$12$abc"), None);
+    }
+
+    #[test]
+    fn test_normalize_group_settings() {
+        assert_eq!(normalize_join_type(Some("Open")), "OPEN");
+        assert_eq!(normalize_join_type(None), "CLOSED");
+        assert_eq!(normalize_permission(Some("members")), "MEMBER");
+        assert_eq!(normalize_permission(None), "ADMIN");
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        let aware = parse_timestamp(Some("2024-02-02T10:00:00.123+01:00")).unwrap();
+        assert_eq!(aware.to_rfc3339(), "2024-02-02T09:00:00.123+00:00");
+        assert!(parse_timestamp(Some("2024-03-01T12:00:00")).is_some());
+        assert!(parse_timestamp(Some("yesterday")).is_none());
+    }
+}
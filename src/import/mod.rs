@@ -0,0 +1,6 @@
+// src/import/mod.rs
+//! Data import from the systems StudySphere replaces
+//! 
+//! The importers are run by the binaries in `src/bin`.
+
+pub mod django;
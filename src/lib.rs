@@ -8,6 +8,8 @@ pub mod auth;
 pub mod permissions;
 pub mod errors;
 pub mod utils;
+pub mod mail;
+pub mod import;
 
 use axum::{routing::get, Router};
 use sqlx::PgPool;
@@ -22,10 +24,10 @@ pub fn create_app(pool: PgPool) -> Router {
         .nest("/api", routes::join_request::join_request_routes()
             .layer(axum::middleware::from_fn_with_state(pool.clone(), middleware::auth_middleware))
         )
-        .nest("/api/groups/{group_name}/members", routes::group_member::group_member_routes()
+        .nest("/api/groups/{group}/members", routes::group_member::group_member_routes()
             .layer(axum::middleware::from_fn_with_state(pool.clone(), middleware::auth_middleware))
         )
-        .nest("/api", routes::course::course_routes(&pool)
+        .nest("/api", routes::course::course_routes()
             .layer(axum::middleware::from_fn_with_state(pool.clone(), middleware::auth_middleware))
         )
         .nest("/api", routes::material::material_routes()
@@ -34,7 +36,12 @@ pub fn create_app(pool: PgPool) -> Router {
         .nest("/api", routes::comment::comment_routes()
             .layer(axum::middleware::from_fn_with_state(pool.clone(), middleware::auth_middleware))
         )
+        .nest("/api/admin", routes::admin::admin_routes()
+            .layer(axum::middleware::from_fn_with_state(pool.clone(), middleware::site_admin_middleware))
+            .layer(axum::middleware::from_fn_with_state(pool.clone(), middleware::auth_middleware))
+        )
         
+        .route("/.well-known/jwks.json", get(handlers::well_known::jwks_handler))
         .route("/health", get(|| async { "OK" }))
         .layer(TraceLayer::new_for_http())
         .with_state(pool)
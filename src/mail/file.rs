@@ -0,0 +1,68 @@
+// src/mail/file.rs
+//! Development transports that never leave the machine
+use std::path::PathBuf;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+
+use super::{MailMessage, Mailer};
+
+/// Writes every message to `<outbox_dir>/<timestamp>-<id>.eml`
+pub struct FileMailer {
+    from: String,
+    outbox_dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(from: &str, outbox_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            from: from.to_string(),
+            outbox_dir: outbox_dir.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, message: MailMessage) -> Result<()> {
+        tokio::fs::create_dir_all(&self.outbox_dir).await?;
+
+        let path = self.outbox_dir.join(format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%dT%H%M%S"),
+            Uuid::new_v4()
+        ));
+        tokio::fs::write(&path, render(&self.from, &message)).await?;
+
+        tracing::info!(to = %message.to, path = %path.display(), "mail written to outbox");
+        Ok(())
+    }
+}
+
+/// Prints every message to the application log
+pub struct LogMailer {
+    from: String,
+}
+
+impl LogMailer {
+    pub fn new(from: &str) -> Self {
+        Self { from: from.to_string() }
+    }
+}
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, message: MailMessage) -> Result<()> {
+        tracing::info!("outgoing mail\n{}", render(&self.from, &message));
+        Ok(())
+    }
+}
+
+fn render(from: &str, message: &MailMessage) -> String {
+    format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n",
+        from, message.to, message.subject, message.body
+    )
+}
@@ -0,0 +1,53 @@
+// src/mail/mod.rs
+//! Outgoing email for StudySphere
+//! 
+//! Handlers send mail through the `Mailer` trait. The transport is chosen
+//! with `MAIL_TRANSPORT`:
+//! - `smtp`: deliver through an SMTP server (`SMTP_*` variables)
+//! - `file`: write each message as an `.eml` file to `MAIL_OUTBOX_DIR`
+//! - `log` (default): print messages to the application log
+
+pub mod smtp;
+pub mod file;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+pub use smtp::SmtpMailer;
+pub use file::{FileMailer, LogMailer};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, message: MailMessage) -> Result<()>;
+}
+
+/// Build the mailer configured by the environment
+pub fn mailer_from_env() -> Result<Box<dyn Mailer>> {
+    let from = std::env::var("MAIL_FROM")
+        .unwrap_or_else(|_| "StudySphere <no-reply@studysphere.local>".to_string());
+
+    match std::env::var("MAIL_TRANSPORT").unwrap_or_default().to_lowercase().as_str() {
+        "smtp" => Ok(Box::new(SmtpMailer::from_env(&from)?)),
+        "file" => {
+            let outbox_dir = std::env::var("MAIL_OUTBOX_DIR").unwrap_or_else(|_| "./outbox".to_string());
+            Ok(Box::new(FileMailer::new(&from, outbox_dir)))
+        }
+        "" | "log" => Ok(Box::new(LogMailer::new(&from))),
+        other => Err(anyhow::anyhow!("Unknown MAIL_TRANSPORT: {}", other)),
+    }
+}
+
+/// Public URL of the web frontend, used to build links in emails
+pub fn frontend_url() -> String {
+    std::env::var("FRONTEND_URL")
+        .unwrap_or_else(|_| "http://localhost:3000".to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
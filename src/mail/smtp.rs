@@ -0,0 +1,61 @@
+// src/mail/smtp.rs
+use anyhow::Result;
+use async_trait::async_trait;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+use super::{MailMessage, Mailer};
+
+/// Delivers mail through an SMTP server.
+///
+/// `SMTP_TLS` selects `starttls` (default), `tls` or `none`; use `none` with
+/// a local catcher such as MailHog (`SMTP_HOST=localhost SMTP_PORT=1025`).
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn from_env(from: &str) -> Result<Self> {
+        let host = std::env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".to_string());
+        let tls = std::env::var("SMTP_TLS").unwrap_or_else(|_| "starttls".to_string());
+
+        let mut builder = match tls.to_lowercase().as_str() {
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host),
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&host)?,
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)?,
+            other => return Err(anyhow::anyhow!("Unknown SMTP_TLS mode: {}", other)),
+        };
+
+        if let Ok(port) = std::env::var("SMTP_PORT") {
+            builder = builder.port(port.parse()?);
+        }
+
+        if let (Ok(username), Ok(password)) = (std::env::var("SMTP_USERNAME"), std::env::var("SMTP_PASSWORD")) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: from.parse()?,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, message: MailMessage) -> Result<()> {
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(message.to.parse()?)
+            .subject(message.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(message.body)?;
+
+        self.transport.send(email).await?;
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
+use std::net::SocketAddr;
+
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-use rusty_studyshpere::{db, create_app};
+use rusty_studyshpere::{auth, db, create_app, utils};
 
 #[tokio::main]
 async fn main() {
@@ -15,11 +17,24 @@ async fn main() {
         .await
         .expect("Failed to initialize database pool");
 
+    auth::load_revoked_access_tokens(&pool)
+        .await
+        .expect("Failed to load revoked access tokens");
+
+    // Fail fast on a bad key configuration rather than on the first login
+    utils::shared_jwt_manager().expect("Failed to load JWT signing keys");
+    utils::PasswordUtils::argon2_params().expect("Invalid Argon2 parameters");
+    auth::shared_auth_backends().expect("Failed to configure authentication backends");
+
+    auth::spawn_account_purger(pool.clone());
+
     let app = create_app(pool);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000")
         .await
         .unwrap();
     tracing::debug!("listening on http://{}", listener.local_addr().unwrap());
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .unwrap();
 }
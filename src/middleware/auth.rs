@@ -1,61 +1,210 @@
 use axum::{
-    extract::{FromRequestParts, Request},
-    http::request::Parts,
+    extract::{FromRequestParts, OriginalUri, Request},
+    http::{request::Parts, Method},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
     extract::State,
 };
 use sqlx::{Pool, Postgres};
 use uuid::Uuid;
 
 use crate::{
+    auth::{self, admin::{AdminAction, AuditTarget}, TokenScope},
     errors::AppError,
+    middleware::ClientInfo,
     models::user::User,
-    utils::JwtManager,
+    utils::shared_jwt_manager,
 };
 
-/// Authentication middleware that extracts and validates JWT tokens
+/// Authentication middleware that extracts and validates JWT access tokens
+/// and personal access tokens
 pub async fn auth_middleware(
     State(pool): State<Pool<Postgres>>,
-    mut request: Request,
+    request: Request,
     next: Next,
 ) -> Result<Response, AppError> {
 
+    let token = request_access_token(&request)?;
+
+    let user = authenticate_bearer(&pool, &token).await?;
+
+    // Audited first so refused attempts are on record too
+    let mut request = audit_impersonated_request(&pool, &user, request).await?;
+    user.authorize(&request)?;
+
+    // Add user_id to request extensions so handlers can access it
+    request.extensions_mut().insert(user);
+
+    Ok(next.run(request).await)
+}
+
+/// The access token from the `Authorization` header, or from the session
+/// cookie once the request has passed the CSRF check
+fn request_access_token(request: &Request) -> Result<String, AppError> {
     let headers = request.headers();
-    let auth_header = headers.get("authorization")
+    if let Some(token) = headers.get("authorization")
         .and_then(|v| v.to_str().ok())
         .and_then(|v| v.strip_prefix("Bearer "))
-        .ok_or(AppError::Unauthorized)?;
+    {
+        return Ok(token.to_string());
+    }
+
+    let token = auth::read_cookie(headers, auth::ACCESS_TOKEN_COOKIE).ok_or(AppError::Unauthorized)?;
+    auth::verify_csrf(request.method(), headers)?;
+    Ok(token)
+}
+
+/// Validate an access token and check it has not been revoked since issue
+async fn authenticate_bearer(pool: &Pool<Postgres>, token: &str) -> Result<AuthenticatedUser, AppError> {
+    if auth::is_personal_access_token(token) {
+        let (stored, scopes) = auth::authenticate_personal_access_token(pool, token).await?;
+        return Ok(AuthenticatedUser {
+            id: stored.user_id,
+            token_id: stored.id,
+            session_id: None,
+            scopes: Some(scopes),
+            impersonation: None,
+        });
+    }
+
+    let jwt_manager = shared_jwt_manager()
+        .map_err(|e| AppError::ValidationError(format!("JWT keys not configured: {}", e)))?;
 
-    let jwt_secret = std::env::var("JWT_SECRET")
-        .map_err(|_| AppError::ValidationError("JWT_SECRET not configured".to_string()))?;
-    
-    let jwt_manager = JwtManager::new(&jwt_secret);
-    
     // Verify the token
     let claims = jwt_manager
-        .verify_access_token(auth_header)
+        .verify_access_token(token)
         .map_err(|_| AppError::Unauthorized)?;
 
     let user_id = claims
         .user_id()
         .map_err(|_| AppError::Unauthorized)?;
+    let token_id = claims
+        .token_id()
+        .map_err(|_| AppError::Unauthorized)?;
+    let session_id = claims
+        .session_id()
+        .map_err(|_| AppError::Unauthorized)?;
 
-    // Verify user exists in database
-    let _user = User::find_by_id(&pool, user_id)
-        .await?
-        .ok_or(AppError::Unauthorized)?;
+    // Logged out tokens and sessions, looked up alongside the user
+    let (revoked, user) = tokio::try_join!(
+        auth::is_revoked(pool, token_id, session_id),
+        User::find_by_id(pool, user_id),
+    )?;
+    if revoked {
+        return Err(AppError::Unauthorized);
+    }
+    let user = user.ok_or(AppError::Unauthorized)?;
 
-    // Add user_id to request extensions so handlers can access it
-    request.extensions_mut().insert(AuthenticatedUser { id: user_id });
+    // Tokens issued before the last sign-out-everywhere or credential change,
+    // and any token of a disabled account
+    if claims.token_version != user.token_version || user.is_disabled() {
+        return Err(AppError::Unauthorized);
+    }
 
-    Ok(next.run(request).await)
+    // An impersonation token is only good while its admin still is one
+    let impersonation = match claims.impersonator_id().map_err(|_| AppError::Unauthorized)? {
+        Some(admin_id) => {
+            let admin = User::find_by_id(pool, admin_id)
+                .await?
+                .ok_or(AppError::Unauthorized)?;
+            if !admin.is_admin() || admin.is_disabled() {
+                return Err(AppError::Unauthorized);
+            }
+            let allow_writes = claims.act.as_ref().is_some_and(|act| act.allow_writes);
+            Some(Impersonation { admin_id, allow_writes })
+        }
+        None => None,
+    };
+
+    Ok(AuthenticatedUser { id: user_id, token_id, session_id, scopes: None, impersonation })
+}
+
+/// Record a request made with an impersonation token before it runs. The
+/// request is refused if it cannot be recorded.
+async fn audit_impersonated_request(
+    pool: &Pool<Postgres>,
+    user: &AuthenticatedUser,
+    request: Request,
+) -> Result<Request, AppError> {
+    let Some(impersonation) = user.impersonation else {
+        return Ok(request);
+    };
+
+    let details = serde_json::json!({
+        "method": request.method().as_str(),
+        "path": request_path(&request),
+    });
+    let (mut parts, body) = request.into_parts();
+    let client = ClientInfo::from_request_parts(&mut parts, &()).await?;
+
+    auth::admin::record_admin_action(
+        pool,
+        Some(impersonation.admin_id),
+        AdminAction::ImpersonatedRequest,
+        AuditTarget::User(user.id),
+        Some(details),
+        client.ip_address,
+    ).await?;
+
+    Ok(Request::from_parts(parts, body))
+}
+
+/// Nested routers see a stripped path, scopes are defined on the full one
+fn request_path(request: &Request) -> &str {
+    request
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.path())
+        .unwrap_or_else(|| request.uri().path())
 }
 
 /// Extractor for authenticated user ID from request
 #[derive(Debug, Clone)]
 pub struct AuthenticatedUser {
     pub id: Uuid,
+    pub token_id: Uuid,
+    /// Session the access token was issued to; `None` for personal access tokens
+    pub session_id: Option<Uuid>,
+    /// Scopes of a personal access token; `None` for a full login session
+    pub scopes: Option<Vec<TokenScope>>,
+    /// Set when a site admin is acting as this user
+    pub impersonation: Option<Impersonation>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Impersonation {
+    pub admin_id: Uuid,
+    pub allow_writes: bool,
+}
+
+impl Impersonation {
+    /// Reads, and writes when granted, but nothing on the user's account or
+    /// the admin API
+    fn permits(&self, method: &Method, path: &str) -> bool {
+        if path.starts_with("/api/auth") || path.starts_with("/api/admin") {
+            return false;
+        }
+        self.allow_writes || matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+    }
+}
+
+impl AuthenticatedUser {
+    /// Whether the credential is allowed to make this request
+    fn authorize(&self, request: &Request) -> Result<(), AppError> {
+        let path = request_path(request);
+
+        if let Some(impersonation) = &self.impersonation
+            && !impersonation.permits(request.method(), path)
+        {
+            return Err(AppError::PermissionDenied("Impersonated sessions cannot make this request".to_string()));
+        }
+        if let Some(scopes) = &self.scopes
+            && !auth::scopes_permit(scopes, request.method(), path)
+        {
+            return Err(AppError::PermissionDenied("Token scope does not allow this request".to_string()));
+        }
+        Ok(())
+    }
 }
 
 impl<S> FromRequestParts<S> for AuthenticatedUser
@@ -80,23 +229,18 @@ pub async fn optional_auth_middleware(
 ) -> Response {
     let pool = request.extensions().get::<Pool<Postgres>>().cloned();
     
-    if let Some(pool) = pool {
-        let headers = request.headers();
-        if let Some(auth_header) = headers.get("authorization")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|v| v.strip_prefix("Bearer "))
-        {
-            if let Ok(jwt_secret) = std::env::var("JWT_SECRET") {
-                let jwt_manager = JwtManager::new(&jwt_secret);
-                
-                if let Ok(claims) = jwt_manager.verify_access_token(auth_header) {
-                    if let Ok(user_id) = claims.user_id() {
-                        if let Ok(Some(_)) = User::find_by_id(&pool, user_id).await {
-                            request.extensions_mut().insert(AuthenticatedUser { id: user_id });
-                        }
-                    }
-                }
-            }
+    if let Some(pool) = pool
+        && let Ok(token) = request_access_token(&request)
+        && let Ok(user) = authenticate_bearer(&pool, &token).await
+    {
+        // Audited first, like `auth_middleware`, so refused attempts are on
+        // record too
+        request = match audit_impersonated_request(&pool, &user, request).await {
+            Ok(audited) => audited,
+            Err(e) => return e.into_response(),
+        };
+        if user.authorize(&request).is_ok() {
+            request.extensions_mut().insert(user);
         }
     }
     next.run(request).await
@@ -120,4 +264,37 @@ where
         
         Ok(OptionalAuthenticatedUser(user_id))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn impersonation(allow_writes: bool) -> Impersonation {
+        Impersonation { admin_id: Uuid::new_v4(), allow_writes }
+    }
+
+    #[test]
+    fn test_impersonation_reads_allowed() {
+        assert!(impersonation(false).permits(&Method::GET, "/api/groups/rust"));
+        assert!(impersonation(false).permits(&Method::HEAD, "/api/materials/abc"));
+    }
+
+    #[test]
+    fn test_impersonation_writes_denied_by_default() {
+        assert!(!impersonation(false).permits(&Method::POST, "/api/materials"));
+        assert!(!impersonation(false).permits(&Method::DELETE, "/api/groups/rust"));
+        assert!(impersonation(true).permits(&Method::POST, "/api/materials"));
+    }
+
+    #[test]
+    fn test_impersonation_never_reaches_account_or_admin() {
+        for allow_writes in [false, true] {
+            let impersonation = impersonation(allow_writes);
+            assert!(!impersonation.permits(&Method::GET, "/api/auth/me/export"));
+            assert!(!impersonation.permits(&Method::POST, "/api/auth/change-password"));
+            assert!(!impersonation.permits(&Method::GET, "/api/admin/users"));
+            assert!(!impersonation.permits(&Method::POST, "/api/admin/users/abc/impersonate"));
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 
 use axum::{
     extract::{Request, Path},
@@ -7,28 +8,44 @@ use axum::{
 };
 use sqlx::{Pool, Postgres};
 use crate::{
+    auth,
     errors::AppError,
     middleware::auth::AuthenticatedUser,
-    models
+    models::group::Group,
+    permissions::{GroupAction, GroupPermissions},
 };
 
-pub async fn auth_middleware(
+/// Lets through callers who may manage the `{group}` in the path, which
+/// today is only its owner
+pub async fn group_admin_middleware(
+    State(pool): State<Pool<Postgres>>,
+    permissions: GroupPermissions,
+    Path(params): Path<HashMap<String, String>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let group_ref = params.get("group").ok_or(AppError::NotFound)?;
+    let group = Group::find_by_ref(&pool, group_ref)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    permissions.require(group.id, GroupAction::ManageGroup).await?;
+    Ok(next.run(request).await)
+}
+
+/// Only site admins signed in with a session get through; personal access
+/// tokens and impersonation tokens never reach the admin API
+pub async fn site_admin_middleware(
     State(pool): State<Pool<Postgres>>,
     user: AuthenticatedUser,
-    Path(group_name): Path<String>,
     request: Request,
     next: Next,
 ) -> Result<Response, AppError> {
-    let group = models::group::Group::find_by_name(&pool, group_name).await?;
-    match group {
-        Some(g) => {
-            if g.owner_id == user.id {
-                Ok(next.run(request).await)
-            } else {
-                Err(AppError::Forbidden)
-            }
-        },
-        None => Err(AppError::NotFound),
-        
+    if user.scopes.is_some() {
+        return Err(AppError::PermissionDenied("Personal access tokens cannot use the admin API".to_string()));
+    }
+    if user.impersonation.is_some() {
+        return Err(AppError::PermissionDenied("Impersonated sessions cannot use the admin API".to_string()));
     }
-}
\ No newline at end of file
+    auth::admin::require_site_admin(&pool, user.id).await?;
+    Ok(next.run(request).await)
+}
@@ -0,0 +1,95 @@
+// src/middleware/client_info.rs
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::OnceLock,
+};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{request::Parts, HeaderMap},
+};
+
+use crate::errors::AppError;
+
+const MAX_USER_AGENT_LENGTH: usize = 512;
+
+/// Device details recorded with a new session. The user agent is
+/// client-controlled; the address is the peer's unless it is a trusted
+/// proxy, see `client_ip`.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let user_agent = parts
+            .headers
+            .get("user-agent")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.chars().take(MAX_USER_AGENT_LENGTH).collect());
+
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let ip_address = client_ip(&parts.headers, peer, trusted_proxies()).map(|ip| ip.to_string());
+
+        Ok(ClientInfo { user_agent, ip_address })
+    }
+}
+
+/// Proxies whose `X-Forwarded-For` is believed, from the comma-separated
+/// `TRUSTED_PROXIES`. None by default.
+fn trusted_proxies() -> &'static [IpAddr] {
+    static TRUSTED_PROXIES: OnceLock<Vec<IpAddr>> = OnceLock::new();
+    TRUSTED_PROXIES.get_or_init(|| {
+        std::env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .filter_map(|v| match v.parse() {
+                Ok(ip) => Some(ip),
+                Err(_) => {
+                    tracing::warn!(proxy = v, "ignoring invalid TRUSTED_PROXIES entry");
+                    None
+                }
+            })
+            .collect()
+    })
+}
+
+/// The address of the client. `X-Forwarded-For` is only read when the peer
+/// is a trusted proxy, and then from the right: the first hop that is not a
+/// trusted proxy is the client, anything left of it could be made up.
+pub fn client_ip(headers: &HeaderMap, peer: Option<IpAddr>, trusted: &[IpAddr]) -> Option<IpAddr> {
+    let peer = peer?;
+    if !trusted.contains(&peer) {
+        return Some(peer);
+    }
+
+    let mut client = peer;
+    let hops = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .collect::<Vec<_>>();
+    for hop in hops.into_iter().rev() {
+        let Ok(ip) = hop.trim().parse::<IpAddr>() else {
+            break;
+        };
+        client = ip;
+        if !trusted.contains(&ip) {
+            break;
+        }
+    }
+    Some(client)
+}
@@ -6,5 +6,7 @@
 
 pub mod auth;
 pub mod authorization;
-pub use auth::{auth_middleware, optional_auth_middleware, AuthenticatedUser, OptionalAuthenticatedUser};
-pub use authorization::auth_middleware as group_admin_middleware;
\ No newline at end of file
+pub mod client_info;
+pub use auth::{auth_middleware, optional_auth_middleware, AuthenticatedUser, Impersonation, OptionalAuthenticatedUser};
+pub use authorization::{group_admin_middleware, site_admin_middleware};
+pub use client_info::ClientInfo;
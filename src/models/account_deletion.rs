@@ -0,0 +1,91 @@
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A pending deletion of a user's account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct AccountDeletion {
+    pub user_id: Uuid,
+    pub requested_at: DateTime<Utc>,
+    pub purge_after: DateTime<Utc>,
+}
+
+impl AccountDeletion {
+    /// Schedule the deletion, or move an already scheduled one
+    pub async fn schedule(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+        purge_after: DateTime<Utc>,
+    ) -> Result<Self, sqlx::Error> {
+        let deletion = sqlx::query_as!(
+            AccountDeletion,
+            r#"
+            INSERT INTO account_deletions (user_id, purge_after)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE
+            SET requested_at = NOW(), purge_after = EXCLUDED.purge_after
+            RETURNING user_id, requested_at, purge_after
+            "#,
+            user_id,
+            purge_after
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(deletion)
+    }
+
+    pub async fn find_by_user(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let deletion = sqlx::query_as!(
+            AccountDeletion,
+            r#"
+            SELECT user_id, requested_at, purge_after
+            FROM account_deletions
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(deletion)
+    }
+
+    /// Returns `false` if no deletion was scheduled
+    pub async fn cancel(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM account_deletions WHERE user_id = $1",
+            user_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Deletions whose grace period is over
+    pub async fn list_due(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let deletions = sqlx::query_as!(
+            AccountDeletion,
+            r#"
+            SELECT user_id, requested_at, purge_after
+            FROM account_deletions
+            WHERE purge_after <= NOW()
+            ORDER BY purge_after
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(deletions)
+    }
+}
@@ -0,0 +1,79 @@
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// An action taken through the admin API
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct AdminAuditEntry {
+    pub id: Uuid,
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: String,
+    pub details: Option<serde_json::Value>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewAdminAuditEntry {
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: String,
+    pub details: Option<serde_json::Value>,
+    pub ip_address: Option<String>,
+}
+
+impl AdminAuditEntry {
+    pub async fn create(
+        executor: impl sqlx::PgExecutor<'_>,
+        new_entry: NewAdminAuditEntry,
+    ) -> Result<Self, sqlx::Error> {
+        let entry = sqlx::query_as!(
+            AdminAuditEntry,
+            r#"
+            INSERT INTO admin_audit_log (actor_id, action, target_type, target_id, details, ip_address)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, actor_id, action, target_type, target_id, details, ip_address, created_at
+            "#,
+            new_entry.actor_id,
+            new_entry.action,
+            new_entry.target_type,
+            new_entry.target_id,
+            new_entry.details,
+            new_entry.ip_address
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(entry)
+    }
+
+    /// Newest first, optionally only the actions of one admin
+    pub async fn list(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        actor_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let entries = sqlx::query_as!(
+            AdminAuditEntry,
+            r#"
+            SELECT id, actor_id, action, target_type, target_id, details, ip_address, created_at
+            FROM admin_audit_log
+            WHERE $1::UUID IS NULL OR actor_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            actor_id,
+            limit,
+            offset
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(entries)
+    }
+}
@@ -0,0 +1,74 @@
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A security relevant authentication event kept for admin review, such as
+/// an account or address being locked out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct AuthEvent {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub event_type: String,
+    pub subject: String,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewAuthEvent {
+    pub user_id: Option<Uuid>,
+    pub event_type: String,
+    pub subject: String,
+    pub ip_address: Option<String>,
+}
+
+impl AuthEvent {
+    pub async fn create(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        new_event: NewAuthEvent,
+    ) -> Result<Self, sqlx::Error> {
+        let event = sqlx::query_as!(
+            AuthEvent,
+            r#"
+            INSERT INTO auth_events (user_id, event_type, subject, ip_address)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, user_id, event_type, subject, ip_address, created_at as "created_at!"
+            "#,
+            new_event.user_id,
+            new_event.event_type,
+            new_event.subject,
+            new_event.ip_address
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(event)
+    }
+
+    /// Newest first, optionally only the events of one user
+    pub async fn list(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let events = sqlx::query_as!(
+            AuthEvent,
+            r#"
+            SELECT id, user_id, event_type, subject, ip_address, created_at as "created_at!"
+            FROM auth_events
+            WHERE $1::UUID IS NULL OR user_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            user_id,
+            limit,
+            offset
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(events)
+    }
+}
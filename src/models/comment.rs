@@ -124,4 +124,24 @@ impl Comment {
 
         Ok(())
     }
+
+    pub async fn find_by_user(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let comments = sqlx::query_as!(
+            Comment,
+            r#"
+            SELECT id, material_id, user_id, content, created_at as "created_at!"
+            FROM comments
+            WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(comments)
+    }
 }
@@ -1,19 +1,27 @@
+use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::{models::group::slug_base, utils::StringUtils};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
 pub struct Course {
-    pub group_name: String,
+    pub id: Uuid,
+    pub group_id: Uuid,
+    /// Unique within the group, free to change
     pub name: String,
+    /// Unique within the group, see `Course::find_by_ref`
+    pub slug: String,
     pub description: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct NewCourse {
-    pub group_name: String,
+    pub group_id: Uuid,
     pub name: String,
+    pub slug: String,
     pub description: Option<String>,
 }
 
@@ -25,12 +33,13 @@ impl Course {
         let course = sqlx::query_as!(
             Course,
             r#"
-            INSERT INTO courses (group_name, name, description)
-            VALUES ($1, $2, $3)
-            RETURNING group_name, name, description, created_at as "created_at!"
+            INSERT INTO courses (group_id, name, slug, description)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, group_id, name, slug, description, created_at as "created_at!"
             "#,
-            new_course.group_name,
+            new_course.group_id,
             new_course.name,
+            new_course.slug,
             new_course.description
         )
         .fetch_one(pool)
@@ -39,19 +48,19 @@ impl Course {
         Ok(course)
     }
 
-    pub async fn find_by_group_name(
+    pub async fn find_by_group(
         pool: &sqlx::Pool<sqlx::Postgres>,
-        group_name: String,
+        group_id: Uuid,
     ) -> Result<Vec<Self>, sqlx::Error> {
         let courses = sqlx::query_as!(
             Course,
             r#"
-            SELECT group_name, name, description, created_at as "created_at!"
+            SELECT id, group_id, name, slug, description, created_at as "created_at!"
             FROM courses
-            WHERE group_name = $1
+            WHERE group_id = $1
             ORDER BY created_at DESC
             "#,
-            group_name
+            group_id
         )
         .fetch_all(pool)
         .await?;
@@ -61,17 +70,17 @@ impl Course {
 
     pub async fn find_by_group_and_name(
         pool: &sqlx::Pool<sqlx::Postgres>,
-        group_name: String,
+        group_id: Uuid,
         name: String,
     ) -> Result<Option<Self>, sqlx::Error> {
         let course = sqlx::query_as!(
             Course,
             r#"
-            SELECT group_name, name, description, created_at as "created_at!"
+            SELECT id, group_id, name, slug, description, created_at as "created_at!"
             FROM courses
-            WHERE group_name = $1 AND name = $2
+            WHERE group_id = $1 AND name = $2
             "#,
-            group_name,
+            group_id,
             name
         )
         .fetch_optional(pool)
@@ -80,43 +89,169 @@ impl Course {
         Ok(course)
     }
 
+    /// Find a course of the group by id, slug, name or former slug, the same
+    /// way `Group::find_by_ref` finds groups
+    pub async fn find_by_ref(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        group_id: Uuid,
+        course_ref: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let id = Uuid::parse_str(course_ref).ok();
+        let course = sqlx::query_as!(
+            Course,
+            r#"
+            SELECT id, group_id, name, slug, description, created_at as "created_at!"
+            FROM courses
+            WHERE group_id = $1 AND (id = $2 OR slug = $3 OR name = $3 OR slug = $4)
+            ORDER BY id = $2 DESC, slug = $3 DESC, name = $3 DESC
+            LIMIT 1
+            "#,
+            group_id,
+            id,
+            course_ref,
+            StringUtils::slugify(course_ref)
+        )
+        .fetch_optional(pool)
+        .await?;
+        if course.is_some() {
+            return Ok(course);
+        }
+
+        let course = sqlx::query_as!(
+            Course,
+            r#"
+            SELECT c.id, c.group_id, c.name, c.slug, c.description, c.created_at as "created_at!"
+            FROM course_slug_aliases a
+            JOIN courses c ON c.id = a.course_id
+            WHERE a.group_id = $1 AND (a.slug = $2 OR a.slug = $3)
+            ORDER BY a.slug = $2 DESC
+            LIMIT 1
+            "#,
+            group_id,
+            course_ref,
+            StringUtils::slugify(course_ref)
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(course)
+    }
+
+    /// Whether a course of the group other than `except_id` has the slug,
+    /// now or as an alias of an earlier one
+    pub async fn slug_taken(
+        executor: impl sqlx::PgExecutor<'_>,
+        group_id: Uuid,
+        slug: &str,
+        except_id: Option<Uuid>,
+    ) -> Result<bool, sqlx::Error> {
+        let taken = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(SELECT 1 FROM courses WHERE group_id = $1 AND slug = $2 AND id IS DISTINCT FROM $3)
+                OR EXISTS(
+                    SELECT 1 FROM course_slug_aliases
+                    WHERE group_id = $1 AND slug = $2 AND course_id IS DISTINCT FROM $3
+                )
+                as "exists!"
+            "#,
+            group_id,
+            slug,
+            except_id
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(taken)
+    }
+
+    /// Slug for a new course called `name`, numbered if the group already
+    /// has a course with it
+    pub async fn available_slug<'c>(
+        conn: impl sqlx::Acquire<'c, Database = sqlx::Postgres>,
+        group_id: Uuid,
+        name: &str,
+    ) -> Result<String, sqlx::Error> {
+        let mut conn = conn.acquire().await?;
+        let base = slug_base(name, "course");
+        let mut slug = base.clone();
+        for suffix in 2.. {
+            if !Course::slug_taken(&mut *conn, group_id, &slug, None).await? {
+                break;
+            }
+            slug = format!("{}-{}", base, suffix);
+        }
+        Ok(slug)
+    }
+
+    /// Save the course, keeping a slug it gives up as an alias like
+    /// `Group::update` does
     pub async fn update(
         pool: &sqlx::Pool<sqlx::Postgres>,
-        group_name: String,
-        course_name: String,
         course: Course,
     ) -> Result<Self, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        let previous_slug = sqlx::query_scalar!(
+            "SELECT slug FROM courses WHERE id = $1 FOR UPDATE",
+            course.id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
         let updated_course = sqlx::query_as!(
             Course,
             r#"
             UPDATE courses
-            SET name = $3, description = $4
-            WHERE group_name = $1 AND name = $2
-            RETURNING group_name, name, description, created_at as "created_at!"
+            SET name = $2, slug = $3, description = $4
+            WHERE id = $1
+            RETURNING id, group_id, name, slug, description, created_at as "created_at!"
             "#,
-            group_name,
-            course_name,
+            course.id,
             course.name,
+            course.slug,
             course.description
         )
-        .fetch_one(pool)
+        .fetch_one(&mut *tx)
         .await?;
 
+        if previous_slug != updated_course.slug {
+            sqlx::query!(
+                r#"
+                INSERT INTO course_slug_aliases (group_id, slug, course_id)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (group_id, slug) DO UPDATE SET course_id = EXCLUDED.course_id
+                "#,
+                updated_course.group_id,
+                previous_slug,
+                course.id
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query!(
+                "DELETE FROM course_slug_aliases WHERE group_id = $1 AND slug = $2",
+                updated_course.group_id,
+                updated_course.slug
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
         Ok(updated_course)
     }
 
+    /// Delete a course along with its materials
     pub async fn delete(
         pool: &sqlx::Pool<sqlx::Postgres>,
-        group_name: String,
-        name: String,
+        id: Uuid,
     ) -> Result<(), sqlx::Error> {
         sqlx::query!(
             r#"
             DELETE FROM courses
-            WHERE group_name = $1 AND name = $2
+            WHERE id = $1
             "#,
-            group_name,
-            name
+            id
         )
         .execute(pool)
         .await?;
@@ -3,10 +3,19 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::{
+    permissions::{GroupSettings, PermissionLevel},
+    utils::StringUtils,
+};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
 pub struct Group {
+    pub id: Uuid,
     pub owner_id: Uuid,
+    /// Unique display name, free to change
     pub name: String,
+    /// Unique URL name, see `Group::find_by_ref`
+    pub slug: String,
     pub description: String,
     pub join_type: String,
     pub post_permission: Option<String>,
@@ -14,36 +23,162 @@ pub struct Group {
     pub created_at: DateTime<Utc>,
 }
 
+/// How users get into a group, stored on `groups.join_type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinType {
+    /// Anyone can join right away
+    Open,
+    /// Joining needs the owner to accept a join request
+    Requests,
+    /// Members join only by accepting the owner's invitation
+    Closed,
+}
+
+impl JoinType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JoinType::Open => "OPEN",
+            JoinType::Requests => "REQUESTS",
+            JoinType::Closed => "CLOSED",
+        }
+    }
+
+    pub fn parse(join_type: &str) -> Option<Self> {
+        match join_type {
+            "OPEN" => Some(JoinType::Open),
+            "REQUESTS" => Some(JoinType::Requests),
+            "CLOSED" => Some(JoinType::Closed),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct NewGroup {
     pub name: String,
+    pub slug: String,
     pub description: String,
     pub owner_id: Uuid,
     pub join_type: String,
+    pub post_permission: String,
+    pub edit_permissions: String,
+}
+
+/// The slugified `name`, or `fallback` when that leaves nothing usable. A
+/// slug that reads as a UUID would be taken for an id, so those fall back
+/// too.
+pub(crate) fn slug_base(name: &str, fallback: &str) -> String {
+    let slug = StringUtils::slugify(name);
+    if slug.is_empty() || Uuid::parse_str(&slug).is_ok() {
+        return fallback.to_string();
+    }
+    slug
+}
+
+/// How a URL reference matched a group, best first. Current slugs and
+/// names never collide across groups (see `name_taken` and `slug_taken`),
+/// so the first two cannot point at different groups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum RefMatch {
+    Slug,
+    Name,
+    NameAlias,
+    SlugAlias,
+    SlugifiedSlug,
+    SlugifiedSlugAlias,
+}
+
+#[derive(Debug, Clone)]
+struct RefCandidate {
+    id: Uuid,
+    name: String,
+    slug: String,
+    name_aliases: Vec<String>,
+    slug_aliases: Vec<String>,
 }
 
+impl RefCandidate {
+    fn matches(&self, group_ref: &str, slugified: &str) -> Option<RefMatch> {
+        let has = |aliases: &[String], value: &str| aliases.iter().any(|alias| alias == value);
+        if self.slug == group_ref {
+            Some(RefMatch::Slug)
+        } else if self.name == group_ref {
+            Some(RefMatch::Name)
+        } else if has(&self.name_aliases, group_ref) {
+            Some(RefMatch::NameAlias)
+        } else if has(&self.slug_aliases, group_ref) {
+            Some(RefMatch::SlugAlias)
+        } else if self.slug == slugified {
+            Some(RefMatch::SlugifiedSlug)
+        } else if has(&self.slug_aliases, slugified) {
+            Some(RefMatch::SlugifiedSlugAlias)
+        } else {
+            None
+        }
+    }
+}
 
+/// The candidate the reference matches best
+fn best_ref_match(group_ref: &str, slugified: &str, candidates: &[RefCandidate]) -> Option<Uuid> {
+    candidates
+        .iter()
+        .filter_map(|candidate| candidate.matches(group_ref, slugified).map(|m| (m, candidate.id)))
+        .min_by_key(|(m, _)| *m)
+        .map(|(_, id)| id)
+}
 
 impl Group {
+    /// Groups with a join type this version does not know are treated as
+    /// closed
+    pub fn join_type(&self) -> JoinType {
+        JoinType::parse(&self.join_type).unwrap_or(JoinType::Closed)
+    }
+
+    pub fn settings(&self) -> GroupSettings {
+        let level = |value: &Option<String>| {
+            value.as_deref().and_then(PermissionLevel::parse).unwrap_or_default()
+        };
+        GroupSettings {
+            post: level(&self.post_permission),
+            edit: level(&self.edit_permissions),
+        }
+    }
+
+    /// Create a group with its owner as an admin member, the way ownership
+    /// transfers leave it
     pub async fn create(
         pool: &sqlx::Pool<sqlx::Postgres>,
         new_group: NewGroup,
     ) -> Result<Self, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
         let group = sqlx::query_as!(
             Group,
             r#"
-            INSERT INTO groups (owner_id, name, description, join_type)
-            VALUES ($1, $2, $3, $4)
-            RETURNING owner_id, name, description, join_type, post_permission, edit_permissions, created_at as "created_at!"
+            INSERT INTO groups (owner_id, name, slug, description, join_type, post_permission, edit_permissions)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, owner_id, name, slug, description, join_type, post_permission, edit_permissions, created_at as "created_at!"
             "#,
             new_group.owner_id,
             new_group.name,
+            new_group.slug,
             new_group.description,
-            new_group.join_type
+            new_group.join_type,
+            new_group.post_permission,
+            new_group.edit_permissions
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "INSERT INTO group_members (user_id, group_id, user_role) VALUES ($1, $2, 'admin')",
+            group.owner_id,
+            group.id
         )
-        .fetch_one(pool)
+        .execute(&mut *tx)
         .await?;
 
+        tx.commit().await?;
         Ok(group)
     }
 
@@ -51,7 +186,7 @@ impl Group {
         let groups = sqlx::query_as!(
             Group,
             r#"
-            SELECT owner_id, name, description, join_type, post_permission, edit_permissions, created_at as "created_at!"
+            SELECT id, owner_id, name, slug, description, join_type, post_permission, edit_permissions, created_at as "created_at!"
             FROM groups
             ORDER BY created_at DESC
             "#
@@ -62,6 +197,25 @@ impl Group {
         Ok(groups)
     }
 
+    pub async fn find_by_id(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let group = sqlx::query_as!(
+            Group,
+            r#"
+            SELECT id, owner_id, name, slug, description, join_type, post_permission, edit_permissions, created_at as "created_at!"
+            FROM groups
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(group)
+    }
+
     pub async fn find_by_name(
         pool: &sqlx::Pool<sqlx::Postgres>,
         name: String,
@@ -69,7 +223,7 @@ impl Group {
         let group = sqlx::query_as!(
             Group,
             r#"
-            SELECT owner_id, name, description, join_type, post_permission, edit_permissions, created_at as "created_at!"
+            SELECT id, owner_id, name, slug, description, join_type, post_permission, edit_permissions, created_at as "created_at!"
             FROM groups
             WHERE name = $1
             "#,
@@ -80,4 +234,372 @@ impl Group {
 
         Ok(group)
     }
+
+    /// Find the group a URL refers to: by id, slug or name, then by a name or
+    /// slug the group had before, then by the reference slugified. See
+    /// [`RefMatch`] for the order.
+    pub async fn find_by_ref(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        group_ref: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        if let Ok(id) = Uuid::parse_str(group_ref)
+            && let Some(group) = Group::find_by_id(pool, id).await?
+        {
+            return Ok(Some(group));
+        }
+
+        let slugified = StringUtils::slugify(group_ref);
+        let candidates = sqlx::query_as!(
+            RefCandidate,
+            r#"
+            SELECT g.id, g.name, g.slug,
+                   ARRAY(SELECT n.name FROM group_name_aliases n WHERE n.group_id = g.id) as "name_aliases!",
+                   ARRAY(SELECT s.slug FROM group_slug_aliases s WHERE s.group_id = g.id) as "slug_aliases!"
+            FROM groups g
+            WHERE g.slug IN ($1, $2) OR g.name = $1
+               OR g.id IN (SELECT group_id FROM group_name_aliases WHERE name = $1)
+               OR g.id IN (SELECT group_id FROM group_slug_aliases WHERE slug IN ($1, $2))
+            "#,
+            group_ref,
+            slugified
+        )
+        .fetch_all(pool)
+        .await?;
+
+        match best_ref_match(group_ref, &slugified, &candidates) {
+            Some(id) => Group::find_by_id(pool, id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Whether `name` is taken by a group other than `except_id`: as its
+    /// name, a name it had before, or its slug, which would shadow the name
+    /// in URLs
+    pub async fn name_taken(
+        executor: impl sqlx::PgExecutor<'_>,
+        name: &str,
+        except_id: Option<Uuid>,
+    ) -> Result<bool, sqlx::Error> {
+        let taken = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(SELECT 1 FROM groups WHERE (name = $1 OR slug = $1) AND id IS DISTINCT FROM $2)
+                OR EXISTS(SELECT 1 FROM group_name_aliases WHERE name = $1 AND group_id IS DISTINCT FROM $2)
+                as "exists!"
+            "#,
+            name,
+            except_id
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(taken)
+    }
+
+    /// Whether a group other than `except_id` has the slug, now or as an
+    /// alias of an earlier one, or is named like it
+    pub async fn slug_taken(
+        executor: impl sqlx::PgExecutor<'_>,
+        slug: &str,
+        except_id: Option<Uuid>,
+    ) -> Result<bool, sqlx::Error> {
+        let taken = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(SELECT 1 FROM groups WHERE (slug = $1 OR name = $1) AND id IS DISTINCT FROM $2)
+                OR EXISTS(SELECT 1 FROM group_slug_aliases WHERE slug = $1 AND group_id IS DISTINCT FROM $2)
+                OR EXISTS(SELECT 1 FROM group_name_aliases WHERE name = $1 AND group_id IS DISTINCT FROM $2)
+                as "exists!"
+            "#,
+            slug,
+            except_id
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(taken)
+    }
+
+    /// Slug for a new group called `name`: the slugified name, numbered if
+    /// another group already has it
+    pub async fn available_slug<'c>(
+        conn: impl sqlx::Acquire<'c, Database = sqlx::Postgres>,
+        name: &str,
+    ) -> Result<String, sqlx::Error> {
+        let mut conn = conn.acquire().await?;
+        let base = slug_base(name, "group");
+        let mut slug = base.clone();
+        for suffix in 2.. {
+            if !Group::slug_taken(&mut *conn, &slug, None).await? {
+                break;
+            }
+            slug = format!("{}-{}", base, suffix);
+        }
+        Ok(slug)
+    }
+
+    pub async fn find_by_owner(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        owner_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let groups = sqlx::query_as!(
+            Group,
+            r#"
+            SELECT id, owner_id, name, slug, description, join_type, post_permission, edit_permissions, created_at as "created_at!"
+            FROM groups
+            WHERE owner_id = $1
+            ORDER BY created_at
+            "#,
+            owner_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(groups)
+    }
+
+    /// Make `new_owner_id` the owner in one transaction. Both the new and
+    /// the previous owner are kept as admin members, and any pending
+    /// ownership offer is dropped.
+    pub async fn transfer_ownership<'c>(
+        conn: impl sqlx::Acquire<'c, Database = sqlx::Postgres>,
+        id: Uuid,
+        new_owner_id: Uuid,
+    ) -> Result<Self, sqlx::Error> {
+        let mut tx = conn.begin().await?;
+        let group = Group::set_owner(&mut tx, id, new_owner_id).await?;
+        tx.commit().await?;
+        Ok(group)
+    }
+
+    /// Hand the group to `to_user_id` if the pending offer still names them,
+    /// taking the offer in the same transaction. Returns `None` if it was
+    /// withdrawn or went to someone else in the meantime.
+    pub async fn accept_ownership_transfer(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+        to_user_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        // Lock the group before the offer, in the same order as `set_owner`
+        sqlx::query!("SELECT id FROM groups WHERE id = $1 FOR UPDATE", id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        let offer = sqlx::query_scalar!(
+            r#"
+            DELETE FROM group_ownership_transfers
+            WHERE group_id = $1 AND to_user_id = $2
+            RETURNING group_id
+            "#,
+            id,
+            to_user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if offer.is_none() {
+            return Ok(None);
+        }
+
+        let group = Group::set_owner(&mut tx, id, to_user_id).await?;
+        tx.commit().await?;
+        Ok(Some(group))
+    }
+
+    async fn set_owner(
+        conn: &mut sqlx::PgConnection,
+        id: Uuid,
+        new_owner_id: Uuid,
+    ) -> Result<Self, sqlx::Error> {
+        let previous_owner_id = sqlx::query_scalar!(
+            "SELECT owner_id FROM groups WHERE id = $1 FOR UPDATE",
+            id
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+
+        let group = sqlx::query_as!(
+            Group,
+            r#"
+            UPDATE groups
+            SET owner_id = $2
+            WHERE id = $1
+            RETURNING id, owner_id, name, slug, description, join_type, post_permission, edit_permissions, created_at as "created_at!"
+            "#,
+            id,
+            new_owner_id
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO group_members (user_id, group_id, user_role)
+            SELECT DISTINCT user_id, $1::uuid, 'admin' FROM UNNEST($2::uuid[]) AS user_id
+            ON CONFLICT (user_id, group_id) DO UPDATE SET user_role = 'admin'
+            "#,
+            id,
+            &[previous_owner_id, new_owner_id][..]
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query!("DELETE FROM group_ownership_transfers WHERE group_id = $1", id)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(group)
+    }
+
+    /// Save the group. A slug or name it gives up is kept as an alias so
+    /// links made with it still find the group.
+    pub async fn update(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        group: Group,
+    ) -> Result<Self, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        let previous = sqlx::query!(
+            "SELECT name, slug FROM groups WHERE id = $1 FOR UPDATE",
+            group.id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let previous_slug = previous.slug;
+
+        let updated_group = sqlx::query_as!(
+            Group,
+            r#"
+            UPDATE groups
+            SET name = $2, slug = $3, description = $4, join_type = $5, post_permission = $6, edit_permissions = $7
+            WHERE id = $1
+            RETURNING id, owner_id, name, slug, description, join_type, post_permission, edit_permissions, created_at as "created_at!"
+            "#,
+            group.id,
+            group.name,
+            group.slug,
+            group.description,
+            group.join_type,
+            group.post_permission,
+            group.edit_permissions
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if previous_slug != updated_group.slug {
+            sqlx::query!(
+                r#"
+                INSERT INTO group_slug_aliases (slug, group_id)
+                VALUES ($1, $2)
+                ON CONFLICT (slug) DO UPDATE SET group_id = EXCLUDED.group_id
+                "#,
+                previous_slug,
+                group.id
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            // Taking back an old slug makes it the real one again
+            sqlx::query!("DELETE FROM group_slug_aliases WHERE slug = $1", updated_group.slug)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        if previous.name != updated_group.name {
+            sqlx::query!(
+                r#"
+                INSERT INTO group_name_aliases (name, group_id)
+                VALUES ($1, $2)
+                ON CONFLICT (name) DO NOTHING
+                "#,
+                previous.name,
+                group.id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(updated_group)
+    }
+
+    /// Delete a group; its courses, materials, labels and memberships go
+    /// with it
+    pub async fn delete(
+        executor: impl sqlx::PgExecutor<'_>,
+        id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!("DELETE FROM groups WHERE id = $1", id)
+            .execute(executor)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(name: &str, slug: &str, name_aliases: &[&str], slug_aliases: &[&str]) -> RefCandidate {
+        RefCandidate {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            slug: slug.to_string(),
+            name_aliases: name_aliases.iter().map(|s| s.to_string()).collect(),
+            slug_aliases: slug_aliases.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    fn resolve(group_ref: &str, candidates: &[RefCandidate]) -> Option<Uuid> {
+        best_ref_match(group_ref, &StringUtils::slugify(group_ref), candidates)
+    }
+
+    #[test]
+    fn test_slug_base() {
+        assert_eq!(slug_base("Linear Algebra II", "group"), "linear-algebra-ii");
+        assert_eq!(slug_base("Café Français", "group"), "café-français");
+        assert_eq!(slug_base("  --!!  ", "group"), "group");
+        assert_eq!(slug_base("0b7c3d0e-4b7e-4a55-9f0e-2f5c3a1d9e10", "group"), "group");
+    }
+
+    #[test]
+    fn test_exact_name_beats_slugified_match() {
+        // "Math" was given `math-<id8>` so the old `/groups/math` link of
+        // the group named "math" keeps working
+        let upper = candidate("Math", "math-0b7c3d0e", &["Math"], &[]);
+        let lower = candidate("math", "math-2", &["math"], &[]);
+        let candidates = [upper.clone(), lower.clone()];
+
+        assert_eq!(resolve("math", &candidates), Some(lower.id));
+        assert_eq!(resolve("Math", &candidates), Some(upper.id));
+        assert_eq!(resolve("math-0b7c3d0e", &candidates), Some(upper.id));
+    }
+
+    #[test]
+    fn test_name_alias_beats_slugified_match() {
+        // Slugged as `caf` by the migration, then renamed
+        let renamed = candidate("Coffee", "caf", &["Café"], &[]);
+        let other = candidate("Café Club", "café", &[], &[]);
+        let candidates = [other.clone(), renamed.clone()];
+
+        assert_eq!(resolve("Café", &candidates), Some(renamed.id));
+        assert_eq!(resolve("café", &candidates), Some(other.id));
+    }
+
+    #[test]
+    fn test_current_slug_beats_aliases() {
+        let old = candidate("Old", "old", &[], &["stats"]);
+        let new = candidate("Statistics", "stats", &[], &[]);
+        let candidates = [old.clone(), new.clone()];
+
+        assert_eq!(resolve("stats", &candidates), Some(new.id));
+        assert_eq!(resolve("Stats", &candidates), Some(new.id));
+        assert_eq!(resolve("nothing", &candidates), None);
+    }
+
+    #[test]
+    fn test_slug_alias_matches_slugified_reference() {
+        let group = candidate("Statistics", "statistics", &[], &["stats-101"]);
+        assert_eq!(resolve("Stats 101", std::slice::from_ref(&group)), Some(group.id));
+    }
 }
@@ -0,0 +1,81 @@
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A group has invited a user, who joins by accepting
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct GroupInvitation {
+    pub group_id: Uuid,
+    pub user_id: Uuid,
+    pub invited_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl GroupInvitation {
+    /// Invite `user_id`, returning `None` if they are invited already
+    pub async fn create(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        group_id: Uuid,
+        user_id: Uuid,
+        invited_by: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let invitation = sqlx::query_as!(
+            GroupInvitation,
+            r#"
+            INSERT INTO group_invitations (group_id, user_id, invited_by)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (group_id, user_id) DO NOTHING
+            RETURNING group_id, user_id, invited_by, created_at
+            "#,
+            group_id,
+            user_id,
+            invited_by
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(invitation)
+    }
+
+    pub async fn find_by_group_and_user(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        group_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let invitation = sqlx::query_as!(
+            GroupInvitation,
+            r#"
+            SELECT group_id, user_id, invited_by, created_at
+            FROM group_invitations
+            WHERE group_id = $1 AND user_id = $2
+            "#,
+            group_id,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(invitation)
+    }
+
+    /// Remove the invitation, returning whether there was one
+    pub async fn delete(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        group_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM group_invitations
+            WHERE group_id = $1 AND user_id = $2
+            "#,
+            group_id,
+            user_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -6,7 +6,7 @@ use sqlx::FromRow;
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
 pub struct GroupMember {
     pub user_id: Uuid,
-    pub group_name: String,
+    pub group_id: Uuid,
     pub user_role: Option<String>,
     pub joined_at: Option<DateTime<Utc>>,
 }
@@ -14,7 +14,7 @@ pub struct GroupMember {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
 pub struct GroupMemberWithUser {
     pub user_id: Uuid,
-    pub group_name: String,
+    pub group_id: Uuid,
     pub user_role: Option<String>,
     pub joined_at: Option<DateTime<Utc>>,
     pub user_email: String,
@@ -25,17 +25,17 @@ impl GroupMember {
     pub async fn create(
         pool: &sqlx::Pool<sqlx::Postgres>,
         user_id: Uuid,
-        group_name: String,
+        group_id: Uuid,
     ) -> Result<Self, sqlx::Error> {
         let group_member = sqlx::query_as!(
             GroupMember,
             r#"
-            INSERT INTO group_members (user_id, group_name)
+            INSERT INTO group_members (user_id, group_id)
             VALUES ($1, $2)
-            RETURNING user_id, group_name, user_role, joined_at as "joined_at!"
+            RETURNING user_id, group_id, user_role, joined_at as "joined_at!"
             "#,
             user_id,
-            group_name
+            group_id
         )
         .fetch_one(pool)
         .await?;
@@ -43,21 +43,21 @@ impl GroupMember {
         Ok(group_member)
     }
 
-    pub async fn find_by_group_name(
+    pub async fn find_by_group(
         pool: &sqlx::Pool<sqlx::Postgres>,
-        group_name: String,
+        group_id: Uuid,
     ) -> Result<Vec<GroupMemberWithUser>, sqlx::Error> {
         let members = sqlx::query_as!(
             GroupMemberWithUser,
             r#"
-            SELECT gm.user_id, gm.group_name, gm.user_role, gm.joined_at,
+            SELECT gm.user_id, gm.group_id, gm.user_role, gm.joined_at,
                    u.email as user_email, u.username as user_name
             FROM group_members gm 
             INNER JOIN users u ON gm.user_id = u.id
-            WHERE gm.group_name = $1
+            WHERE gm.group_id = $1
             ORDER BY gm.joined_at DESC
             "#,
-            group_name
+            group_id
         )
         .fetch_all(pool)
         .await?;
@@ -65,20 +65,43 @@ impl GroupMember {
         Ok(members)
     }
 
+    pub async fn find_with_user(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        group_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<GroupMemberWithUser>, sqlx::Error> {
+        let member = sqlx::query_as!(
+            GroupMemberWithUser,
+            r#"
+            SELECT gm.user_id, gm.group_id, gm.user_role, gm.joined_at,
+                   u.email as user_email, u.username as user_name
+            FROM group_members gm
+            INNER JOIN users u ON gm.user_id = u.id
+            WHERE gm.group_id = $1 AND gm.user_id = $2
+            "#,
+            group_id,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(member)
+    }
+
     pub async fn find_by_user_and_group(
         pool: &sqlx::Pool<sqlx::Postgres>,
         user_id: Uuid,
-        group_name: String,
+        group_id: Uuid,
     ) -> Result<Option<Self>, sqlx::Error> {
         let member = sqlx::query_as!(
             GroupMember,
             r#"
-            SELECT user_id, group_name, user_role, joined_at as "joined_at!"
+            SELECT user_id, group_id, user_role, joined_at as "joined_at!"
             FROM group_members
-            WHERE user_id = $1 AND group_name = $2
+            WHERE user_id = $1 AND group_id = $2
             "#,
             user_id,
-            group_name
+            group_id
         )
         .fetch_optional(pool)
         .await?;
@@ -89,7 +112,7 @@ impl GroupMember {
     pub async fn update(
         pool: &sqlx::Pool<sqlx::Postgres>,
         user_id: Uuid,
-        group_name: String,
+        group_id: Uuid,
         user_role: String,
     ) -> Result<Self, sqlx::Error> {
         let updated_group_member = sqlx::query_as!(
@@ -97,11 +120,11 @@ impl GroupMember {
             r#"
             UPDATE group_members
             SET user_role = $3
-            WHERE user_id = $1 AND group_name = $2
-            RETURNING user_id, group_name, user_role, joined_at as "joined_at!"
+            WHERE user_id = $1 AND group_id = $2
+            RETURNING user_id, group_id, user_role, joined_at as "joined_at!"
             "#,
             user_id,
-            group_name,
+            group_id,
             user_role
         )
         .fetch_one(pool)
@@ -113,19 +136,63 @@ impl GroupMember {
     pub async fn delete(
         pool: &sqlx::Pool<sqlx::Postgres>,
         user_id: Uuid,
-        group_name: String,
+        group_id: Uuid,
     ) -> Result<(), sqlx::Error> {
         sqlx::query!(
             r#"
             DELETE FROM group_members
-            WHERE user_id = $1 AND group_name = $2
+            WHERE user_id = $1 AND group_id = $2
             "#,
             user_id,
-            group_name
+            group_id
         )
         .execute(pool)
         .await?;
 
         Ok(())
     }
+
+    pub async fn find_by_user(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let memberships = sqlx::query_as!(
+            GroupMember,
+            r#"
+            SELECT user_id, group_id, user_role, joined_at as "joined_at!"
+            FROM group_members
+            WHERE user_id = $1
+            ORDER BY joined_at
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(memberships)
+    }
+
+    /// The member to hand a group to when its owner leaves: admins first,
+    /// then whoever joined earliest
+    pub async fn find_successor(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        group_id: Uuid,
+        leaving_user_id: Uuid,
+    ) -> Result<Option<Uuid>, sqlx::Error> {
+        let successor = sqlx::query_scalar!(
+            r#"
+            SELECT user_id
+            FROM group_members
+            WHERE group_id = $1 AND user_id <> $2
+            ORDER BY user_role = 'admin' DESC, joined_at ASC
+            LIMIT 1
+            "#,
+            group_id,
+            leaving_user_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(successor)
+    }
 }
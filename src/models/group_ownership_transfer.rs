@@ -0,0 +1,114 @@
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// The owner of a group has offered it to another member
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct GroupOwnershipTransfer {
+    pub group_id: Uuid,
+    pub from_user_id: Uuid,
+    pub to_user_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+impl GroupOwnershipTransfer {
+    /// Only the owner who made the offer and the nominee can see it
+    pub fn is_visible_to(&self, user_id: Uuid) -> bool {
+        self.from_user_id == user_id || self.to_user_id == user_id
+    }
+
+    /// Only the nominee can take the group
+    pub fn can_be_accepted_by(&self, user_id: Uuid) -> bool {
+        self.to_user_id == user_id
+    }
+
+    /// Offer the group to `to_user_id`, replacing any earlier offer
+    pub async fn create(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        group_id: Uuid,
+        from_user_id: Uuid,
+        to_user_id: Uuid,
+    ) -> Result<Self, sqlx::Error> {
+        let transfer = sqlx::query_as!(
+            GroupOwnershipTransfer,
+            r#"
+            INSERT INTO group_ownership_transfers (group_id, from_user_id, to_user_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (group_id) DO UPDATE
+            SET from_user_id = EXCLUDED.from_user_id, to_user_id = EXCLUDED.to_user_id, created_at = NOW()
+            RETURNING group_id, from_user_id, to_user_id, created_at
+            "#,
+            group_id,
+            from_user_id,
+            to_user_id
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(transfer)
+    }
+
+    pub async fn find_by_group(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        group_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let transfer = sqlx::query_as!(
+            GroupOwnershipTransfer,
+            r#"
+            SELECT group_id, from_user_id, to_user_id, created_at
+            FROM group_ownership_transfers
+            WHERE group_id = $1
+            "#,
+            group_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(transfer)
+    }
+
+    pub async fn delete(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        group_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "DELETE FROM group_ownership_transfers WHERE group_id = $1",
+            group_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer() -> GroupOwnershipTransfer {
+        GroupOwnershipTransfer {
+            group_id: Uuid::new_v4(),
+            from_user_id: Uuid::new_v4(),
+            to_user_id: Uuid::new_v4(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_only_owner_and_nominee_see_the_offer() {
+        let transfer = transfer();
+        assert!(transfer.is_visible_to(transfer.from_user_id));
+        assert!(transfer.is_visible_to(transfer.to_user_id));
+        assert!(!transfer.is_visible_to(Uuid::new_v4()));
+    }
+
+    #[test]
+    fn test_only_nominee_can_accept() {
+        let transfer = transfer();
+        assert!(transfer.can_be_accepted_by(transfer.to_user_id));
+        assert!(!transfer.can_be_accepted_by(transfer.from_user_id));
+        assert!(!transfer.can_be_accepted_by(Uuid::new_v4()));
+    }
+}
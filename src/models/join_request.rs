@@ -5,14 +5,14 @@ use sqlx::FromRow;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
 pub struct JoinRequest {
-    pub group_name: String,
+    pub group_id: Uuid,
     pub user_id: Uuid,
     pub created_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
 pub struct JoinRequestWithUser {
-    pub group_name: String,
+    pub group_id: Uuid,
     pub created_at: Option<DateTime<Utc>>,
     pub user_id: Uuid,
     pub user_email: String,
@@ -20,20 +20,20 @@ pub struct JoinRequestWithUser {
 }
 
 impl JoinRequest {
-    pub async fn find_by_group_name(
+    pub async fn find_by_group(
         pool: &sqlx::Pool<sqlx::Postgres>,
-        group_name: String,
+        group_id: Uuid,
     ) -> Result<Vec<JoinRequestWithUser>, sqlx::Error> {
         let join_requests = sqlx::query_as!(
             JoinRequestWithUser,
             r#"
-            SELECT jr.*, u.email as user_email, u.username as user_name
+            SELECT jr.group_id, jr.user_id, jr.created_at, u.email as user_email, u.username as user_name
             FROM join_requests jr INNER JOIN users u 
             ON jr.user_id = u.id
-            WHERE jr.group_name = $1
+            WHERE jr.group_id = $1
             ORDER BY jr.created_at DESC
             "#,
-            group_name
+            group_id
         )
         .fetch_all(pool)
         .await?;
@@ -43,17 +43,17 @@ impl JoinRequest {
 
     pub async fn find_by_group_and_user(
         pool: &sqlx::Pool<sqlx::Postgres>,
-        group_name: String,
+        group_id: Uuid,
         user_id: Uuid,
     ) -> Result<Option<Self>, sqlx::Error> {
         let join_request = sqlx::query_as!(
             JoinRequest,
             r#"
-            SELECT group_name, user_id, created_at as "created_at!"
+            SELECT group_id, user_id, created_at as "created_at!"
             FROM join_requests
-            WHERE group_name = $1 AND user_id = $2
+            WHERE group_id = $1 AND user_id = $2
             "#,
-            group_name,
+            group_id,
             user_id
         )
         .fetch_optional(pool)
@@ -69,11 +69,11 @@ impl JoinRequest {
         let join_request = sqlx::query_as!(
             JoinRequest,
             r#"
-            INSERT INTO join_requests (group_name, user_id)
+            INSERT INTO join_requests (group_id, user_id)
             VALUES ($1, $2)
-            RETURNING group_name, user_id, created_at as "created_at!"
+            RETURNING group_id, user_id, created_at as "created_at!"
             "#,
-            new_join_request.group_name,
+            new_join_request.group_id,
             new_join_request.user_id
         )
         .fetch_one(pool)
@@ -84,15 +84,15 @@ impl JoinRequest {
 
     pub async fn delete(
         pool: &sqlx::Pool<sqlx::Postgres>,
-        group_name: String,
+        group_id: Uuid,
         user_id: Uuid,
     ) -> Result<(), sqlx::Error> {
         sqlx::query!(
             r#"
             DELETE FROM join_requests
-            WHERE group_name = $1 AND user_id = $2
+            WHERE group_id = $1 AND user_id = $2
             "#,
-            group_name,
+            group_id,
             user_id
         )
         .execute(pool)
@@ -100,4 +100,24 @@ impl JoinRequest {
 
         Ok(())
     }
+
+    pub async fn find_by_user(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let join_requests = sqlx::query_as!(
+            JoinRequest,
+            r#"
+            SELECT group_id, user_id, created_at
+            FROM join_requests
+            WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(join_requests)
+    }
 }
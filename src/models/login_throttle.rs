@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Unsuccessful sign-in attempts for one account (`scope = "account"`, keyed by
+/// normalized email) or one client address (`scope = "ip"`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct LoginThrottle {
+    pub scope: String,
+    pub key: String,
+    pub failed_count: i32,
+    pub last_failed_at: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+impl LoginThrottle {
+    pub async fn find(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        scope: &str,
+        key: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let throttle = sqlx::query_as!(
+            LoginThrottle,
+            r#"
+            SELECT scope, key, failed_count, last_failed_at, locked_until
+            FROM login_throttles
+            WHERE scope = $1 AND key = $2
+            "#,
+            scope,
+            key
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(throttle)
+    }
+
+    /// Count an attempt before it is checked, so concurrent attempts cannot
+    /// all pass on the same count. Attempts older than `window_seconds` are
+    /// forgotten and the count starts over. From the `free_attempts`-th
+    /// attempt on the key is locked for `lock_seconds` in the same statement.
+    /// Returns `None` without counting while the key is locked.
+    pub async fn record_attempt(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        scope: &str,
+        key: &str,
+        window_seconds: i64,
+        free_attempts: i32,
+        lock_seconds: i64,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let throttle = sqlx::query_as!(
+            LoginThrottle,
+            r#"
+            INSERT INTO login_throttles (scope, key, failed_count, last_failed_at)
+            VALUES ($1, $2, 1, NOW())
+            ON CONFLICT (scope, key) DO UPDATE
+            SET failed_count = CASE
+                    WHEN login_throttles.last_failed_at < NOW() - make_interval(secs => $3::BIGINT::DOUBLE PRECISION)
+                    THEN 1
+                    ELSE login_throttles.failed_count + 1
+                END,
+                last_failed_at = NOW(),
+                locked_until = CASE
+                    WHEN login_throttles.last_failed_at >= NOW() - make_interval(secs => $3::BIGINT::DOUBLE PRECISION)
+                        AND login_throttles.failed_count + 1 >= $4
+                    THEN NOW() + make_interval(secs => $5::BIGINT::DOUBLE PRECISION)
+                END
+            WHERE login_throttles.locked_until IS NULL OR login_throttles.locked_until <= NOW()
+            RETURNING scope, key, failed_count, last_failed_at, locked_until
+            "#,
+            scope,
+            key,
+            window_seconds,
+            free_attempts,
+            lock_seconds
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(throttle)
+    }
+
+    /// Take back an attempt that turned out not to be a failure, lifting the
+    /// lock if the remaining count is within `free_attempts`
+    pub async fn refund_attempt(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        scope: &str,
+        key: &str,
+        free_attempts: i32,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE login_throttles
+            SET failed_count = GREATEST(failed_count - 1, 0),
+                locked_until = CASE WHEN failed_count - 1 < $3 THEN NULL ELSE locked_until END
+            WHERE scope = $1 AND key = $2
+            "#,
+            scope,
+            key,
+            free_attempts
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn lock_until(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        scope: &str,
+        key: &str,
+        locked_until: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE login_throttles
+            SET locked_until = $3
+            WHERE scope = $1 AND key = $2
+            "#,
+            scope,
+            key,
+            locked_until
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Forget all failures, returning the removed record if there was one
+    pub async fn clear(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        scope: &str,
+        key: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let throttle = sqlx::query_as!(
+            LoginThrottle,
+            r#"
+            DELETE FROM login_throttles
+            WHERE scope = $1 AND key = $2
+            RETURNING scope, key, failed_count, last_failed_at, locked_until
+            "#,
+            scope,
+            key
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(throttle)
+    }
+}
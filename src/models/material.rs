@@ -6,8 +6,9 @@ use sqlx::FromRow;
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
 pub struct Material {
     pub id: Uuid,
-    pub group_name: String,
-    pub course_name: String,
+    pub course_id: Uuid,
+    /// Group of the course, for permission checks
+    pub group_id: Uuid,
     pub title: String,
     pub file: Option<String>,
     pub url: Option<String>,
@@ -16,13 +17,12 @@ pub struct Material {
     pub material_type: String, // Renamed to avoid Rust keyword conflict
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
-    pub creator: Uuid,
+    pub creator: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct NewMaterial {
-    pub group_name: String,
-    pub course_name: String,
+    pub course_id: Uuid,
     pub title: String,
     pub file: Option<String>,
     pub url: Option<String>,
@@ -38,12 +38,15 @@ impl Material {
         let material = sqlx::query_as!(
             Material,
             r#"
-            INSERT INTO materials (group_name, course_name, title, file, url, type, creator)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-            RETURNING id, group_name, course_name, title, file, url, type as "material_type", created_at as "created_at!", updated_at as "updated_at!", creator
+            WITH m AS (
+                INSERT INTO materials (course_id, title, file, url, type, creator)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING *
+            )
+            SELECT m.id as "id!", m.course_id as "course_id!", c.group_id, m.title as "title!", m.file, m.url, m.type as "material_type!", m.created_at as "created_at!", m.updated_at as "updated_at!", m.creator
+            FROM m INNER JOIN courses c ON c.id = m.course_id
             "#,
-            new_material.group_name,
-            new_material.course_name,
+            new_material.course_id,
             new_material.title,
             new_material.file,
             new_material.url,
@@ -58,19 +61,17 @@ impl Material {
 
     pub async fn find_by_course(
         pool: &sqlx::Pool<sqlx::Postgres>,
-        group_name: String,
-        course_name: String,
+        course_id: Uuid,
     ) -> Result<Vec<Self>, sqlx::Error> {
         let materials = sqlx::query_as!(
             Material,
             r#"
-            SELECT id, group_name, course_name, title, file, url, type as "material_type", created_at as "created_at!", updated_at as "updated_at!", creator
-            FROM materials
-            WHERE group_name = $1 AND course_name = $2
-            ORDER BY created_at DESC
+            SELECT m.id, m.course_id, c.group_id, m.title, m.file, m.url, m.type as "material_type", m.created_at as "created_at!", m.updated_at as "updated_at!", m.creator
+            FROM materials m INNER JOIN courses c ON c.id = m.course_id
+            WHERE m.course_id = $1
+            ORDER BY m.created_at DESC
             "#,
-            group_name,
-            course_name
+            course_id
         )
         .fetch_all(pool)
         .await?;
@@ -85,9 +86,9 @@ impl Material {
         let material = sqlx::query_as!(
             Material,
             r#"
-            SELECT id, group_name, course_name, title, file, url, type as "material_type", created_at as "created_at!", updated_at as "updated_at!", creator
-            FROM materials
-            WHERE id = $1
+            SELECT m.id, m.course_id, c.group_id, m.title, m.file, m.url, m.type as "material_type", m.created_at as "created_at!", m.updated_at as "updated_at!", m.creator
+            FROM materials m INNER JOIN courses c ON c.id = m.course_id
+            WHERE m.id = $1
             "#,
             id
         )
@@ -108,10 +109,14 @@ impl Material {
         let updated_material = sqlx::query_as!(
             Material,
             r#"
-            UPDATE materials
-            SET title = $2, file = $3, url = $4, type = $5, updated_at = NOW()
-            WHERE id = $1
-            RETURNING id, group_name, course_name, title, file, url, type as "material_type", created_at as "created_at!", updated_at as "updated_at!", creator
+            WITH m AS (
+                UPDATE materials
+                SET title = $2, file = $3, url = $4, type = $5, updated_at = NOW()
+                WHERE id = $1
+                RETURNING *
+            )
+            SELECT m.id as "id!", m.course_id as "course_id!", c.group_id, m.title as "title!", m.file, m.url, m.type as "material_type!", m.created_at as "created_at!", m.updated_at as "updated_at!", m.creator
+            FROM m INNER JOIN courses c ON c.id = m.course_id
             "#,
             id,
             title,
@@ -141,4 +146,24 @@ impl Material {
 
         Ok(())
     }
+
+    pub async fn find_by_creator(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        creator: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let materials = sqlx::query_as!(
+            Material,
+            r#"
+            SELECT m.id, m.course_id, c.group_id, m.title, m.file, m.url, m.type as "material_type", m.created_at as "created_at!", m.updated_at as "updated_at!", m.creator
+            FROM materials m INNER JOIN courses c ON c.id = m.course_id
+            WHERE m.creator = $1
+            ORDER BY m.created_at
+            "#,
+            creator
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(materials)
+    }
 }
@@ -5,7 +5,7 @@ use sqlx::FromRow;
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
 pub struct MaterialLabel {
     pub material_id: Uuid,
-    pub group_name: String,
+    pub group_id: Uuid,
     pub label_name: String,
     pub number: i32,
 }
@@ -18,12 +18,12 @@ impl MaterialLabel {
         let material_label = sqlx::query_as!(
             MaterialLabel,
             r#"
-            INSERT INTO material_labels (material_id, group_name, label_name, number)
+            INSERT INTO material_labels (material_id, group_id, label_name, number)
             VALUES ($1, $2, $3, $4)
-            RETURNING material_id, group_name, label_name, number
+            RETURNING material_id, group_id, label_name, number
             "#,
             material.material_id,
-            material.group_name,
+            material.group_id,
             material.label_name,
             material.number
         )
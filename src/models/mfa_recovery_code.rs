@@ -0,0 +1,87 @@
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A single-use MFA recovery code, stored as a SHA-256 hash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct MfaRecoveryCode {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub code_hash: String,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl MfaRecoveryCode {
+    /// Replace all of the user's recovery codes with a new set
+    pub async fn replace_for_user(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+        code_hashes: Vec<String>,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM mfa_recovery_codes
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO mfa_recovery_codes (user_id, code_hash)
+            SELECT $1, code_hash FROM UNNEST($2::VARCHAR[]) AS code_hash
+            "#,
+            user_id,
+            &code_hashes
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Mark an unused code as used. Returns `false` if no such code exists.
+    pub async fn consume(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+        code_hash: String,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE mfa_recovery_codes
+            SET used_at = NOW()
+            WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            "#,
+            user_id,
+            code_hash
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn delete_for_user(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            DELETE FROM mfa_recovery_codes
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
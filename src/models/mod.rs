@@ -5,4 +5,20 @@ pub mod join_request;
 pub mod material;
 pub mod material_label;
 pub mod comment;
-pub mod user;
\ No newline at end of file
+pub mod user;
+pub mod refresh_token;
+pub mod revoked_access_token;
+pub mod password_reset_token;
+pub mod email_verification_token;
+pub mod user_totp;
+pub mod mfa_recovery_code;
+pub mod personal_access_token;
+pub mod session;
+pub mod login_throttle;
+pub mod auth_event;
+pub mod oidc_login_state;
+pub mod user_identity;
+pub mod account_deletion;
+pub mod admin_audit_log;
+pub mod group_ownership_transfer;
+pub mod group_invitation;
@@ -0,0 +1,105 @@
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// PKCE verifier and nonce of an OIDC authorization request in flight, keyed
+/// by the SHA-256 hash of the `state` parameter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct OidcLoginState {
+    pub id: Uuid,
+    pub state_hash: String,
+    pub code_verifier: String,
+    pub nonce: String,
+    pub expires_at: DateTime<Utc>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewOidcLoginState {
+    pub state_hash: String,
+    pub code_verifier: String,
+    pub nonce: String,
+    pub expires_at: DateTime<Utc>,
+    pub ip_address: Option<String>,
+}
+
+impl OidcLoginState {
+    pub async fn create(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        new_state: NewOidcLoginState,
+    ) -> Result<Self, sqlx::Error> {
+        // Abandoned logins are never consumed, clear them out as we go
+        OidcLoginState::delete_expired(pool).await?;
+
+        let state = sqlx::query_as!(
+            OidcLoginState,
+            r#"
+            INSERT INTO oidc_login_states (state_hash, code_verifier, nonce, expires_at, ip_address)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, state_hash, code_verifier, nonce, expires_at, ip_address, created_at as "created_at!"
+            "#,
+            new_state.state_hash,
+            new_state.code_verifier,
+            new_state.nonce,
+            new_state.expires_at,
+            new_state.ip_address
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(state)
+    }
+
+    /// Remove and return an unexpired state. Returns `None` if the state is
+    /// unknown, expired or was already used.
+    pub async fn consume(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        state_hash: String,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let state = sqlx::query_as!(
+            OidcLoginState,
+            r#"
+            DELETE FROM oidc_login_states
+            WHERE state_hash = $1 AND expires_at > NOW()
+            RETURNING id, state_hash, code_verifier, nonce, expires_at, ip_address, created_at as "created_at!"
+            "#,
+            state_hash
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(state)
+    }
+
+    /// Unexpired logins started from the address
+    pub async fn count_pending_for_ip(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        ip_address: &str,
+    ) -> Result<i64, sqlx::Error> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM oidc_login_states
+            WHERE ip_address = $1 AND expires_at > NOW()
+            "#,
+            ip_address
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Returns how many expired states were removed
+    pub async fn delete_expired(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!("DELETE FROM oidc_login_states WHERE expires_at <= NOW()")
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,87 @@
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A single-use password reset token. Only the SHA-256 hash of the token
+/// sent by email is stored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct PasswordResetToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewPasswordResetToken {
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl PasswordResetToken {
+    pub async fn create(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        new_token: NewPasswordResetToken,
+    ) -> Result<Self, sqlx::Error> {
+        let token = sqlx::query_as!(
+            PasswordResetToken,
+            r#"
+            INSERT INTO password_reset_tokens (user_id, token_hash, expires_at)
+            VALUES ($1, $2, $3)
+            RETURNING id, user_id, token_hash, expires_at, used_at, created_at as "created_at!"
+            "#,
+            new_token.user_id,
+            new_token.token_hash,
+            new_token.expires_at
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Mark an unused, unexpired token as used and return it. Returns `None`
+    /// if the token is unknown, expired or was already used.
+    pub async fn consume(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        token_hash: String,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let token = sqlx::query_as!(
+            PasswordResetToken,
+            r#"
+            UPDATE password_reset_tokens
+            SET used_at = NOW()
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+            RETURNING id, user_id, token_hash, expires_at, used_at, created_at as "created_at!"
+            "#,
+            token_hash
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Invalidate every outstanding token of the user
+    pub async fn invalidate_for_user(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE password_reset_tokens
+            SET used_at = NOW()
+            WHERE user_id = $1 AND used_at IS NULL
+            "#,
+            user_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A named, long-lived API token for scripts. Only the SHA-256 hash of the
+/// token is stored; the plaintext is shown once on creation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct PersonalAccessToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewPersonalAccessToken {
+    pub user_id: Uuid,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl PersonalAccessToken {
+    pub async fn create(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        new_token: NewPersonalAccessToken,
+    ) -> Result<Self, sqlx::Error> {
+        let token = sqlx::query_as!(
+            PersonalAccessToken,
+            r#"
+            INSERT INTO personal_access_tokens (user_id, name, token_hash, scopes, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, user_id, name, token_hash, scopes, expires_at, last_used_at,
+                      created_at as "created_at!"
+            "#,
+            new_token.user_id,
+            new_token.name,
+            new_token.token_hash,
+            &new_token.scopes,
+            new_token.expires_at
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(token)
+    }
+
+    pub async fn list_by_user(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let tokens = sqlx::query_as!(
+            PersonalAccessToken,
+            r#"
+            SELECT id, user_id, name, token_hash, scopes, expires_at, last_used_at,
+                   created_at as "created_at!"
+            FROM personal_access_tokens
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(tokens)
+    }
+
+    /// Look up an unexpired token by hash and stamp its last use. Returns
+    /// `None` if the token is unknown, revoked or expired.
+    pub async fn touch_by_hash(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        token_hash: String,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let token = sqlx::query_as!(
+            PersonalAccessToken,
+            r#"
+            UPDATE personal_access_tokens
+            SET last_used_at = NOW()
+            WHERE token_hash = $1 AND (expires_at IS NULL OR expires_at > NOW())
+            RETURNING id, user_id, name, token_hash, scopes, expires_at, last_used_at,
+                      created_at as "created_at!"
+            "#,
+            token_hash
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Delete one of the user's tokens. Returns whether a token was deleted.
+    pub async fn delete(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM personal_access_tokens
+            WHERE id = $1 AND user_id = $2
+            "#,
+            id,
+            user_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn delete_all_for_user(
+        executor: impl sqlx::PgExecutor<'_>,
+        user_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            DELETE FROM personal_access_tokens
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A persisted refresh token. `id` is the `jti` claim of the issued JWT and
+/// `family_id` is shared by every token rotated from the same login.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct RefreshToken {
+    pub id: Uuid,
+    pub family_id: Uuid,
+    pub user_id: Uuid,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub replaced_by: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewRefreshToken {
+    pub id: Uuid,
+    pub family_id: Uuid,
+    pub user_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl RefreshToken {
+    pub async fn create(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        new_token: NewRefreshToken,
+    ) -> Result<Self, sqlx::Error> {
+        let token = sqlx::query_as!(
+            RefreshToken,
+            r#"
+            INSERT INTO refresh_tokens (id, family_id, user_id, expires_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, family_id, user_id, issued_at as "issued_at!", expires_at, revoked_at, replaced_by
+            "#,
+            new_token.id,
+            new_token.family_id,
+            new_token.user_id,
+            new_token.expires_at
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(token)
+    }
+
+    pub async fn find_by_id(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let token = sqlx::query_as!(
+            RefreshToken,
+            r#"
+            SELECT id, family_id, user_id, issued_at as "issued_at!", expires_at, revoked_at, replaced_by
+            FROM refresh_tokens
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Revokes a single token if it is still active. Returns `false` when the
+    /// token was already revoked, which callers treat as token reuse.
+    pub async fn revoke(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+        replaced_by: Option<Uuid>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = NOW(), replaced_by = $2
+            WHERE id = $1 AND revoked_at IS NULL
+            "#,
+            id,
+            replaced_by
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn revoke_family(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        family_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = NOW()
+            WHERE family_id = $1 AND revoked_at IS NULL
+            "#,
+            family_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn revoke_all_for_user(
+        executor: impl sqlx::PgExecutor<'_>,
+        user_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = NOW()
+            WHERE user_id = $1 AND revoked_at IS NULL
+            "#,
+            user_id
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct RevokedAccessToken {
+    pub jti: Uuid,
+    pub user_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl RevokedAccessToken {
+    pub async fn create(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        revoked: RevokedAccessToken,
+    ) -> Result<Self, sqlx::Error> {
+        let revoked_token = sqlx::query_as!(
+            RevokedAccessToken,
+            r#"
+            INSERT INTO revoked_access_tokens (jti, user_id, expires_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (jti) DO UPDATE SET expires_at = EXCLUDED.expires_at
+            RETURNING jti, user_id, expires_at
+            "#,
+            revoked.jti,
+            revoked.user_id,
+            revoked.expires_at
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(revoked_token)
+    }
+
+    /// Insert unless the id is already denied, returning whether it was new
+    pub async fn create_if_absent(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        revoked: RevokedAccessToken,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO revoked_access_tokens (jti, user_id, expires_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (jti) DO NOTHING
+            "#,
+            revoked.jti,
+            revoked.user_id,
+            revoked.expires_at
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn find_unexpired(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let revoked_tokens = sqlx::query_as!(
+            RevokedAccessToken,
+            r#"
+            SELECT jti, user_id, expires_at
+            FROM revoked_access_tokens
+            WHERE expires_at > NOW()
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(revoked_tokens)
+    }
+
+    /// The unexpired entries among the given token and session ids
+    pub async fn find_unexpired_by_ids(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        ids: &[Uuid],
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let revoked_tokens = sqlx::query_as!(
+            RevokedAccessToken,
+            r#"
+            SELECT jti, user_id, expires_at
+            FROM revoked_access_tokens
+            WHERE jti = ANY($1) AND expires_at > NOW()
+            "#,
+            ids
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(revoked_tokens)
+    }
+
+    pub async fn delete_expired(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            DELETE FROM revoked_access_tokens
+            WHERE expires_at <= NOW()
+            "#
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
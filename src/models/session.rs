@@ -0,0 +1,161 @@
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A signed-in device. `id` is the `family_id` of the refresh tokens rotated
+/// from the login and the `sid` claim of its access tokens.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct Session {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewSession {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Session {
+    pub async fn create(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        new_session: NewSession,
+    ) -> Result<Self, sqlx::Error> {
+        let session = sqlx::query_as!(
+            Session,
+            r#"
+            INSERT INTO sessions (id, user_id, user_agent, ip_address, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, user_id, user_agent, ip_address, created_at as "created_at!",
+                      last_used_at as "last_used_at!", expires_at, revoked_at
+            "#,
+            new_session.id,
+            new_session.user_id,
+            new_session.user_agent,
+            new_session.ip_address,
+            new_session.expires_at
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(session)
+    }
+
+    /// Sessions that are neither revoked nor expired, most recently used first
+    pub async fn list_active_by_user(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let sessions = sqlx::query_as!(
+            Session,
+            r#"
+            SELECT id, user_id, user_agent, ip_address, created_at as "created_at!",
+                   last_used_at as "last_used_at!", expires_at, revoked_at
+            FROM sessions
+            WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+            ORDER BY last_used_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(sessions)
+    }
+
+    /// Record a refresh of the session and extend it to the new token's expiry
+    pub async fn touch(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE sessions
+            SET last_used_at = NOW(), expires_at = $2
+            WHERE id = $1
+            "#,
+            id,
+            expires_at
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Revoke one of the user's sessions. Returns `false` if it does not
+    /// exist, belongs to someone else or was already revoked.
+    pub async fn revoke(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE sessions
+            SET revoked_at = NOW()
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+            "#,
+            id,
+            user_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Revoke every live session of the user except `keep`, returning the
+    /// ids of the sessions revoked
+    pub async fn revoke_all_except(
+        executor: impl sqlx::PgExecutor<'_>,
+        user_id: Uuid,
+        keep: Option<Uuid>,
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            UPDATE sessions
+            SET revoked_at = NOW()
+            WHERE user_id = $1 AND revoked_at IS NULL AND ($2::UUID IS NULL OR id <> $2)
+            RETURNING id
+            "#,
+            user_id,
+            keep
+        )
+        .fetch_all(executor)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.id).collect())
+    }
+
+    pub async fn find_by_id(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let session = sqlx::query_as!(
+            Session,
+            r#"
+            SELECT id, user_id, user_agent, ip_address, created_at as "created_at!",
+                   last_used_at as "last_used_at!", expires_at, revoked_at
+            FROM sessions
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(session)
+    }
+}
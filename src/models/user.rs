@@ -3,6 +3,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::permissions::PlatformRole;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
 pub struct User {
     pub id: Uuid,
@@ -13,6 +15,12 @@ pub struct User {
     pub created_at: DateTime<Utc>,
     #[serde(skip_serializing)]
     pub password_hash: String,
+    #[serde(skip_serializing)]
+    pub token_version: i32,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    /// Platform-wide role, see `PlatformRole`
+    pub role: String,
+    pub disabled_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -25,6 +33,14 @@ pub struct NewUser {
 }
 
 impl User {
+    pub fn is_admin(&self) -> bool {
+        PlatformRole::parse(&self.role) == Some(PlatformRole::Admin)
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.disabled_at.is_some()
+    }
+
     pub async fn create(
         pool: &sqlx::Pool<sqlx::Postgres>,
         new_user: NewUser,
@@ -34,7 +50,7 @@ impl User {
             r#"
             INSERT INTO users (email, username, password_hash, bio, image_url)
             VALUES ($1, $2, $3, $4, $5)
-            RETURNING id, email, username, password_hash, bio, image_url, created_at as "created_at!"
+            RETURNING id, email, username, password_hash, bio, image_url, created_at as "created_at!", token_version, email_verified_at, role, disabled_at
             "#,
             new_user.email,
             new_user.username,
@@ -49,19 +65,19 @@ impl User {
     }
 
     pub async fn find_by_id(
-        pool: &sqlx::Pool<sqlx::Postgres>,
+        executor: impl sqlx::PgExecutor<'_>,
         id: Uuid,
     ) -> Result<Option<Self>, sqlx::Error> {
         let user = sqlx::query_as!(
             User,
             r#"
-            SELECT id, email, username, password_hash, bio, image_url, created_at as "created_at!"
+            SELECT id, email, username, password_hash, bio, image_url, created_at as "created_at!", token_version, email_verified_at, role, disabled_at
             FROM users
             WHERE id = $1
             "#,
             id
         )
-        .fetch_optional(pool)
+        .fetch_optional(executor)
         .await?;
 
         Ok(user)
@@ -74,7 +90,7 @@ impl User {
         let user = sqlx::query_as!(
             User,
             r#"
-            SELECT id, email, username, password_hash, bio, image_url, created_at as "created_at!"
+            SELECT id, email, username, password_hash, bio, image_url, created_at as "created_at!", token_version, email_verified_at, role, disabled_at
             FROM users
             WHERE email = $1
             "#,
@@ -93,7 +109,7 @@ impl User {
         let user = sqlx::query_as!(
             User,
             r#"
-            SELECT id, email, username, password_hash, bio, image_url, created_at as "created_at!"
+            SELECT id, email, username, password_hash, bio, image_url, created_at as "created_at!", token_version, email_verified_at, role, disabled_at
             FROM users
             WHERE username = $1
             "#,
@@ -106,7 +122,7 @@ impl User {
     }
 
     pub async fn update_password(
-        pool: &sqlx::Pool<sqlx::Postgres>,
+        executor: impl sqlx::PgExecutor<'_>,
         id: Uuid,
         new_password_hash: String,
     ) -> Result<Self, sqlx::Error> {
@@ -116,17 +132,85 @@ impl User {
             UPDATE users
             SET password_hash = $2
             WHERE id = $1
-            RETURNING id, email, username, password_hash, bio, image_url, created_at as "created_at!"
+            RETURNING id, email, username, password_hash, bio, image_url, created_at as "created_at!", token_version, email_verified_at, role, disabled_at
             "#,
             id,
             new_password_hash
         )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(updated_user)
+    }
+
+    pub async fn update_profile(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+        username: String,
+        bio: Option<String>,
+        image_url: Option<String>,
+    ) -> Result<Self, sqlx::Error> {
+        let updated_user = sqlx::query_as!(
+            User,
+            r#"
+            UPDATE users
+            SET username = $2, bio = $3, image_url = $4
+            WHERE id = $1
+            RETURNING id, email, username, password_hash, bio, image_url, created_at as "created_at!", token_version, email_verified_at, role, disabled_at
+            "#,
+            id,
+            username,
+            bio,
+            image_url
+        )
         .fetch_one(pool)
         .await?;
 
         Ok(updated_user)
     }
 
+    pub async fn mark_email_verified(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+    ) -> Result<Self, sqlx::Error> {
+        let updated_user = sqlx::query_as!(
+            User,
+            r#"
+            UPDATE users
+            SET email_verified_at = COALESCE(email_verified_at, NOW())
+            WHERE id = $1
+            RETURNING id, email, username, password_hash, bio, image_url, created_at as "created_at!", token_version, email_verified_at, role, disabled_at
+            "#,
+            id
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(updated_user)
+    }
+
+    /// Bump the user's token version so every access token issued before
+    /// now is rejected by the auth middleware
+    pub async fn invalidate_tokens(
+        executor: impl sqlx::PgExecutor<'_>,
+        id: Uuid,
+    ) -> Result<Self, sqlx::Error> {
+        let updated_user = sqlx::query_as!(
+            User,
+            r#"
+            UPDATE users
+            SET token_version = token_version + 1
+            WHERE id = $1
+            RETURNING id, email, username, password_hash, bio, image_url, created_at as "created_at!", token_version, email_verified_at, role, disabled_at
+            "#,
+            id
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(updated_user)
+    }
+
     pub async fn delete(
         pool: &sqlx::Pool<sqlx::Postgres>,
         id: Uuid,
@@ -143,4 +227,76 @@ impl User {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Users whose email or username contains `query`, newest first
+    pub async fn search(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        query: Option<String>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let pattern = query.map(|q| format!("%{}%", q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")));
+        let users = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id, email, username, password_hash, bio, image_url, created_at as "created_at!", token_version, email_verified_at, role, disabled_at
+            FROM users
+            WHERE $1::TEXT IS NULL OR email ILIKE $1 OR username ILIKE $1
+            ORDER BY created_at DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            pattern,
+            limit,
+            offset
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(users)
+    }
+
+    pub async fn update_role(
+        executor: impl sqlx::PgExecutor<'_>,
+        id: Uuid,
+        role: String,
+    ) -> Result<Self, sqlx::Error> {
+        let updated_user = sqlx::query_as!(
+            User,
+            r#"
+            UPDATE users
+            SET role = $2
+            WHERE id = $1
+            RETURNING id, email, username, password_hash, bio, image_url, created_at as "created_at!", token_version, email_verified_at, role, disabled_at
+            "#,
+            id,
+            role
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(updated_user)
+    }
+
+    /// Disable the account, or enable it again with `None`
+    pub async fn set_disabled_at(
+        executor: impl sqlx::PgExecutor<'_>,
+        id: Uuid,
+        disabled_at: Option<DateTime<Utc>>,
+    ) -> Result<Self, sqlx::Error> {
+        let updated_user = sqlx::query_as!(
+            User,
+            r#"
+            UPDATE users
+            SET disabled_at = $2
+            WHERE id = $1
+            RETURNING id, email, username, password_hash, bio, image_url, created_at as "created_at!", token_version, email_verified_at, role, disabled_at
+            "#,
+            id,
+            disabled_at
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(updated_user)
+    }
+}
@@ -0,0 +1,69 @@
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// An account at an external identity provider, identified by the ID token
+/// `iss` and `sub` claims, that signs in as a local user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct UserIdentity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub issuer: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewUserIdentity {
+    pub user_id: Uuid,
+    pub issuer: String,
+    pub subject: String,
+    pub email: Option<String>,
+}
+
+impl UserIdentity {
+    pub async fn create(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        new_identity: NewUserIdentity,
+    ) -> Result<Self, sqlx::Error> {
+        let identity = sqlx::query_as!(
+            UserIdentity,
+            r#"
+            INSERT INTO user_identities (user_id, issuer, subject, email)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, user_id, issuer, subject, email, created_at as "created_at!"
+            "#,
+            new_identity.user_id,
+            new_identity.issuer,
+            new_identity.subject,
+            new_identity.email
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(identity)
+    }
+
+    pub async fn find_by_subject(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let identity = sqlx::query_as!(
+            UserIdentity,
+            r#"
+            SELECT id, user_id, issuer, subject, email, created_at as "created_at!"
+            FROM user_identities
+            WHERE issuer = $1 AND subject = $2
+            "#,
+            issuer,
+            subject
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(identity)
+    }
+}
@@ -0,0 +1,120 @@
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// TOTP enrollment of a user. The row exists but `confirmed_at` is null
+/// until the user proves possession with a first code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct UserTotp {
+    pub user_id: Uuid,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub last_used_step: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl UserTotp {
+    /// Start (or restart) an unconfirmed enrollment with a new secret
+    pub async fn upsert_pending(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+        secret: String,
+    ) -> Result<Self, sqlx::Error> {
+        let user_totp = sqlx::query_as!(
+            UserTotp,
+            r#"
+            INSERT INTO user_totp (user_id, secret)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE
+            SET secret = EXCLUDED.secret, confirmed_at = NULL, last_used_step = NULL, created_at = NOW()
+            RETURNING user_id, secret, confirmed_at, last_used_step, created_at as "created_at!"
+            "#,
+            user_id,
+            secret
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(user_totp)
+    }
+
+    pub async fn find_by_user_id(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let user_totp = sqlx::query_as!(
+            UserTotp,
+            r#"
+            SELECT user_id, secret, confirmed_at, last_used_step, created_at as "created_at!"
+            FROM user_totp
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(user_totp)
+    }
+
+    pub async fn confirm(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+    ) -> Result<Self, sqlx::Error> {
+        let user_totp = sqlx::query_as!(
+            UserTotp,
+            r#"
+            UPDATE user_totp
+            SET confirmed_at = NOW()
+            WHERE user_id = $1
+            RETURNING user_id, secret, confirmed_at, last_used_step, created_at as "created_at!"
+            "#,
+            user_id
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(user_totp)
+    }
+
+    /// Record the time step of an accepted code. Returns `false` if that step
+    /// (or a later one) was already used, so a code can't be replayed.
+    pub async fn record_used_step(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+        step: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE user_totp
+            SET last_used_step = $2
+            WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+            "#,
+            user_id,
+            step
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn delete(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        user_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            DELETE FROM user_totp
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
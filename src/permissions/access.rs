@@ -0,0 +1,177 @@
+// src/permissions/access.rs
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    errors::AppError,
+    middleware::AuthenticatedUser,
+    models::{group::Group, group_member::GroupMember},
+    permissions::group::{can_manage_member, is_allowed, GroupAction, GroupRole},
+};
+
+/// Checks what the signed-in user may do in a group. Needs `auth_middleware`
+/// in front of the handler.
+#[derive(Debug, Clone)]
+pub struct GroupPermissions {
+    pool: Pool<Postgres>,
+    user_id: Uuid,
+}
+
+impl GroupPermissions {
+    pub fn user_id(&self) -> Uuid {
+        self.user_id
+    }
+
+    /// The caller's role in the group, `None` if they are not a member
+    pub async fn role_in(&self, group: &Group) -> Result<Option<GroupRole>, AppError> {
+        role_of(&self.pool, group, self.user_id).await
+    }
+
+    /// Fail unless the caller may take `action` in the group
+    pub async fn require(&self, group_id: Uuid, action: GroupAction) -> Result<GroupRole, AppError> {
+        self.require_on(group_id, action, None).await
+    }
+
+    /// Like `require`, for an action on a material or comment written by
+    /// `author_id`
+    pub async fn require_on(
+        &self,
+        group_id: Uuid,
+        action: GroupAction,
+        author_id: Option<Uuid>,
+    ) -> Result<GroupRole, AppError> {
+        let group = Group::find_by_id(&self.pool, group_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+
+        let role = self.role_in(&group).await?;
+        let is_author = author_id == Some(self.user_id);
+        if is_allowed(role, action, is_author, &group.settings()) {
+            // is_allowed never passes non-members
+            return Ok(role.unwrap_or(GroupRole::Member));
+        }
+
+        Err(match role {
+            None => AppError::PermissionDenied("You are not a member of this group".to_string()),
+            Some(role) => AppError::PermissionDenied(format!(
+                "Group {}s are not allowed to {}",
+                role.as_str(),
+                action.as_str()
+            )),
+        })
+    }
+
+    /// Fail unless the caller may remove `member_id` from the group, or
+    /// change their role to `new_role`. Returns the member's current role.
+    pub async fn require_over_member(
+        &self,
+        group_id: Uuid,
+        member_id: Uuid,
+        new_role: Option<GroupRole>,
+    ) -> Result<GroupRole, AppError> {
+        let actor = self.require(group_id, GroupAction::ManageMembers).await?;
+        let group = Group::find_by_id(&self.pool, group_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+
+        let target = role_of(&self.pool, &group, member_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        if target == GroupRole::Owner {
+            return Err(AppError::Conflict(
+                "The group owner cannot be removed or demoted; transfer ownership first".to_string(),
+            ));
+        }
+
+        if !can_manage_member(actor, target, new_role) {
+            return Err(AppError::PermissionDenied(if actor <= target {
+                "You can only manage members with a role below yours".to_string()
+            } else {
+                "You can only hand out roles below your own".to_string()
+            }));
+        }
+        Ok(target)
+    }
+}
+
+/// Role of `user_id` in the group, `None` if they are not a member
+pub async fn role_of(pool: &Pool<Postgres>, group: &Group, user_id: Uuid) -> Result<Option<GroupRole>, AppError> {
+    if group.owner_id == user_id {
+        return Ok(Some(GroupRole::Owner));
+    }
+
+    let member = GroupMember::find_by_user_and_group(pool, user_id, group.id).await?;
+    Ok(resolve_role(group, user_id, member.as_ref()))
+}
+
+/// The owner is whoever the group names, whatever their membership says
+fn resolve_role(group: &Group, user_id: Uuid, member: Option<&GroupMember>) -> Option<GroupRole> {
+    if group.owner_id == user_id {
+        return Some(GroupRole::Owner);
+    }
+    member.map(|m| GroupRole::from_member_role(m.user_role.as_deref()))
+}
+
+impl<S> FromRequestParts<S> for GroupPermissions
+where
+    S: Send + Sync,
+    Pool<Postgres>: FromRef<S>,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let user = AuthenticatedUser::from_request_parts(parts, state).await?;
+        Ok(GroupPermissions {
+            pool: Pool::from_ref(state),
+            user_id: user.id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn admin_member(group: &Group, user_id: Uuid) -> GroupMember {
+        GroupMember {
+            user_id,
+            group_id: group.id,
+            user_role: Some("admin".to_string()),
+            joined_at: Some(Utc::now()),
+        }
+    }
+
+    #[test]
+    fn test_roles_after_ownership_transfer() {
+        let previous_owner = Uuid::new_v4();
+        let new_owner = Uuid::new_v4();
+        // `Group::set_owner` names the new owner and keeps both as admin members
+        let group = Group {
+            id: Uuid::new_v4(),
+            owner_id: new_owner,
+            name: "Linear Algebra".to_string(),
+            slug: "linear-algebra".to_string(),
+            description: String::new(),
+            join_type: "OPEN".to_string(),
+            post_permission: None,
+            edit_permissions: None,
+            created_at: Utc::now(),
+        };
+        let new_role = resolve_role(&group, new_owner, Some(&admin_member(&group, new_owner))).unwrap();
+        let previous_role = resolve_role(&group, previous_owner, Some(&admin_member(&group, previous_owner))).unwrap();
+        assert_eq!(new_role, GroupRole::Owner);
+        assert_eq!(previous_role, GroupRole::Admin);
+        assert_eq!(resolve_role(&group, Uuid::new_v4(), None), None);
+
+        // The new owner manages the previous one, not the other way round
+        assert!(can_manage_member(new_role, previous_role, Some(GroupRole::Member)));
+        assert!(!can_manage_member(previous_role, new_role, None));
+        assert!(!can_manage_member(previous_role, previous_role, Some(GroupRole::Member)));
+        assert!(can_manage_member(previous_role, GroupRole::Moderator, Some(GroupRole::Member)));
+    }
+}
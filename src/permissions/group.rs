@@ -0,0 +1,301 @@
+// src/permissions/group.rs
+//! What each role in a group may do.
+//!
+//! The owner is whoever `groups.owner_id` names; everyone else gets their
+//! role from `group_members.user_role`. Authors can always edit and delete
+//! their own materials and comments while they are members.
+//!
+//! Who may post and who may edit is up to each group, see
+//! [`GroupSettings`].
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupRole {
+    Member,
+    Moderator,
+    Admin,
+    Owner,
+}
+
+impl GroupRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupRole::Member => "member",
+            GroupRole::Moderator => "moderator",
+            GroupRole::Admin => "admin",
+            GroupRole::Owner => "owner",
+        }
+    }
+
+    /// Parse a stored `group_members.user_role`. Ownership only comes from
+    /// `groups.owner_id`, so "owner" is not accepted here.
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "member" => Some(GroupRole::Member),
+            "moderator" => Some(GroupRole::Moderator),
+            "admin" => Some(GroupRole::Admin),
+            _ => None,
+        }
+    }
+
+    /// Role of a membership row; missing or unknown roles get the least
+    /// privilege
+    pub fn from_member_role(role: Option<&str>) -> Self {
+        role.and_then(GroupRole::parse).unwrap_or(GroupRole::Member)
+    }
+
+    pub fn can(&self, action: GroupAction, settings: &GroupSettings) -> bool {
+        let least = match action {
+            GroupAction::Post => settings.post.least_role(),
+            GroupAction::Edit => settings.edit.least_role(),
+            GroupAction::Comment => GroupRole::Member,
+            GroupAction::Delete | GroupAction::Moderate => GroupRole::Moderator,
+            GroupAction::ManageMembers => GroupRole::Admin,
+            GroupAction::ManageGroup => GroupRole::Owner,
+        };
+        *self >= least
+    }
+}
+
+/// Who a group lets post or edit, stored on `groups.post_permission` and
+/// `groups.edit_permissions`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PermissionLevel {
+    /// Group admins and the owner
+    #[default]
+    Admins,
+    /// Every member
+    Members,
+}
+
+impl PermissionLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PermissionLevel::Admins => "ADMIN",
+            PermissionLevel::Members => "MEMBER",
+        }
+    }
+
+    pub fn parse(level: &str) -> Option<Self> {
+        match level {
+            "ADMIN" => Some(PermissionLevel::Admins),
+            "MEMBER" => Some(PermissionLevel::Members),
+            _ => None,
+        }
+    }
+
+    fn least_role(&self) -> GroupRole {
+        match self {
+            PermissionLevel::Admins => GroupRole::Admin,
+            PermissionLevel::Members => GroupRole::Member,
+        }
+    }
+}
+
+/// The per-group part of the policy. Missing or unknown settings fall back to
+/// the column defaults, admins only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GroupSettings {
+    /// Adding materials and courses
+    pub post: PermissionLevel,
+    /// Changing other members' materials, and changing courses
+    pub edit: PermissionLevel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupAction {
+    /// Add materials and courses
+    Post,
+    /// Comment on materials
+    Comment,
+    /// Change other members' materials and their labels, and change
+    /// courses
+    Edit,
+    /// Remove other members' materials, and remove courses along with
+    /// everyone's materials in them
+    Delete,
+    /// Change or remove other members' comments
+    Moderate,
+    /// Handle join requests and add members
+    ManageMembers,
+    /// Change the group's settings, or delete it
+    ManageGroup,
+}
+
+impl GroupAction {
+    pub const ALL: [GroupAction; 7] = [
+        GroupAction::Post,
+        GroupAction::Comment,
+        GroupAction::Edit,
+        GroupAction::Delete,
+        GroupAction::Moderate,
+        GroupAction::ManageMembers,
+        GroupAction::ManageGroup,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupAction::Post => "post",
+            GroupAction::Comment => "comment",
+            GroupAction::Edit => "edit",
+            GroupAction::Delete => "delete",
+            GroupAction::Moderate => "moderate",
+            GroupAction::ManageMembers => "manage members",
+            GroupAction::ManageGroup => "manage the group",
+        }
+    }
+
+    /// Actions an author may take on their own content whatever their role
+    fn allowed_for_author(&self) -> bool {
+        matches!(self, GroupAction::Edit | GroupAction::Delete | GroupAction::Moderate)
+    }
+}
+
+/// Whether someone with `role` in a group (`None` for non-members) may take
+/// `action`. `is_author` is true when the action targets their own material
+/// or comment.
+pub fn is_allowed(
+    role: Option<GroupRole>,
+    action: GroupAction,
+    is_author: bool,
+    settings: &GroupSettings,
+) -> bool {
+    match role {
+        Some(role) => role.can(action, settings) || (is_author && action.allowed_for_author()),
+        None => false,
+    }
+}
+
+/// Whether `actor` may remove a member whose role is `target`, or change it
+/// to `new_role`. Members can only be managed by someone above them, and
+/// only roles below the actor's own can be handed out. The owner is never
+/// managed this way; ownership has to be transferred.
+pub fn can_manage_member(actor: GroupRole, target: GroupRole, new_role: Option<GroupRole>) -> bool {
+    // Member management does not depend on the group's settings
+    actor.can(GroupAction::ManageMembers, &GroupSettings::default())
+        && target != GroupRole::Owner
+        && actor > target
+        && new_role.is_none_or(|role| role != GroupRole::Owner && actor > role)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROLES: [GroupRole; 4] = [GroupRole::Owner, GroupRole::Admin, GroupRole::Moderator, GroupRole::Member];
+
+    fn expected(role: GroupRole, action: GroupAction) -> bool {
+        use GroupAction::*;
+        use GroupRole::*;
+        match (role, action) {
+            (_, Comment) => true,
+            (Owner, _) => true,
+            (Admin, ManageGroup) => false,
+            (Admin, _) => true,
+            (Moderator, Delete) | (Moderator, Moderate) => true,
+            (Moderator, _) => false,
+            (Member, _) => false,
+        }
+    }
+
+    #[test]
+    fn test_every_role_action_pair() {
+        let settings = GroupSettings::default();
+        for role in ROLES {
+            for action in GroupAction::ALL {
+                assert_eq!(
+                    is_allowed(Some(role), action, false, &settings),
+                    expected(role, action),
+                    "{} / {}",
+                    role.as_str(),
+                    action.as_str()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_member_level_settings_open_post_and_edit_to_everyone() {
+        let settings = GroupSettings { post: PermissionLevel::Members, edit: PermissionLevel::Members };
+        for role in ROLES {
+            for action in GroupAction::ALL {
+                let allowed = match action {
+                    GroupAction::Post | GroupAction::Edit => true,
+                    _ => expected(role, action),
+                };
+                assert_eq!(is_allowed(Some(role), action, false, &settings), allowed);
+            }
+        }
+
+        let post_only = GroupSettings { post: PermissionLevel::Members, edit: PermissionLevel::Admins };
+        assert!(is_allowed(Some(GroupRole::Member), GroupAction::Post, false, &post_only));
+        assert!(!is_allowed(Some(GroupRole::Member), GroupAction::Edit, false, &post_only));
+    }
+
+    #[test]
+    fn test_member_level_edit_does_not_allow_deleting() {
+        let settings = GroupSettings { post: PermissionLevel::Admins, edit: PermissionLevel::Members };
+        assert!(is_allowed(Some(GroupRole::Member), GroupAction::Edit, false, &settings));
+        assert!(!is_allowed(Some(GroupRole::Member), GroupAction::Delete, false, &settings));
+        assert!(is_allowed(Some(GroupRole::Moderator), GroupAction::Delete, false, &settings));
+    }
+
+    #[test]
+    fn test_non_members_can_do_nothing() {
+        let open = GroupSettings { post: PermissionLevel::Members, edit: PermissionLevel::Members };
+        for action in GroupAction::ALL {
+            assert!(!is_allowed(None, action, false, &open));
+            assert!(!is_allowed(None, action, true, &open));
+        }
+    }
+
+    #[test]
+    fn test_authors_manage_their_own_content() {
+        let settings = GroupSettings::default();
+        for action in [GroupAction::Edit, GroupAction::Delete, GroupAction::Moderate] {
+            assert!(is_allowed(Some(GroupRole::Member), action, true, &settings));
+        }
+        assert!(!is_allowed(Some(GroupRole::Member), GroupAction::Post, true, &settings));
+        assert!(!is_allowed(Some(GroupRole::Member), GroupAction::ManageMembers, true, &settings));
+        assert!(!is_allowed(Some(GroupRole::Member), GroupAction::ManageGroup, true, &settings));
+    }
+
+    #[test]
+    fn test_managing_members_needs_a_higher_role() {
+        use GroupRole::*;
+        for actor in ROLES {
+            for target in ROLES {
+                let expected = actor >= Admin && target != Owner && actor > target;
+                assert_eq!(can_manage_member(actor, target, None), expected, "{} / {}", actor.as_str(), target.as_str());
+            }
+        }
+
+        assert!(can_manage_member(Owner, Member, Some(Admin)));
+        assert!(can_manage_member(Owner, Admin, Some(Member)));
+        assert!(can_manage_member(Admin, Member, Some(Moderator)));
+        assert!(!can_manage_member(Admin, Member, Some(Admin)));
+        assert!(!can_manage_member(Admin, Admin, Some(Member)));
+        assert!(!can_manage_member(Owner, Member, Some(Owner)));
+        assert!(!can_manage_member(Moderator, Member, Some(Member)));
+    }
+
+    #[test]
+    fn test_member_roles_parse() {
+        for role in [GroupRole::Admin, GroupRole::Moderator, GroupRole::Member] {
+            assert_eq!(GroupRole::parse(role.as_str()), Some(role));
+        }
+        assert_eq!(GroupRole::parse("owner"), None);
+        assert_eq!(GroupRole::from_member_role(Some("superuser")), GroupRole::Member);
+        assert_eq!(GroupRole::from_member_role(None), GroupRole::Member);
+    }
+
+    #[test]
+    fn test_permission_level_round_trip() {
+        for level in [PermissionLevel::Admins, PermissionLevel::Members] {
+            assert_eq!(PermissionLevel::parse(level.as_str()), Some(level));
+        }
+        assert_eq!(PermissionLevel::parse("members"), None);
+    }
+}
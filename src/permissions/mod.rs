@@ -1,9 +1,53 @@
 // src/permissions/mod.rs
 //! Permissions and authorization module for StudySphere
-//! 
+//!
 //! This module handles role-based access control, permission checking,
 //! and authorization logic for different user roles.
 
-// TODO: Implement role-based permission checking
-// TODO: Implement group ownership validation
-// TODO: Implement resource access control
\ No newline at end of file
+pub mod access;
+pub mod group;
+
+pub use access::GroupPermissions;
+pub use group::{can_manage_member, is_allowed, GroupAction, GroupRole, GroupSettings, PermissionLevel};
+
+use serde::{Deserialize, Serialize};
+
+/// Platform-wide role stored on `users.role`. Site admins can use the admin
+/// API regardless of their group memberships.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlatformRole {
+    User,
+    Admin,
+}
+
+impl PlatformRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlatformRole::User => "user",
+            PlatformRole::Admin => "admin",
+        }
+    }
+
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "user" => Some(PlatformRole::User),
+            "admin" => Some(PlatformRole::Admin),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_platform_role_round_trip() {
+        for role in [PlatformRole::User, PlatformRole::Admin] {
+            assert_eq!(PlatformRole::parse(role.as_str()), Some(role));
+        }
+        assert_eq!(PlatformRole::parse("Admin"), None);
+        assert_eq!(PlatformRole::parse("superuser"), None);
+    }
+}
@@ -0,0 +1,37 @@
+// src/routes/admin.rs
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+use sqlx::PgPool;
+
+use crate::handlers::admin::{
+    list_users_handler,
+    get_user_handler,
+    disable_user_handler,
+    enable_user_handler,
+    force_password_reset_handler,
+    change_role_handler,
+    impersonate_user_handler,
+    delete_group_handler,
+    transfer_group_handler,
+    list_audit_log_handler,
+    list_auth_events_handler,
+};
+
+/// Site administration, layered with the auth and site admin middleware in
+/// `create_app`
+pub fn admin_routes() -> Router<PgPool> {
+    Router::new()
+        .route("/users", get(list_users_handler))
+        .route("/users/{user_id}", get(get_user_handler))
+        .route("/users/{user_id}/disable", post(disable_user_handler))
+        .route("/users/{user_id}/enable", post(enable_user_handler))
+        .route("/users/{user_id}/password-reset", post(force_password_reset_handler))
+        .route("/users/{user_id}/role", put(change_role_handler))
+        .route("/users/{user_id}/impersonate", post(impersonate_user_handler))
+        .route("/groups/{group}", delete(delete_group_handler))
+        .route("/groups/{group}/transfer", post(transfer_group_handler))
+        .route("/audit-log", get(list_audit_log_handler))
+        .route("/auth-events", get(list_auth_events_handler))
+}
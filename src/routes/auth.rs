@@ -1,7 +1,7 @@
 // src/routes/auth.rs
 use axum::{
     middleware,
-    routing::{get, post},
+    routing::{delete, get, post},
     Router,
 };
 use sqlx::PgPool;
@@ -11,8 +11,39 @@ use crate::{
         register_handler,
         login_handler,
         logout_handler,
+        logout_all_handler,
         refresh_token_handler,
         me_handler,
+        update_me_handler,
+        change_password_handler,
+        forgot_password_handler,
+        reset_password_handler,
+        verify_email_handler,
+        resend_verification_email_handler,
+    },
+    handlers::mfa::{
+        enroll_totp_handler,
+        confirm_totp_handler,
+        disable_totp_handler,
+        mfa_login_handler,
+    },
+    handlers::access_token::{
+        list_access_tokens_handler,
+        create_access_token_handler,
+        revoke_access_token_handler,
+    },
+    handlers::oidc::{
+        oidc_authorize_handler,
+        oidc_callback_handler,
+    },
+    handlers::session::{
+        list_sessions_handler,
+        revoke_session_handler,
+        revoke_other_sessions_handler,
+    },
+    handlers::account::{
+        export_me_handler,
+        delete_me_handler,
     },
     middleware::auth::auth_middleware,
 };
@@ -23,9 +54,27 @@ pub fn auth_routes(pool: &PgPool) -> Router<PgPool> {
         .route("/register", post(register_handler))
         .route("/login", post(login_handler))
         .route("/refresh", post(refresh_token_handler))
+        .route("/password/forgot", post(forgot_password_handler))
+        .route("/password/reset", post(reset_password_handler))
+        .route("/verify-email", post(verify_email_handler))
+        .route("/login/mfa", post(mfa_login_handler))
+        .route("/oidc/authorize", get(oidc_authorize_handler))
+        .route("/oidc/callback", post(oidc_callback_handler))
         // Protected routes (authentication required)
-        .route("/me", get(me_handler).layer(middleware::from_fn_with_state(pool.clone(),auth_middleware)))
+        .route("/me", get(me_handler).patch(update_me_handler).delete(delete_me_handler).layer(middleware::from_fn_with_state(pool.clone(),auth_middleware)))
+        .route("/me/export", get(export_me_handler).layer(middleware::from_fn_with_state(pool.clone(),auth_middleware)))
+        .route("/me/password", post(change_password_handler).layer(middleware::from_fn_with_state(pool.clone(),auth_middleware)))
 
         .route("/logout", post(logout_handler).layer(middleware::from_fn_with_state(pool.clone(),auth_middleware)))
+        .route("/verify-email/resend", post(resend_verification_email_handler).layer(middleware::from_fn_with_state(pool.clone(),auth_middleware)))
+        .route("/logout-all", post(logout_all_handler).layer(middleware::from_fn_with_state(pool.clone(),auth_middleware)))
+        .route("/mfa/totp", delete(disable_totp_handler).layer(middleware::from_fn_with_state(pool.clone(),auth_middleware)))
+        .route("/mfa/totp/enroll", post(enroll_totp_handler).layer(middleware::from_fn_with_state(pool.clone(),auth_middleware)))
+        .route("/sessions", get(list_sessions_handler).layer(middleware::from_fn_with_state(pool.clone(),auth_middleware)))
+        .route("/sessions/revoke-others", post(revoke_other_sessions_handler).layer(middleware::from_fn_with_state(pool.clone(),auth_middleware)))
+        .route("/sessions/{session_id}", delete(revoke_session_handler).layer(middleware::from_fn_with_state(pool.clone(),auth_middleware)))
+        .route("/tokens", get(list_access_tokens_handler).post(create_access_token_handler).layer(middleware::from_fn_with_state(pool.clone(),auth_middleware)))
+        .route("/tokens/{token_id}", delete(revoke_access_token_handler).layer(middleware::from_fn_with_state(pool.clone(),auth_middleware)))
+        .route("/mfa/totp/confirm", post(confirm_totp_handler).layer(middleware::from_fn_with_state(pool.clone(),auth_middleware)))
 
 }
@@ -1,6 +1,6 @@
 // src/routes/course.rs
 use axum::{
-    routing::{get, post, put},
+    routing::get,
     Router,
 };
 use sqlx::PgPool;
@@ -12,21 +12,13 @@ use crate::handlers::course::{
     update_course_handler,
     delete_course_handler,
 };
-use crate::middleware::group_admin_middleware;
 
-pub fn course_routes(pool: &PgPool) -> Router<PgPool> {
+pub fn course_routes() -> Router<PgPool> {
     Router::new()
-        .route("/groups/{group_name}/courses", get(list_courses_handler))
-        .route("/groups/{group_name}/courses", 
-            post(create_course_handler)
-        .layer(axum::middleware::from_fn_with_state(pool.clone(),group_admin_middleware))
+        .route("/groups/{group}/courses", get(list_courses_handler).post(create_course_handler))
+        .route("/groups/{group}/courses/{course}",
+            get(get_course_detail_handler)
+                .put(update_course_handler)
+                .delete(delete_course_handler)
         )
-
-        .route("/groups/{group_name}/courses/{course_name}", get(get_course_detail_handler))
-
-        .route("/groups/{group_name}/courses/{course_name}", 
-put(update_course_handler)
-        .delete(delete_course_handler)
-            .layer(axum::middleware::from_fn_with_state(pool.clone(), group_admin_middleware))
-        )
-}
\ No newline at end of file
+}
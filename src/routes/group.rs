@@ -1,18 +1,42 @@
 // src/routes/group.rs
 use axum::{
     middleware,
-    routing::{get, post},
+    routing::{get, post, put},
     Router,
 };
 use sqlx::PgPool;
 
 use crate::{
-    handlers::group::{create_group_handler, list_groups_handler},
-    middleware::auth::auth_middleware,
+    handlers::group::{
+        create_group_handler,
+        list_groups_handler,
+        get_group_handler,
+        update_group_handler,
+        delete_group_handler,
+        nominate_owner_handler,
+        get_ownership_transfer_handler,
+        cancel_ownership_transfer_handler,
+        accept_ownership_transfer_handler,
+    },
+    middleware::{auth::auth_middleware, group_admin_middleware},
 };
 
 pub fn group_routes(pool: &PgPool) -> Router<PgPool> {
     Router::new()
-    .route("/", post(create_group_handler)).layer(middleware::from_fn_with_state(pool.clone(), auth_middleware))
+    .route("/", post(create_group_handler))
+    .route("/{group}", get(get_group_handler))
+    .route("/{group}",
+        put(update_group_handler)
+        .patch(update_group_handler)
+        .delete(delete_group_handler)
+            .layer(middleware::from_fn_with_state(pool.clone(), group_admin_middleware))
+    )
+    .route("/{group}/transfer",
+        post(nominate_owner_handler)
+        .get(get_ownership_transfer_handler)
+        .delete(cancel_ownership_transfer_handler)
+    )
+    .route("/{group}/transfer/accept", post(accept_ownership_transfer_handler))
+    .layer(middleware::from_fn_with_state(pool.clone(), auth_middleware))
     .merge(Router::new().route("/", get(list_groups_handler)))
-}
\ No newline at end of file
+}
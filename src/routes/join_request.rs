@@ -1,6 +1,6 @@
 // src/routes/join_request.rs
 use axum::{
-    routing::{get, post},
+    routing::{delete, get, post},
     Router,
 };
 use sqlx::PgPool;
@@ -9,10 +9,12 @@ use crate::handlers::join_request::{
     list_join_requests_handler,
     create_join_request_handler,
     respond_to_join_request_handler,
+    decline_invitation_handler,
 };
 
 pub fn join_request_routes() -> Router<PgPool> {
     Router::new()
-        .route("/groups/{group_name}/join-requests", get(list_join_requests_handler).post(create_join_request_handler))
-        .route("/groups/{group_name}/join-requests/{join_request_id}", post(respond_to_join_request_handler))
-}
\ No newline at end of file
+        .route("/groups/{group}/join-requests", get(list_join_requests_handler).post(create_join_request_handler))
+        .route("/groups/{group}/join-requests/{user_id}", post(respond_to_join_request_handler))
+        .route("/groups/{group}/invitation", delete(decline_invitation_handler))
+}
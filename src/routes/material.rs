@@ -24,12 +24,12 @@ pub fn material_routes() -> Router<PgPool> {
     Router::new()
         // Material routes
         .route("/materials", post(create_material_handler))
-        .route("/groups/{group_name}/courses/{course_name}/materials", get(list_materials_by_course_handler))
+        .route("/groups/{group}/courses/{course}/materials", get(list_materials_by_course_handler))
         .route("/materials/{id}", get(get_material_handler))
         .route("/materials/{id}", put(update_material_handler))
         .route("/materials/{id}", delete(delete_material_handler))
         // MaterialLabel routes
         .route("/materials/{material_id}/labels", post(create_material_label_handler))
         .route("/materials/{material_id}/labels", get(list_material_labels_handler))
-        .route("/materials/{material_id}/labels/{label_id}", delete(delete_material_label_handler))
+        .route("/materials/{material_id}/labels/{label_name}", delete(delete_material_label_handler))
 }
@@ -5,3 +5,4 @@ pub mod group_member;
 pub mod course;
 pub mod material;
 pub mod comment;
+pub mod admin;
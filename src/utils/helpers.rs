@@ -2,13 +2,16 @@ use std::collections::HashMap;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
 
 /// Pagination utilities for API responses
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PaginationParams {
     pub page: u32,
     pub page_size: u32,
-    pub offset: u32,
+    /// Row offset for SQL; `i64` so no page number can overflow it
+    pub offset: i64,
 }
 
 impl Default for PaginationParams {
@@ -25,7 +28,7 @@ impl PaginationParams {
     pub fn new(page: Option<u32>, page_size: Option<u32>) -> Self {
         let page = page.unwrap_or(1).max(1);
         let page_size = page_size.unwrap_or(20).clamp(1, 100);
-        let offset = (page - 1) * page_size;
+        let offset = (i64::from(page) - 1) * i64::from(page_size);
         
         Self {
             page,
@@ -74,8 +77,8 @@ impl QueryUtils {
     pub fn parse_sort_params(sort_by: Option<String>) -> SortParams {
         let sort_by = sort_by.unwrap_or_else(|| "created_at".to_string());
         
-        let (field, direction) = if sort_by.starts_with('-') {
-            (sort_by[1..].to_string(), SortDirection::Desc)
+        let (field, direction) = if let Some(field) = sort_by.strip_prefix('-') {
+            (field.to_string(), SortDirection::Desc)
         } else {
             (sort_by, SortDirection::Asc)
         };
@@ -137,16 +140,16 @@ impl DateUtils {
         start: Option<DateTime<Utc>>,
         end: Option<DateTime<Utc>>,
     ) -> bool {
-        if let Some(start) = start {
-            if date < start {
-                return false;
-            }
+        if let Some(start) = start
+            && date < start
+        {
+            return false;
         }
         
-        if let Some(end) = end {
-            if date > end {
-                return false;
-            }
+        if let Some(end) = end
+            && date > end
+        {
+            return false;
         }
         
         true
@@ -229,6 +232,23 @@ impl StringUtils {
     }
 }
 
+/// Opaque token utilities for emailed links and API keys
+pub struct TokenUtils;
+
+impl TokenUtils {
+    /// Generate a random 256-bit token, hex encoded
+    pub fn generate_token() -> String {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        hex::encode(bytes)
+    }
+    
+    /// Hash a token for storage (SHA-256, hex encoded)
+    pub fn hash_token(token: &str) -> String {
+        hex::encode(Sha256::digest(token.as_bytes()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -250,6 +270,10 @@ mod tests {
         let clamped = PaginationParams::new(Some(0), Some(500));
         assert_eq!(clamped.page, 1);
         assert_eq!(clamped.page_size, 100);
+
+        // Test the largest page does not overflow
+        let last = PaginationParams::new(Some(u32::MAX), Some(100));
+        assert_eq!(last.offset, (i64::from(u32::MAX) - 1) * 100);
     }
     
     #[test]
@@ -363,4 +387,20 @@ mod tests {
         assert_eq!(StringUtils::clean_whitespace("normal text"), "normal text");
         assert_eq!(StringUtils::clean_whitespace(""), "");
     }
+    
+    #[test]
+    fn test_token_utils() {
+        let token = TokenUtils::generate_token();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, TokenUtils::generate_token());
+        
+        let hash = TokenUtils::hash_token(&token);
+        assert_eq!(hash.len(), 64);
+        assert_ne!(hash, token);
+        assert_eq!(hash, TokenUtils::hash_token(&token));
+        assert_eq!(
+            TokenUtils::hash_token("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
 }
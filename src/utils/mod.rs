@@ -2,10 +2,16 @@ pub mod jwt;
 pub mod validation;
 pub mod helpers;
 
-pub use jwt::{JwtManager, Claims, TokenType};
-pub use validation::{PasswordUtils, EmailUtils, UsernameUtils, TextUtils, validate_password_match};
+pub use jwt::{
+    JwtManager, Claims, ActorClaim, TokenType, shared_jwt_manager,
+    ACCESS_TOKEN_TTL_MINUTES, REFRESH_TOKEN_TTL_MINUTES, MFA_PENDING_TOKEN_TTL_MINUTES,
+    IMPERSONATION_TOKEN_TTL_MINUTES,
+};
+pub use validation::{
+    PasswordUtils, EmailUtils, UsernameUtils, TextUtils, validate_password_match, DJANGO_PBKDF2_PREFIX,
+};
 pub use helpers::{
     PaginationParams, PaginatedResponse, PaginationInfo,
     QueryUtils, SearchParams, SortParams, SortDirection,
-    DateUtils, UuidUtils, ErrorUtils, StringUtils,
+    DateUtils, UuidUtils, ErrorUtils, StringUtils, TokenUtils,
 };
\ No newline at end of file
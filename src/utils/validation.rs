@@ -1,17 +1,31 @@
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use std::sync::OnceLock;
+
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
 use argon2::password_hash::{rand_core::OsRng, SaltString};
 use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
 use validator::ValidationError;
 use regex::Regex;
 
+use super::helpers::TokenUtils;
+
+/// Prefix of password hashes imported from the Django deployment
+pub const DJANGO_PBKDF2_PREFIX: &str = "pbkdf2_sha256$";
+
+/// Prefix of the stored hash of accounts that never had a password, such as
+/// those created on first external sign-in. Nothing verifies against it.
+pub const UNUSABLE_PASSWORD_PREFIX: &str = "!";
+
 /// Password utility functions for hashing and verification
 pub struct PasswordUtils;
 
 impl PasswordUtils {
-    /// Hash a password using Argon2
+    /// Hash a password using Argon2id with the configured parameters
     pub fn hash_password(password: &str) -> Result<String> {
         let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, Self::argon2_params()?.clone());
         
         let password_hash = argon2
             .hash_password(password.as_bytes(), &salt)
@@ -19,12 +33,32 @@ impl PasswordUtils {
         
         Ok(password_hash.to_string())
     }
+
+    /// Hash for an account without a password; the password reset flow can
+    /// set a real one
+    pub fn unusable_password() -> String {
+        format!("{}{}", UNUSABLE_PASSWORD_PREFIX, TokenUtils::generate_token())
+    }
+
+    /// Whether a password was ever set for the hash's account
+    pub fn has_usable_password(hash: &str) -> bool {
+        !hash.starts_with(UNUSABLE_PASSWORD_PREFIX)
+    }
     
-    /// Verify a password against a hash
+    /// Verify a password against a hash. Besides Argon2 this accepts the
+    /// Django `pbkdf2_sha256$<iterations>$<salt>$<hash>` format.
     pub fn verify_password(password: &str, hash: &str) -> Result<bool> {
+        if !Self::has_usable_password(hash) {
+            return Ok(false);
+        }
+        if let Some(django_hash) = hash.strip_prefix(DJANGO_PBKDF2_PREFIX) {
+            return verify_django_pbkdf2(password, django_hash);
+        }
+
         let parsed_hash = PasswordHash::new(hash)
             .map_err(|e| anyhow::anyhow!("Invalid hash format: {}", e))?;
         
+        // The parameters come from the hash itself
         let argon2 = Argon2::default();
         
         match argon2.verify_password(password.as_bytes(), &parsed_hash) {
@@ -32,6 +66,49 @@ impl PasswordUtils {
             Err(_) => Ok(false),
         }
     }
+
+    /// Whether a hash should be replaced after the next successful login:
+    /// a legacy format, another Argon2 variant, or weaker parameters than
+    /// the configured ones
+    pub fn needs_rehash(hash: &str) -> bool {
+        let Ok(parsed_hash) = PasswordHash::new(hash) else {
+            return true;
+        };
+        if parsed_hash.algorithm != Algorithm::Argon2id.ident()
+            || parsed_hash.version != Some(Version::V0x13.into())
+        {
+            return true;
+        }
+        let (Ok(params), Ok(target)) = (Params::try_from(&parsed_hash), Self::argon2_params()) else {
+            return true;
+        };
+        params.m_cost() < target.m_cost() || params.t_cost() < target.t_cost() || params.p_cost() < target.p_cost()
+    }
+
+    /// Argon2id cost from `ARGON2_MEMORY_KIB`, `ARGON2_ITERATIONS` and
+    /// `ARGON2_PARALLELISM`, defaulting to the OWASP minimum that
+    /// `Argon2::default()` uses
+    pub fn argon2_params() -> Result<&'static Params> {
+        static PARAMS: OnceLock<Params> = OnceLock::new();
+
+        if let Some(params) = PARAMS.get() {
+            return Ok(params);
+        }
+        let env = |name: &str, default: u32| -> Result<u32> {
+            match std::env::var(name) {
+                Ok(value) => value.parse().map_err(|_| anyhow::anyhow!("{} must be a positive integer", name)),
+                Err(_) => Ok(default),
+            }
+        };
+        let params = Params::new(
+            env("ARGON2_MEMORY_KIB", Params::DEFAULT_M_COST)?,
+            env("ARGON2_ITERATIONS", Params::DEFAULT_T_COST)?,
+            env("ARGON2_PARALLELISM", Params::DEFAULT_P_COST)?,
+            None,
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid Argon2 parameters: {}", e))?;
+        Ok(PARAMS.get_or_init(|| params))
+    }
     
     /// Check password strength
     pub fn validate_password_strength(password: &str) -> Result<(), Vec<String>> {
@@ -69,6 +146,42 @@ impl PasswordUtils {
     }
 }
 
+/// Check a password against the part of a Django hash after the algorithm
+fn verify_django_pbkdf2(password: &str, django_hash: &str) -> Result<bool> {
+    let mut parts = django_hash.splitn(3, '$');
+    let (Some(iterations), Some(salt), Some(expected)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(anyhow::anyhow!("Invalid hash format: malformed pbkdf2_sha256 hash"));
+    };
+    let iterations: u32 = iterations
+        .parse()
+        .ok()
+        .filter(|i| *i > 0)
+        .ok_or_else(|| anyhow::anyhow!("Invalid hash format: bad pbkdf2_sha256 iteration count"))?;
+
+    let derived = STANDARD.encode(pbkdf2_sha256(password.as_bytes(), salt.as_bytes(), iterations));
+    // Compare digests so the time taken says nothing about the stored hash
+    Ok(Sha256::digest(derived.as_bytes()) == Sha256::digest(expected.as_bytes()))
+}
+
+/// PBKDF2-HMAC-SHA256 with a single output block, as Django uses it
+fn pbkdf2_sha256(password: &[u8], salt: &[u8], iterations: u32) -> [u8; 32] {
+    let prf = Hmac::<Sha256>::new_from_slice(password).expect("HMAC accepts keys of any length");
+
+    let mut mac = prf.clone();
+    mac.update(salt);
+    mac.update(&1u32.to_be_bytes());
+    let mut block = mac.finalize().into_bytes();
+    let mut output = block;
+
+    for _ in 1..iterations {
+        let mut mac = prf.clone();
+        mac.update(&block);
+        block = mac.finalize().into_bytes();
+        output.iter_mut().zip(block.iter()).for_each(|(out, b)| *out ^= b);
+    }
+    output.into()
+}
+
 /// Email validation utilities
 pub struct EmailUtils;
 
@@ -165,13 +278,56 @@ mod tests {
         let hash = PasswordUtils::hash_password(password).unwrap();
         
         assert_ne!(hash, password);
-        assert!(hash.len() > 0);
+        assert!(!hash.is_empty());
         
         // Verify the password
         assert!(PasswordUtils::verify_password(password, &hash).unwrap());
         assert!(!PasswordUtils::verify_password("wrong_password", &hash).unwrap());
     }
     
+    #[test]
+    fn test_pbkdf2_sha256_rfc_7914_vector() {
+        let derived = pbkdf2_sha256(b"passwd", b"salt", 1);
+        assert_eq!(hex::encode(derived), "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc");
+    }
+
+    #[test]
+    fn test_django_hash_verification() {
+        let hash = "pbkdf2_sha256$1000$Qx3nVbTn1kzM$YJjhxBlbBwh9vwhEbq7MREbA7E5YJI1NhMgTaT3yxcI=";
+        assert!(PasswordUtils::verify_password("StrongPass123!", hash).unwrap());
+        assert!(!PasswordUtils::verify_password("wrong_password", hash).unwrap());
+        assert!(PasswordUtils::verify_password("x", "pbkdf2_sha256$abc$salt$hash").is_err());
+        assert!(PasswordUtils::needs_rehash(hash));
+    }
+
+    #[test]
+    fn test_unusable_password_never_verifies() {
+        let hash = PasswordUtils::unusable_password();
+        assert!(!PasswordUtils::has_usable_password(&hash));
+        assert!(!PasswordUtils::verify_password(&hash, &hash).unwrap());
+        assert!(!PasswordUtils::verify_password(&hash[1..], &hash).unwrap());
+        assert!(PasswordUtils::has_usable_password(&PasswordUtils::hash_password("StrongPass123!").unwrap()));
+    }
+
+    #[test]
+    fn test_needs_rehash_for_weaker_argon2() {
+        let current = PasswordUtils::hash_password("StrongPass123!").unwrap();
+        assert!(!PasswordUtils::needs_rehash(&current));
+
+        let salt = SaltString::generate(&mut OsRng);
+        let weak = Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::new(4096, 1, 1, None).unwrap())
+            .hash_password(b"StrongPass123!", &salt)
+            .unwrap()
+            .to_string();
+        assert!(PasswordUtils::needs_rehash(&weak));
+
+        let argon2i = Argon2::new(Algorithm::Argon2i, Version::V0x13, Params::default())
+            .hash_password(b"StrongPass123!", &salt)
+            .unwrap()
+            .to_string();
+        assert!(PasswordUtils::needs_rehash(&argon2i));
+    }
+
     #[test]
     fn test_password_strength_validation() {
         // Valid password